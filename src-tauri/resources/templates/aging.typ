@@ -0,0 +1,46 @@
+#set page(width: 297mm, height: 210mm)
+
+#set text(font: "IBM Plex Sans Hebrew")
+#set table(
+  stroke: none,
+  gutter: 0.05em,
+  fill: (x, y) => if y == 0 { rgb(239, 240, 243) },
+)
+
+#let asOfDate = "{as_of_date}"
+#let basis = "{basis}"
+#let total = "{total}"
+#let rows = (
+  {rows}
+)
+
+#let aging-table(rows) = table(
+  columns: {columns},
+  align: right,
+  stroke: (x, y) => rgb(231, 227, 228),
+  inset: 8pt,
+  table.header([المجموع], {header} [الهاتف], [العميل]),
+  ..rows.flatten(),
+  [#total], {totals} [], [المجموع],
+)
+
+#let footer() = block[
+  #line(length: 100%)
+  #align(center)[تاريخ الطباعة: #datetime.today().display("[day]/[month]/[year]")]
+]
+
+#align(center)[
+  #block(inset: 10pt)[= أعمار الديون]
+  #box(height: 2mm)
+]
+
+#align(end)[
+  #text(size: 10pt)[بتاريخ: #asOfDate] \
+  #text(size: 10pt)[حسب: #basis]
+]
+
+#line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+
+#aging-table(rows)
+
+#footer()
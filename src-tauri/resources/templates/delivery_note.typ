@@ -0,0 +1,103 @@
+#set page(width: 210mm, height: 297mm)
+
+#set text(font: "IBM Plex Sans Hebrew")
+#set table(
+  stroke: none,
+  gutter: 0.05em,
+  fill: (x, y) => if y == 0 { rgb(239, 240, 243) },
+)
+
+#let company_name = "{company_name}"
+#let phone = "{phone}"
+#let address = "{address}"
+#let deliveryNoteNumber = {delivery_note_number}
+#let invoiceNumber = {invoice_number}
+#let deliveryDate = "{date}"
+#let notes = {notes}
+#let customerName = {customer_name}
+#let customerPhone = {customer_phone}
+#let deliveryAddress = {delivery_address}
+#let rows = (
+{rows}
+)
+#let custom_fields = (
+{custom_fields}
+)
+
+#let items-table(rows) = table(
+  columns: (1fr, 1fr, 1fr, 2fr),
+  align: right,
+  stroke: (x, y) => rgb(231, 227, 228),
+  inset: 8pt,
+  table.header(
+    [المتبقي],
+    [الكمية المسلمة],
+    [الكمية المطلوبة],
+    [المنتج],
+  ),
+  ..rows.flatten()
+)
+
+#let footer() = block[
+  #line(length: 100%)
+  #grid(
+    columns: (1fr, 1fr),
+    align(center)[توقيع المستلم],
+    align(center)[توقيع المسلم],
+  )
+  #box(height: 15mm)
+  #align(center)[تاريخ الطباعة: #datetime.today().display("[day]/[month]/[year]")]
+]
+
+#align(center)[
+  #block(inset: 10pt)[= وصل تسليم رقم #deliveryNoteNumber]
+  #text(size: 10pt)[عن الفاتورة رقم #invoiceNumber]
+  #box(height: 2mm)
+]
+
+#grid(
+  columns: (1fr, 1fr),
+  gutter: 50pt,
+  box()[
+    #align(end)[
+      معلومات العميل
+    ]
+    #line(
+      length: 100%,
+      stroke: 0.5pt + rgb(231, 227, 228)
+    )
+    #align(end)[
+      #text(size: 10pt)[الاسم: #customerName] \
+      #text(size: 10pt)[الهاتف: #customerPhone] \
+      #text(size: 10pt)[عنوان التسليم: #deliveryAddress] \
+      #for (label, value) in custom_fields [
+        #text(size: 10pt)[#label: #value] \
+      ]
+    ]
+  ],
+  box()[
+    #align(end)[
+      تفاصيل الشركة
+    ]
+    #line(
+      length: 100%,
+      stroke: 0.5pt + rgb(231, 227, 228)
+    )
+    #align(end)[
+      #text(size: 10pt)[الاسم: #company_name] \
+      #text(size: 10pt)[الهاتف: #phone] \
+      #text(size: 10pt)[العنوان: #address] \
+      #text(size: 10pt)[تاريخ التسليم: #deliveryDate] \
+    ]
+  ],
+)
+
+#line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+
+#items-table(rows)
+
+#if notes != "" [
+  #text(size: 10pt)[ملاحظات: #notes]
+]
+
+#footer()
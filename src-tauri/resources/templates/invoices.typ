@@ -12,7 +12,12 @@
 #let address = "{address}"
 #let fromDate = "{from_date}"
 #let toDate = "{to_date}"
-#let total = {total}
+#let total = "{total}"
+#let subtotal = "{subtotal}"
+#let taxTotal = "{tax_total}"
+#let taxes = (
+{taxes}
+)
 #let rows = (
 {rows}  
 )
@@ -26,13 +31,15 @@
 ]
 
 #let invoices-table(rows) = table(
-  columns: (1fr, 1fr, 1fr, 1fr, 1fr),
-  align: (right, right, right, right, right),
+  columns: (1fr, 1fr, 1fr, 1fr, 1fr, 1fr, 1fr),
+  align: (right, right, right, right, right, right, right),
   stroke: (x, y) => rgb(231, 227, 228),
   inset: 10pt,
   table.header(
     [التسديد],
     [قيمة الفاتورة],
+    [الضريبة],
+    [دون ضريبة],
     [الهاتف],
     [العنوان],
     [اسم العميل],
@@ -93,9 +100,27 @@
 
 #invoices-table(rows)
 
-#block(inset: 10pt, width: 80mm)[
-  *الإجمالي:* #h(1fr) #total
-  #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
-]
+#let taxes-table(taxes) = table(
+  columns: (1fr, 1fr, 1fr),
+  align: right,
+  stroke: (x, y) => rgb(231, 227, 228),
+  inset: 6pt,
+  table.header([مبلغ الضريبة], [الوعاء الضريبي], [النسبة]),
+  ..taxes.flatten()
+)
+
+#grid(
+  columns: (1fr, 1fr),
+  gutter: 20pt,
+  block(inset: 10pt)[
+    *دون ضريبة:* #h(1fr) #subtotal
+    #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+    *الضريبة:* #h(1fr) #taxTotal
+    #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+    *الإجمالي:* #h(1fr) #total
+    #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+  ],
+  if taxes.len() > 0 { taxes-table(taxes) },
+)
 
 #footer()
@@ -0,0 +1,138 @@
+#set page(width: 210mm, height: 297mm)
+
+#set text(font: "IBM Plex Sans Hebrew")
+#set table(
+  stroke: none,
+  gutter: 0.05em,
+  fill: (x, y) => if y == 0 { rgb(239, 240, 243) },
+)
+
+#let company_name = "{company_name}"
+#let phone = "{phone}"
+#let address = "{address}"
+#let quoteNumber = {quote_number}
+#let quoteDate = "{date}"
+#let validUntil = "{valid_until}"
+#let notes = {notes}
+#let customerName = {customer_name}
+#let customerPhone = {customer_phone}
+#let customerAddress = {customer_address}
+#let grossTotal = "{gross_total}"
+#let discount = "{discount}"
+#let subtotal = "{subtotal}"
+#let taxTotal = "{tax_total}"
+#let total = "{total}"
+#let rows = (
+{rows}
+)
+#let taxes = (
+{taxes}
+)
+#let custom_fields = (
+{custom_fields}
+)
+
+#let items-table(rows) = table(
+  columns: (1fr, 0.7fr, 1fr, 0.8fr, 0.7fr, 1fr, 2fr),
+  align: right,
+  stroke: (x, y) => rgb(231, 227, 228),
+  inset: 8pt,
+  table.header(
+    [المبلغ مع الضريبة],
+    [الضريبة %],
+    [المبلغ دون ضريبة],
+    [الخصم],
+    [الكمية],
+    [سعر الوحدة],
+    [المنتج],
+  ),
+  ..rows.flatten()
+)
+
+#let taxes-table(taxes) = table(
+  columns: (1fr, 1fr, 1fr),
+  align: right,
+  stroke: (x, y) => rgb(231, 227, 228),
+  inset: 6pt,
+  table.header([مبلغ الضريبة], [الوعاء الضريبي], [النسبة]),
+  ..taxes.flatten()
+)
+
+#let footer() = block[
+  #line(length: 100%)
+  #align(center)[شكراً لتعاملكم معنا] \
+  #align(center)[تاريخ الطباعة: #datetime.today().display("[day]/[month]/[year]")]
+]
+
+#align(center)[
+  #block(inset: 10pt)[= عرض سعر رقم #quoteNumber]
+  #box(height: 2mm)
+]
+
+#grid(
+  columns: (1fr, 1fr),
+  gutter: 50pt,
+  box()[
+    #align(end)[
+      معلومات العميل
+    ]
+    #line(
+      length: 100%,
+      stroke: 0.5pt + rgb(231, 227, 228)
+    )
+    #align(end)[
+      #text(size: 10pt)[الاسم: #customerName] \
+      #text(size: 10pt)[الهاتف: #customerPhone] \
+      #text(size: 10pt)[العنوان: #customerAddress] \
+      #for (label, value) in custom_fields [
+        #text(size: 10pt)[#label: #value] \
+      ]
+    ]
+  ],
+  box()[
+    #align(end)[
+      تفاصيل الشركة
+    ]
+    #line(
+      length: 100%,
+      stroke: 0.5pt + rgb(231, 227, 228)
+    )
+    #align(end)[
+      #text(size: 10pt)[الاسم: #company_name] \
+      #text(size: 10pt)[الهاتف: #phone] \
+      #text(size: 10pt)[العنوان: #address] \
+      #text(size: 10pt)[تاريخ العرض: #quoteDate] \
+      #text(size: 10pt)[صالح حتى: #validUntil] \
+    ]
+  ],
+)
+
+#line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+
+#items-table(rows)
+
+#grid(
+  columns: (1fr, 1fr),
+  gutter: 20pt,
+  block(inset: 10pt)[
+    #if discount != "" [
+      *المجموع قبل الخصم:* #h(1fr) #grossTotal
+      #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+      *الخصم:* #h(1fr) #discount
+      #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+    ]
+    *المجموع دون ضريبة:* #h(1fr) #subtotal
+    #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+    *مجموع الضريبة:* #h(1fr) #taxTotal
+    #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+    *المجموع مع الضريبة:* #h(1fr) #total
+    #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
+  ],
+  if taxes.len() > 0 { taxes-table(taxes) },
+)
+
+#if notes != "" [
+  #text(size: 10pt)[ملاحظات: #notes]
+]
+
+#footer()
@@ -14,7 +14,14 @@
 #let toDate = "{to_date}"
 #let total_invoices = "{total_invoices}"
 #let total_payments = "{total_payments}"
+#let total_credits = "{total_credits}"
 #let remaining_total = "{remaining_total}"
+#let opening_balance = "{opening_balance}"
+#let closing_balance = "{closing_balance}"
+// ("label", "value") pairs of the customer's custom fields
+#let custom_fields = (
+  {custom_fields}
+)
 #let rows = (
   {rows}
 )
@@ -28,7 +35,7 @@
 ]
 
 #let invoices-table(rows) = table(
-  columns: (1fr, 1fr, 1fr, 1fr),
+  columns: (1fr, 1fr, 1fr, 1fr, 1fr),
   align: (right, right, right, right, right),
   stroke: (x, y) => if y == 0 {
     rgb(231, 227, 228)
@@ -36,7 +43,7 @@
     rgb(231, 227, 228)
   },
   inset: 10pt,
-  table.header([المبلغ], [نوع المعاملة], [التاريخ], []),
+  table.header([الرصيد], [المبلغ], [نوع المعاملة], [التاريخ], []),
 
   ..rows.flatten(),
 )
@@ -85,6 +92,9 @@
       #text(size: 10pt)[الاسم: #name] \
       #text(size: 10pt)[الهاتف: #phone] \
       #text(size: 10pt)[العنوان: #address] \
+      #for (label, value) in custom_fields [
+        #text(size: 10pt)[#label: #value] \
+      ]
 
     ]
   ],
@@ -92,6 +102,17 @@
 
 #line(length: 100%, stroke: 0.5pt + rgb(231, 227, 228))
 
+#block(
+  inset: 5pt,
+  width: 80mm,
+)[
+  الرصيد الافتتاحي : #h(1fr) #opening_balance
+  #line(
+    length: 100%,
+    stroke: 0.5pt + rgb(231, 227, 228),
+  )
+]
+
 #invoices-table(rows)
 
 #block(
@@ -116,6 +137,17 @@
   )
 ]
 
+#block(
+  inset: 5pt,
+  width: 80mm,
+)[
+  مجموع الإشعارات الدائنة : #h(1fr) #total_credits
+  #line(
+    length: 100%,
+    stroke: 0.5pt + rgb(231, 227, 228),
+  )
+]
+
 #block(
   inset: 5pt,
   width: 80mm,
@@ -127,5 +159,16 @@
   )
 ]
 
+#block(
+  inset: 5pt,
+  width: 80mm,
+)[
+  الرصيد الختامي : #h(1fr) #closing_balance
+  #line(
+    length: 100%,
+    stroke: 0.5pt + rgb(231, 227, 228),
+  )
+]
+
 
 #footer()
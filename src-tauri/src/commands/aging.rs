@@ -0,0 +1,355 @@
+use crate::db::get_db;
+use crate::models::{AgingBucket, AgingReport, AgingRow, OverdueInvoice};
+use chrono::NaiveDate;
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+
+//
+// ==================== Payment allocation ====================
+//
+
+/// An invoice with what is still owed on it after payments are allocated.
+pub(crate) struct OpenInvoice {
+    pub id: i32,
+    pub customer_id: i32,
+    pub date: String,
+    pub due_date: String,
+    pub outstanding: f64,
+}
+
+/// Allocates the payments made up to `as_of_date` to the invoices issued up
+/// to that date. A payment linked to an invoice settles that invoice first;
+/// the rest (and unlinked payments) settles the oldest invoices of the
+/// customer. Credit notes count as payments linked to their invoice.
+/// Returns the invoices still open and, per customer, any credit left once
+/// every invoice is paid, in the base currency.
+pub(crate) fn allocate_payments(
+    conn: &Connection,
+    as_of_date: &str,
+) -> Result<(Vec<OpenInvoice>, HashMap<i32, f64>), String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT i.id, i.customer_id, i.date, IFNULL(i.due_date, i.date), i.total * i.exchange_rate
+            FROM invoices i
+            JOIN customers c ON c.id = i.customer_id
+            WHERE i.deleted_at IS NULL AND i.state <> 'cancelled' AND c.deleted_at IS NULL AND i.date <= ?1
+            ORDER BY i.customer_id, i.date, i.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let mut invoices: Vec<OpenInvoice> = stmt
+        .query_map([as_of_date], |row| {
+            Ok(OpenInvoice {
+                id: row.get(0)?,
+                customer_id: row.get(1)?,
+                date: row.get(2)?,
+                due_date: row.get(3)?,
+                outstanding: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT customer_id, invoice_id, amount FROM (
+              SELECT p.customer_id, p.invoice_id, p.amount * p.exchange_rate AS amount, p.date, p.created_at
+              FROM payments p
+              JOIN customers c ON c.id = p.customer_id
+              WHERE p.deleted_at IS NULL AND c.deleted_at IS NULL AND p.date <= ?1
+              UNION ALL
+              SELECT cn.customer_id, cn.invoice_id, cn.total * cn.exchange_rate, cn.date, cn.created_at
+              FROM live_credit_notes cn
+              JOIN customers c ON c.id = cn.customer_id
+              WHERE c.deleted_at IS NULL AND cn.date <= ?1
+            )
+            ORDER BY date, created_at
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let payments: Vec<(i32, Option<i32>, f64)> = stmt
+        .query_map([as_of_date], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    // Linked payments first, the remainder goes to a per-customer pool
+    let mut pool: HashMap<i32, f64> = HashMap::new();
+    for (customer_id, invoice_id, amount) in payments {
+        let mut left = amount;
+        if let Some(inv) = invoice_id.and_then(|id| {
+            invoices
+                .iter_mut()
+                .find(|i| i.id == id && i.customer_id == customer_id)
+        }) {
+            let applied = left.min(inv.outstanding).max(0.0);
+            inv.outstanding -= applied;
+            left -= applied;
+        }
+        *pool.entry(customer_id).or_insert(0.0) += left;
+    }
+
+    // Oldest invoices first
+    for inv in invoices.iter_mut() {
+        if let Some(left) = pool.get_mut(&inv.customer_id) {
+            let applied = left.min(inv.outstanding).max(0.0);
+            inv.outstanding -= applied;
+            *left -= applied;
+        }
+    }
+
+    invoices.retain(|i| i.outstanding > 0.005);
+    pool.retain(|_, left| *left > 0.005);
+    Ok((invoices, pool))
+}
+
+/// Days between an invoice date ("YYYY-MM-DD…") and `as_of`.
+pub(crate) fn days_between(date: &str, as_of: NaiveDate) -> i64 {
+    date.get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .map(|d| (as_of - d).num_days())
+        .unwrap_or(0)
+}
+
+//
+// ==================== Aging report ====================
+//
+
+const DEFAULT_BUCKET_EDGES: &[i64] = &[30, 60, 90];
+
+/// Buckets for edges `[30, 60, 90]`: current, 1-30, 31-60, 61-90, 90+.
+fn aging_buckets(edges: &[i64]) -> Vec<AgingBucket> {
+    let mut buckets = vec![AgingBucket {
+        label: "current".to_string(),
+        from_days: 0,
+        to_days: Some(0),
+    }];
+    let mut from = 1;
+    for edge in edges {
+        buckets.push(AgingBucket {
+            label: format!("{}-{}", from, edge),
+            from_days: from,
+            to_days: Some(*edge),
+        });
+        from = edge + 1;
+    }
+    buckets.push(AgingBucket {
+        label: format!("{}+", from - 1),
+        from_days: from,
+        to_days: None,
+    });
+    buckets
+}
+
+fn bucket_index(buckets: &[AgingBucket], days: i64) -> usize {
+    buckets
+        .iter()
+        .position(|b| b.to_days.map_or(true, |to| days <= to))
+        .unwrap_or(buckets.len() - 1)
+}
+
+/// `basis` is "invoice_date" (the default) or "due_date"; invoices without
+/// a due date are aged from their date either way.
+pub(crate) fn build_aging_report(
+    conn: &Connection,
+    as_of_date: Option<String>,
+    bucket_edges: Option<Vec<i64>>,
+    basis: Option<String>,
+) -> Result<AgingReport, String> {
+    let basis = basis.unwrap_or_else(|| "invoice_date".to_string());
+    if !matches!(basis.as_str(), "invoice_date" | "due_date") {
+        return Err(format!("أساس التعمير غير معروف: {}", basis));
+    }
+    let as_of_date =
+        as_of_date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let as_of = NaiveDate::parse_from_str(&as_of_date, "%Y-%m-%d")
+        .map_err(|_| format!("التاريخ غير صالح: {}", as_of_date))?;
+
+    let edges = bucket_edges.unwrap_or_else(|| DEFAULT_BUCKET_EDGES.to_vec());
+    if edges.first().is_some_and(|e| *e < 1) || edges.windows(2).any(|w| w[0] >= w[1]) {
+        return Err("يجب أن تكون حدود الفترات أعداداً موجبة ومتزايدة".into());
+    }
+    let buckets = aging_buckets(&edges);
+
+    let (invoices, credits) = allocate_payments(conn, &as_of_date)?;
+
+    let mut amounts: HashMap<i32, Vec<f64>> = HashMap::new();
+    for inv in &invoices {
+        let row = amounts
+            .entry(inv.customer_id)
+            .or_insert_with(|| vec![0.0; buckets.len()]);
+        // Not yet due counts as current on the due date basis
+        let aged_from = if basis == "due_date" {
+            &inv.due_date
+        } else {
+            &inv.date
+        };
+        row[bucket_index(&buckets, days_between(aged_from, as_of))] += inv.outstanding;
+    }
+    // Unallocated credit is shown against the current bucket so that each
+    // row adds up to the customer's balance
+    for (customer_id, credit) in &credits {
+        amounts
+            .entry(*customer_id)
+            .or_insert_with(|| vec![0.0; buckets.len()])[0] -= credit;
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, phone FROM customers WHERE deleted_at IS NULL")
+        .map_err(|e| e.to_string())?;
+    let customers: HashMap<i32, (String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| e.to_string())?
+        .filter_map(|x| x.ok())
+        .collect();
+
+    let mut rows: Vec<AgingRow> = amounts
+        .into_iter()
+        .filter_map(|(customer_id, amounts)| {
+            let (name, phone) = customers.get(&customer_id)?.clone();
+            Some(AgingRow {
+                customer_id,
+                customer_name: name,
+                customer_phone: phone,
+                total: amounts.iter().sum(),
+                amounts,
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| b.total.total_cmp(&a.total));
+
+    let totals: Vec<f64> = (0..buckets.len())
+        .map(|i| rows.iter().map(|r| r.amounts[i]).sum())
+        .collect();
+
+    Ok(AgingReport {
+        as_of_date,
+        basis,
+        total: totals.iter().sum(),
+        buckets,
+        rows,
+        totals,
+    })
+}
+
+/// Arabic column title of a bucket, as printed in exports.
+pub(crate) fn bucket_title(bucket: &AgingBucket) -> String {
+    if bucket.label == "current" {
+        "جاري".to_string()
+    } else {
+        format!("{} يوم", bucket.label)
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSV export of the report, with a BOM so spreadsheet apps read Arabic
+/// text correctly.
+pub(crate) fn aging_report_csv(report: &AgingReport) -> String {
+    let mut header = vec!["العميل".to_string(), "الهاتف".to_string()];
+    header.extend(report.buckets.iter().map(bucket_title));
+    header.push("المجموع".to_string());
+
+    let mut lines = vec![header
+        .iter()
+        .map(|h| csv_field(h))
+        .collect::<Vec<_>>()
+        .join(",")];
+    for row in &report.rows {
+        let mut fields = vec![
+            csv_field(&row.customer_name),
+            csv_field(&row.customer_phone),
+        ];
+        fields.extend(row.amounts.iter().map(|a| format!("{:.2}", a)));
+        fields.push(format!("{:.2}", row.total));
+        lines.push(fields.join(","));
+    }
+
+    let mut totals = vec!["المجموع".to_string(), String::new()];
+    totals.extend(report.totals.iter().map(|a| format!("{:.2}", a)));
+    totals.push(format!("{:.2}", report.total));
+    lines.push(totals.join(","));
+
+    format!("\u{feff}{}\n", lines.join("\n"))
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Outstanding amounts per customer bucketed by invoice age on
+/// `as_of_date` (today by default), counted from the invoice date or the due
+/// date. `bucket_edges` defaults to 30/60/90.
+#[tauri::command]
+pub fn get_aging_report(
+    as_of_date: Option<String>,
+    bucket_edges: Option<Vec<i64>>,
+    basis: Option<String>,
+) -> Result<AgingReport, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    build_aging_report(&conn, as_of_date, bucket_edges, basis)
+}
+
+/// Invoices still not fully paid on `as_of_date` (today by default) after
+/// their due date, most overdue first.
+#[tauri::command]
+pub fn get_overdue_invoices(as_of_date: Option<String>) -> Result<Vec<OverdueInvoice>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let as_of_date =
+        as_of_date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let as_of = NaiveDate::parse_from_str(&as_of_date, "%Y-%m-%d")
+        .map_err(|_| format!("التاريخ غير صالح: {}", as_of_date))?;
+
+    let (invoices, _) = allocate_payments(&conn, &as_of_date)?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT i.invoice_number, i.total * i.exchange_rate, c.name, c.phone
+            FROM invoices i
+            JOIN customers c ON c.id = i.customer_id
+            WHERE i.id = ?1
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut overdue = vec![];
+    for inv in invoices {
+        let days_overdue = days_between(&inv.due_date, as_of);
+        if days_overdue <= 0 {
+            continue;
+        }
+        let (invoice_number, total, customer_name, customer_phone) = stmt
+            .query_row([inv.id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| e.to_string())?;
+        overdue.push(OverdueInvoice {
+            id: inv.id,
+            invoice_number,
+            customer_id: inv.customer_id,
+            customer_name,
+            customer_phone,
+            date: inv.date,
+            due_date: inv.due_date,
+            total,
+            outstanding: inv.outstanding,
+            days_overdue,
+        });
+    }
+    overdue.sort_by_key(|o| std::cmp::Reverse(o.days_overdue));
+
+    Ok(overdue)
+}
@@ -0,0 +1,262 @@
+use crate::db::get_db;
+use crate::models::AuditEntry;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, Result};
+use serde_json::{Map, Value};
+
+//
+// ==================== Helpers ====================
+//
+
+fn current_user() -> Option<String> {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .ok()
+}
+
+/// Reads every row returned by `sql` into a JSON object keyed by column name,
+/// so snapshots keep working when columns are added to a table.
+pub(crate) fn snapshot_rows(
+    conn: &Connection,
+    sql: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Result<Vec<Value>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+    let rows = stmt
+        .query_map(params, |row| {
+            let mut obj = Map::new();
+            for (i, name) in names.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                    ValueRef::Integer(n) => Value::from(n),
+                    ValueRef::Real(f) => Value::from(f),
+                    ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+                };
+                obj.insert(name.clone(), value);
+            }
+            Ok(Value::Object(obj))
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// Snapshot of a single row of `table` by id.
+pub(crate) fn snapshot(conn: &Connection, table: &str, id: i64) -> Result<Option<Value>, String> {
+    let sql = format!("SELECT * FROM {} WHERE id = ?1", table);
+    Ok(snapshot_rows(conn, &sql, &[&id])?.into_iter().next())
+}
+
+/// Snapshot of an invoice together with its lines.
+pub(crate) fn snapshot_invoice(conn: &Connection, id: i64) -> Result<Option<Value>, String> {
+    let mut invoice = match snapshot(conn, "invoices", id)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let items = snapshot_rows(
+        conn,
+        "SELECT * FROM invoice_items WHERE invoice_id = ?1 ORDER BY id",
+        &[&id],
+    )?;
+    invoice["items"] = Value::Array(items);
+    Ok(Some(invoice))
+}
+
+fn customer_field_values(conn: &Connection, id: i64) -> Result<Value, String> {
+    Ok(Value::Array(snapshot_rows(
+        conn,
+        "SELECT f.key, v.value FROM customer_field_values v JOIN custom_fields f ON f.id = v.field_id WHERE v.customer_id = ?1 ORDER BY f.position, f.id",
+        &[&id],
+    )?))
+}
+
+/// Snapshot of a customer row together with its custom field values.
+pub(crate) fn snapshot_customer_row(conn: &Connection, id: i64) -> Result<Option<Value>, String> {
+    let mut customer = match snapshot(conn, "customers", id)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    customer["custom_fields"] = customer_field_values(conn, id)?;
+    Ok(Some(customer))
+}
+
+/// Snapshot of a customer together with the records that depend on it.
+pub(crate) fn snapshot_customer(conn: &Connection, id: i64) -> Result<Option<Value>, String> {
+    let mut customer = match snapshot_customer_row(conn, id)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    customer["invoices"] = Value::Array(snapshot_rows(
+        conn,
+        "SELECT * FROM invoices WHERE customer_id = ?1 ORDER BY id",
+        &[&id],
+    )?);
+    customer["payments"] = Value::Array(snapshot_rows(
+        conn,
+        "SELECT * FROM payments WHERE customer_id = ?1 ORDER BY id",
+        &[&id],
+    )?);
+    customer["contacts"] = Value::Array(snapshot_rows(
+        conn,
+        "SELECT * FROM customer_contacts WHERE customer_id = ?1 ORDER BY id",
+        &[&id],
+    )?);
+    customer["addresses"] = Value::Array(snapshot_rows(
+        conn,
+        "SELECT * FROM customer_addresses WHERE customer_id = ?1 ORDER BY id",
+        &[&id],
+    )?);
+    customer["tags"] = Value::Array(snapshot_rows(
+        conn,
+        "SELECT * FROM customer_tags WHERE customer_id = ?1 ORDER BY tag_id",
+        &[&id],
+    )?);
+    Ok(Some(customer))
+}
+
+/// Appends an entry to `audit_log`. Call it on the same connection (or
+/// transaction) as the change it describes.
+pub(crate) fn record_audit(
+    conn: &Connection,
+    entity: &str,
+    entity_id: i64,
+    action: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO audit_log (entity, entity_id, action, before_data, after_data, user_name) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            entity,
+            entity_id,
+            action,
+            before.map(|v| v.to_string()),
+            after.map(|v| v.to_string()),
+            current_user()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn parse_json(raw: Option<String>) -> Option<Value> {
+    raw.and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn query_audit(
+    conn: &Connection,
+    where_sql: &str,
+    params: &[&dyn rusqlite::ToSql],
+    limit_sql: &str,
+) -> Result<Vec<AuditEntry>, String> {
+    let sql = format!(
+        "SELECT id, entity, entity_id, action, before_data, after_data, user_name, created_at
+         FROM audit_log
+         {}
+         ORDER BY id DESC
+         {}",
+        where_sql, limit_sql
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params, |row| {
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                entity: row.get(1)?,
+                entity_id: row.get(2)?,
+                action: row.get(3)?,
+                before: parse_json(row.get(4)?),
+                after: parse_json(row.get(5)?),
+                user_name: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_audit_log(
+    entity: Option<String>,
+    entity_id: Option<i64>,
+    action: Option<String>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    page: Option<i64>,
+    page_size: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut conditions: Vec<String> = vec![];
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![];
+
+    if let Some(v) = entity {
+        params_vec.push(Box::new(v));
+        conditions.push(format!("entity = ?{}", params_vec.len()));
+    }
+    if let Some(v) = entity_id {
+        params_vec.push(Box::new(v));
+        conditions.push(format!("entity_id = ?{}", params_vec.len()));
+    }
+    if let Some(v) = action {
+        params_vec.push(Box::new(v));
+        conditions.push(format!("action = ?{}", params_vec.len()));
+    }
+    if let Some(v) = from_date {
+        params_vec.push(Box::new(v));
+        conditions.push(format!("date(created_at) >= ?{}", params_vec.len()));
+    }
+    if let Some(v) = to_date {
+        params_vec.push(Box::new(v));
+        conditions.push(format!("date(created_at) <= ?{}", params_vec.len()));
+    }
+
+    let where_sql = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let limit_sql = if let (Some(p), Some(size)) = (page, page_size) {
+        format!("LIMIT {} OFFSET {}", size, (p - 1) * size)
+    } else {
+        String::new()
+    };
+
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec
+        .iter()
+        .map(|b| &**b as &dyn rusqlite::ToSql)
+        .collect();
+
+    query_audit(&conn, &where_sql, params_refs.as_slice(), &limit_sql)
+}
+
+#[tauri::command]
+pub fn get_invoice_history(invoice_id: i64) -> Result<Vec<AuditEntry>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    query_audit(
+        &conn,
+        "WHERE entity = 'invoice' AND entity_id = ?1",
+        &[&invoice_id],
+        "",
+    )
+}
+
+#[tauri::command]
+pub fn get_payment_history(payment_id: i64) -> Result<Vec<AuditEntry>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    query_audit(
+        &conn,
+        "WHERE entity = 'payment' AND entity_id = ?1",
+        &[&payment_id],
+        "",
+    )
+}
@@ -0,0 +1,416 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::settings::default_country;
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{CustomerAddress, CustomerContact};
+use crate::phone::parse_phone;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+//
+// ==================== Helpers ====================
+//
+
+/// Returns `address_id` if it belongs to `customer_id`.
+pub(crate) fn check_address_owner(
+    conn: &Connection,
+    address_id: i32,
+    customer_id: i32,
+) -> Result<i32, String> {
+    let owner: Option<i32> = conn
+        .query_row(
+            "SELECT customer_id FROM customer_addresses WHERE id = ?1",
+            [address_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match owner {
+        Some(owner) if owner == customer_id => Ok(address_id),
+        Some(_) => Err("العنوان لا يخص هذا العميل".into()),
+        None => Err("العنوان غير موجود".into()),
+    }
+}
+
+pub(crate) fn primary_address_id(
+    conn: &Connection,
+    customer_id: i32,
+    kind: &str,
+) -> Result<Option<i32>, String> {
+    conn.query_row(
+        "SELECT id FROM customer_addresses WHERE customer_id = ?1 AND kind = ?2 AND is_primary = 1",
+        params![customer_id, kind],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+fn validate_contact(
+    conn: &Connection,
+    contact: &CustomerContact,
+) -> Result<Option<(String, String)>, CommandError> {
+    let mut errors = FieldErrors::default();
+    if contact.name.trim().is_empty() {
+        errors.add("name", "الاسم مطلوب");
+    }
+
+    let mut phone = None;
+    if let Some(v) = contact.phone.as_deref().filter(|v| !v.trim().is_empty()) {
+        match parse_phone(v, &default_country(conn)?) {
+            Ok(parsed) => phone = Some((parsed.display, parsed.e164)),
+            Err(e) => errors.add("phone", e),
+        }
+    }
+    if let Some(v) = contact.email.as_deref().filter(|v| !v.trim().is_empty()) {
+        if !v.contains('@') {
+            errors.add("email", "البريد الإلكتروني غير صالح");
+        }
+    }
+
+    errors.into_result()?;
+    Ok(phone)
+}
+
+fn validate_address(address: &CustomerAddress) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if address.address.trim().is_empty() {
+        errors.add("address", "العنوان مطلوب");
+    }
+    if !matches!(address.kind.as_str(), "billing" | "delivery") {
+        errors.add("kind", "نوع العنوان غير صالح");
+    }
+    errors.into_result()
+}
+
+/// Keeps a single primary row per customer (and per kind for addresses).
+/// The first row of its group is always primary.
+fn settle_primary(
+    conn: &Connection,
+    table: &str,
+    scope_sql: &str,
+    id: i64,
+    customer_id: i32,
+    is_primary: bool,
+) -> Result<(), String> {
+    if is_primary {
+        conn.execute(
+            &format!(
+                "UPDATE {} SET is_primary = 0 WHERE customer_id = ?1 AND id <> ?2 {}",
+                table, scope_sql
+            ),
+            params![customer_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        &format!(
+            "UPDATE {0} SET is_primary = 1 WHERE id = ?2 AND NOT EXISTS (SELECT 1 FROM {0} WHERE customer_id = ?1 AND is_primary = 1 {1})",
+            table, scope_sql
+        ),
+        params![customer_id, id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn contact_from_row(row: &rusqlite::Row) -> rusqlite::Result<CustomerContact> {
+    Ok(CustomerContact {
+        id: row.get(0)?,
+        customer_id: row.get(1)?,
+        name: row.get(2)?,
+        role: row.get(3)?,
+        phone: row.get(4)?,
+        phone_e164: row.get(5)?,
+        email: row.get(6)?,
+        is_primary: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+fn address_from_row(row: &rusqlite::Row) -> rusqlite::Result<CustomerAddress> {
+    Ok(CustomerAddress {
+        id: row.get(0)?,
+        customer_id: row.get(1)?,
+        kind: row.get(2)?,
+        label: row.get(3)?,
+        address: row.get(4)?,
+        is_primary: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+//
+// ==================== Contacts ====================
+//
+
+#[tauri::command]
+pub fn get_customer_contacts(customer_id: i32) -> Result<Vec<CustomerContact>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, customer_id, name, role, phone, phone_e164, email, is_primary, created_at
+             FROM customer_contacts
+             WHERE customer_id = ?
+             ORDER BY is_primary DESC, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([customer_id], contact_from_row)
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+#[tauri::command]
+pub fn create_customer_contact(contact: CustomerContact) -> Result<i32, CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let phone = validate_contact(&conn, &contact)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (phone, phone_e164) = phone.unzip();
+    tx.execute(
+        "INSERT INTO customer_contacts (customer_id, name, role, phone, phone_e164, email) VALUES (?, ?, ?, ?, ?, ?)",
+        params![contact.customer_id, contact.name, contact.role, phone, phone_e164, contact.email],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    settle_primary(
+        &tx,
+        "customer_contacts",
+        "",
+        id,
+        contact.customer_id,
+        contact.is_primary,
+    )?;
+
+    let after = snapshot(&tx, "customer_contacts", id)?;
+    record_audit(&tx, "customer_contact", id, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id as i32)
+}
+
+#[tauri::command]
+pub fn update_customer_contact(id: i32, contact: CustomerContact) -> Result<(), CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let phone = validate_contact(&conn, &contact)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // The contact stays with its customer whatever the payload says
+    let customer_id: i32 = tx
+        .query_row(
+            "SELECT customer_id FROM customer_contacts WHERE id = ?",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("جهة الاتصال غير موجودة")?;
+
+    let before = snapshot(&tx, "customer_contacts", id as i64)?;
+    let (phone, phone_e164) = phone.unzip();
+    tx.execute(
+        "UPDATE customer_contacts SET name = ?, role = ?, phone = ?, phone_e164 = ?, email = ? WHERE id = ?",
+        params![contact.name, contact.role, phone, phone_e164, contact.email, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    settle_primary(
+        &tx,
+        "customer_contacts",
+        "",
+        id as i64,
+        customer_id,
+        contact.is_primary,
+    )?;
+
+    let after = snapshot(&tx, "customer_contacts", id as i64)?;
+    record_audit(
+        &tx,
+        "customer_contact",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_customer_contact(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "customer_contacts", id as i64)?;
+    tx.execute("DELETE FROM customer_contacts WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    // Promote the oldest remaining contact if the primary one was removed
+    if let Some(customer_id) = before.as_ref().and_then(|b| b["customer_id"].as_i64()) {
+        tx.execute(
+            "UPDATE customer_contacts SET is_primary = 1
+             WHERE id = (SELECT MIN(id) FROM customer_contacts WHERE customer_id = ?1)
+               AND NOT EXISTS (SELECT 1 FROM customer_contacts WHERE customer_id = ?1 AND is_primary = 1)",
+            [customer_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    record_audit(
+        &tx,
+        "customer_contact",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        None,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+//
+// ==================== Addresses ====================
+//
+
+#[tauri::command]
+pub fn get_customer_addresses(
+    customer_id: i32,
+    kind: Option<String>,
+) -> Result<Vec<CustomerAddress>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, customer_id, kind, label, address, is_primary, created_at
+             FROM customer_addresses
+             WHERE customer_id = ?1 AND (?2 IS NULL OR kind = ?2)
+             ORDER BY kind, is_primary DESC, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![customer_id, kind], address_from_row)
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+#[tauri::command]
+pub fn create_customer_address(address: CustomerAddress) -> Result<i32, CommandError> {
+    validate_address(&address)?;
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO customer_addresses (customer_id, kind, label, address) VALUES (?, ?, ?, ?)",
+        params![
+            address.customer_id,
+            address.kind,
+            address.label,
+            address.address
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let scope = format!("AND kind = '{}'", address.kind);
+    settle_primary(
+        &tx,
+        "customer_addresses",
+        &scope,
+        id,
+        address.customer_id,
+        address.is_primary,
+    )?;
+
+    let after = snapshot(&tx, "customer_addresses", id)?;
+    record_audit(&tx, "customer_address", id, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id as i32)
+}
+
+#[tauri::command]
+pub fn update_customer_address(id: i32, address: CustomerAddress) -> Result<(), CommandError> {
+    validate_address(&address)?;
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let customer_id: i32 = tx
+        .query_row(
+            "SELECT customer_id FROM customer_addresses WHERE id = ?",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("العنوان غير موجود")?;
+
+    let before = snapshot(&tx, "customer_addresses", id as i64)?;
+    tx.execute(
+        "UPDATE customer_addresses SET kind = ?, label = ?, address = ? WHERE id = ?",
+        params![address.kind, address.label, address.address, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let scope = format!("AND kind = '{}'", address.kind);
+    settle_primary(
+        &tx,
+        "customer_addresses",
+        &scope,
+        id as i64,
+        customer_id,
+        address.is_primary,
+    )?;
+
+    let after = snapshot(&tx, "customer_addresses", id as i64)?;
+    record_audit(
+        &tx,
+        "customer_address",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Invoices that used the address fall back to the customer's main address.
+#[tauri::command]
+pub fn delete_customer_address(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "customer_addresses", id as i64)?;
+    tx.execute("DELETE FROM customer_addresses WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    if let Some(b) = before.as_ref() {
+        tx.execute(
+            "UPDATE customer_addresses SET is_primary = 1
+             WHERE id = (SELECT MIN(id) FROM customer_addresses WHERE customer_id = ?1 AND kind = ?2)
+               AND NOT EXISTS (SELECT 1 FROM customer_addresses WHERE customer_id = ?1 AND kind = ?2 AND is_primary = 1)",
+            params![b["customer_id"].as_i64(), b["kind"].as_str()],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    record_audit(
+        &tx,
+        "customer_address",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        None,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,350 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::settings::{default_payment_terms, read_setting};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::CreditStatus;
+use crate::terms::{due_date, is_known_terms};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde_json::{json, Value};
+
+//
+// ==================== Helpers ====================
+//
+
+// Balance and days past due of the oldest overdue invoice for every live
+// customer.
+const CREDIT_STATUS_SELECT: &str = r#"
+    SELECT * FROM (
+      SELECT
+        c.id,
+        c.name,
+        IFNULL((SELECT SUM(total * exchange_rate) FROM invoices WHERE customer_id = c.id AND deleted_at IS NULL AND state <> 'cancelled'), 0)
+          - IFNULL((SELECT SUM(amount * exchange_rate) FROM payments WHERE customer_id = c.id AND deleted_at IS NULL), 0)
+          - IFNULL((SELECT SUM(total * exchange_rate) FROM live_credit_notes WHERE customer_id = c.id), 0) AS balance,
+        c.credit_limit,
+        c.max_days_overdue,
+        (SELECT CAST(julianday('now', 'localtime', 'start of day') - julianday(MIN(IFNULL(due_date, date))) AS INTEGER)
+           FROM invoices
+          WHERE customer_id = c.id AND deleted_at IS NULL AND state <> 'cancelled' AND IFNULL(status, 'unpaid') <> 'paid'
+            AND IFNULL(due_date, date) < date('now', 'localtime')) AS days_overdue
+      FROM customers c
+      WHERE c.deleted_at IS NULL
+    )
+"#;
+
+fn credit_status_from_row(row: &rusqlite::Row) -> rusqlite::Result<CreditStatus> {
+    let balance: f64 = row.get(2)?;
+    let credit_limit: Option<f64> = row.get(3)?;
+    let max_days_overdue: Option<i32> = row.get(4)?;
+    let days_overdue: Option<i64> = row.get(5)?;
+
+    Ok(CreditStatus {
+        customer_id: row.get(0)?,
+        customer_name: row.get(1)?,
+        balance,
+        credit_limit,
+        max_days_overdue,
+        days_overdue,
+        over_limit: credit_limit.is_some_and(|l| balance > l + 0.005),
+        over_days: matches!((days_overdue, max_days_overdue), (Some(d), Some(m)) if d > m as i64),
+    })
+}
+
+pub(crate) fn credit_status(
+    conn: &Connection,
+    customer_id: i32,
+) -> Result<Option<CreditStatus>, String> {
+    conn.query_row(
+        &format!("{} WHERE id = ?1", CREDIT_STATUS_SELECT),
+        [customer_id],
+        credit_status_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// "block" refuses the invoice unless it is overridden with a reason,
+/// "warn" only asks for confirmation.
+pub(crate) fn credit_limit_mode(conn: &Connection) -> Result<String, String> {
+    Ok(read_setting(conn, "credit_limit_mode")?.unwrap_or_else(|| "block".to_string()))
+}
+
+/// Checks that adding `amount` of new debt keeps the customer within their
+/// credit limit and overdue allowance. When the check is overridden, returns
+/// the details to keep in the audit log.
+pub(crate) fn check_credit(
+    conn: &Connection,
+    customer_id: i32,
+    amount: f64,
+    override_limit: bool,
+    override_reason: Option<String>,
+) -> Result<Option<Value>, CommandError> {
+    credit_decision(
+        conn,
+        customer_id,
+        amount,
+        amount,
+        override_limit,
+        override_reason,
+    )
+}
+
+/// Re-runs the credit check for an invoice already saved in `conn`, e.g.
+/// when it is issued or moved to another customer, and records an override
+/// like `insert_invoice` does.
+pub(crate) fn check_invoice_credit(
+    conn: &Connection,
+    invoice_id: i32,
+    override_limit: bool,
+    override_reason: Option<String>,
+) -> Result<(), CommandError> {
+    let (customer_id, amount): (i32, f64) = conn
+        .query_row(
+            "SELECT customer_id, (total - IFNULL(paid_amount, 0)) * exchange_rate FROM invoices WHERE id = ?1",
+            [invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    // The invoice is already part of the customer's balance
+    let Some(details) = credit_decision(
+        conn,
+        customer_id,
+        amount,
+        0.0,
+        override_limit,
+        override_reason,
+    )?
+    else {
+        return Ok(());
+    };
+
+    conn.execute(
+        "UPDATE invoices SET credit_override_reason = ?1 WHERE id = ?2",
+        params![details["reason"].as_str(), invoice_id],
+    )
+    .map_err(|e| e.to_string())?;
+    record_audit(
+        conn,
+        "invoice",
+        invoice_id as i64,
+        "credit_override",
+        None,
+        Some(&details),
+    )?;
+    Ok(())
+}
+
+/// `amount` is the new debt, of which `unsaved` is not in the customer's
+/// balance yet.
+fn credit_decision(
+    conn: &Connection,
+    customer_id: i32,
+    amount: f64,
+    unsaved: f64,
+    override_limit: bool,
+    override_reason: Option<String>,
+) -> Result<Option<Value>, CommandError> {
+    let reason = override_reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+
+    // Cash sales never add debt, so they are always allowed
+    if amount <= 0.005 {
+        return Ok(None);
+    }
+    let status = match credit_status(conn, customer_id)? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    let new_balance = status.balance + unsaved;
+    let over_limit = status.credit_limit.is_some_and(|l| new_balance > l + 0.005);
+    if !over_limit && !status.over_days {
+        return Ok(None);
+    }
+
+    let mode = credit_limit_mode(conn)?;
+    if override_limit {
+        if mode == "block" && reason.is_none() {
+            let mut errors = FieldErrors::default();
+            errors.add("override_reason", "سبب التجاوز مطلوب");
+            errors.into_result()?;
+        }
+        return Ok(Some(json!({
+            "mode": mode,
+            "reason": reason,
+            "new_balance": new_balance,
+            "status": status,
+        })));
+    }
+
+    let message = if over_limit {
+        format!(
+            "سيصبح رصيد العميل {:.2} متجاوزاً الحد الائتماني ({:.2})",
+            new_balance,
+            status.credit_limit.unwrap_or_default()
+        )
+    } else {
+        format!(
+            "لدى العميل فاتورة متأخرة عن السداد منذ {} يوماً (الحد {} يوماً)",
+            status.days_overdue.unwrap_or_default(),
+            status.max_days_overdue.unwrap_or_default()
+        )
+    };
+
+    Err(CommandError::CreditLimit {
+        message,
+        mode,
+        new_balance,
+        status: Box::new(status),
+    })
+}
+
+/// Due date of an invoice dated `date` under the customer's payment terms,
+/// or the default terms when the customer has none.
+pub(crate) fn customer_due_date(
+    conn: &Connection,
+    customer_id: i32,
+    date: &str,
+) -> Result<String, String> {
+    let terms: Option<(Option<String>, Option<i32>)> = conn
+        .query_row(
+            "SELECT payment_terms, payment_terms_days FROM customers WHERE id = ?1",
+            [customer_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let (terms, days) = match terms {
+        Some((Some(terms), days)) => (terms, days),
+        _ => default_payment_terms(conn)?,
+    };
+    due_date(date, &terms, days)
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn set_customer_credit_limit(
+    customer_id: i32,
+    credit_limit: Option<f64>,
+    max_days_overdue: Option<i32>,
+) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if credit_limit.is_some_and(|l| l < 0.0) {
+        errors.add("credit_limit", "الحد الائتماني لا يمكن أن يكون سالباً");
+    }
+    if max_days_overdue.is_some_and(|d| d < 0) {
+        errors.add("max_days_overdue", "عدد الأيام لا يمكن أن يكون سالباً");
+    }
+    errors.into_result()?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "customers", customer_id as i64)?;
+    if before.is_none() {
+        return Err("العميل غير موجود".into());
+    }
+
+    tx.execute(
+        "UPDATE customers SET credit_limit = ?1, max_days_overdue = ?2 WHERE id = ?3",
+        params![credit_limit, max_days_overdue, customer_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = snapshot(&tx, "customers", customer_id as i64)?;
+    record_audit(
+        &tx,
+        "customer",
+        customer_id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Sets the payment terms used to compute the due date of the customer's
+/// new invoices. `None` falls back to the default terms.
+#[tauri::command]
+pub fn set_customer_payment_terms(
+    customer_id: i32,
+    payment_terms: Option<String>,
+    payment_terms_days: Option<i32>,
+) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if let Some(terms) = &payment_terms {
+        if !is_known_terms(terms) {
+            errors.add("payment_terms", "شروط الدفع غير معروفة");
+        } else if terms == "custom" && payment_terms_days.map_or(true, |d| d < 0) {
+            errors.add("payment_terms_days", "عدد أيام الأجل مطلوب");
+        }
+    }
+    errors.into_result()?;
+
+    // Days only mean something for custom terms
+    let payment_terms_days =
+        payment_terms_days.filter(|_| payment_terms.as_deref() == Some("custom"));
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "customers", customer_id as i64)?;
+    if before.is_none() {
+        return Err("العميل غير موجود".into());
+    }
+
+    tx.execute(
+        "UPDATE customers SET payment_terms = ?1, payment_terms_days = ?2 WHERE id = ?3",
+        params![payment_terms, payment_terms_days, customer_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = snapshot(&tx, "customers", customer_id as i64)?;
+    record_audit(
+        &tx,
+        "customer",
+        customer_id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_customer_credit_status(customer_id: i32) -> Result<Option<CreditStatus>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    credit_status(&conn, customer_id)
+}
+
+/// Customers whose balance is above their credit limit or whose oldest
+/// unpaid invoice is overdue for longer than they are allowed.
+#[tauri::command]
+pub fn get_customers_over_limit() -> Result<Vec<CreditStatus>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let sql = format!(
+        "{}
+         WHERE (credit_limit IS NOT NULL AND balance > credit_limit + 0.005)
+            OR (max_days_overdue IS NOT NULL AND days_overdue > max_days_overdue)
+         ORDER BY balance DESC",
+        CREDIT_STATUS_SELECT
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], credit_status_from_row)
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
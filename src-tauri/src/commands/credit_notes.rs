@@ -0,0 +1,410 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::lifecycle::sync_invoice_payments;
+use crate::commands::numbering::next_document_number;
+use crate::commands::stock::record_credit_note_stock;
+use crate::commands::taxes::round2;
+use crate::commands::units::check_quantity;
+use crate::db::get_db;
+use crate::error::CommandError;
+use crate::models::{CreditNote, CreditNoteItem, CreditNoteLine};
+use crate::terms::parse_date;
+use chrono::Datelike;
+use rusqlite::{params, Connection, OptionalExtension, Result, ToSql};
+
+//
+// ==================== Helpers ====================
+//
+
+const CREDIT_NOTE_SELECT: &str = r#"
+    SELECT
+      cn.id,
+      cn.credit_note_number,
+      cn.invoice_id,
+      i.invoice_number,
+      cn.customer_id,
+      c.name,
+      cn.date,
+      cn.reason,
+      cn.subtotal,
+      cn.tax_total,
+      cn.total,
+      cn.created_at,
+      cn.currency
+    FROM credit_notes cn
+    LEFT JOIN invoices i ON i.id = cn.invoice_id
+    LEFT JOIN customers c ON c.id = cn.customer_id
+"#;
+
+fn credit_note_from_row(row: &rusqlite::Row) -> rusqlite::Result<CreditNote> {
+    Ok(CreditNote {
+        id: row.get(0)?,
+        credit_note_number: row.get(1)?,
+        invoice_id: row.get(2)?,
+        invoice_number: row.get(3)?,
+        customer_id: row.get(4)?,
+        customer_name: row.get(5)?,
+        date: row.get(6)?,
+        reason: row.get(7)?,
+        subtotal: row.get(8)?,
+        tax_total: row.get(9)?,
+        total: row.get(10)?,
+        currency: row.get(12)?,
+        created_at: row.get(11)?,
+        items: Vec::new(),
+    })
+}
+
+// An invoice line with what has already been credited on it
+struct ReturnableLine {
+    id: i32,
+    product_name: String,
+    unit_price: f64,
+    quantity: f64,
+    unit_id: Option<i32>,
+    unit_name: Option<String>,
+    tax_rate: f64,
+    subtotal: f64,
+    tax_amount: f64,
+    credited_quantity: f64,
+    credited_subtotal: f64,
+    credited_tax: f64,
+}
+
+fn returnable_lines(conn: &Connection, invoice_id: i32) -> Result<Vec<ReturnableLine>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT
+              it.id, it.product_name, it.unit_price, it.quantity, it.tax_rate,
+              IFNULL(it.subtotal, it.total), it.tax_amount,
+              IFNULL(SUM(ci.quantity), 0), IFNULL(SUM(ci.subtotal), 0), IFNULL(SUM(ci.tax_amount), 0),
+              it.unit_id, u.name
+            FROM invoice_items it
+            LEFT JOIN credit_note_items ci ON ci.invoice_item_id = it.id
+            LEFT JOIN units u ON u.id = it.unit_id
+            WHERE it.invoice_id = ?1
+            GROUP BY it.id
+            ORDER BY it.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([invoice_id], |row| {
+            Ok(ReturnableLine {
+                id: row.get(0)?,
+                product_name: row.get(1)?,
+                unit_price: row.get(2)?,
+                quantity: row.get(3)?,
+                unit_id: row.get(10)?,
+                unit_name: row.get(11)?,
+                tax_rate: row.get(4)?,
+                subtotal: row.get(5)?,
+                tax_amount: row.get(6)?,
+                credited_quantity: row.get(7)?,
+                credited_subtotal: row.get(8)?,
+                credited_tax: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// Amounts of returning `quantity` of a line. The invoice discount is
+/// spread over the line like in the invoice totals; returning the last of a
+/// line credits exactly what is left of it so nothing is lost to rounding.
+fn credited_item(line: &ReturnableLine, quantity: f64, factor: f64) -> CreditNoteItem {
+    let remaining = line.quantity - line.credited_quantity;
+    let (subtotal, tax_amount) = if (quantity - remaining).abs() < 1e-9 {
+        (
+            round2(line.subtotal * factor - line.credited_subtotal),
+            round2(line.tax_amount * factor - line.credited_tax),
+        )
+    } else {
+        let ratio = quantity / line.quantity;
+        (
+            round2(line.subtotal * factor * ratio),
+            round2(line.tax_amount * factor * ratio),
+        )
+    };
+    CreditNoteItem {
+        id: None,
+        invoice_item_id: Some(line.id),
+        product_name: line.product_name.clone(),
+        unit_price: line.unit_price,
+        quantity,
+        unit_name: line.unit_name.clone(),
+        tax_rate: line.tax_rate,
+        subtotal,
+        tax_amount,
+        total: round2(subtotal + tax_amount),
+    }
+}
+
+fn credit_note_items(
+    conn: &Connection,
+    credit_note_id: i32,
+) -> Result<Vec<CreditNoteItem>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT ci.id, ci.invoice_item_id, ci.product_name, ci.unit_price, ci.quantity, ci.tax_rate,
+                   ci.subtotal, ci.tax_amount, ci.total, u.name
+            FROM credit_note_items ci
+            LEFT JOIN invoice_items it ON it.id = ci.invoice_item_id
+            LEFT JOIN units u ON u.id = it.unit_id
+            WHERE ci.credit_note_id = ?1 ORDER BY ci.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([credit_note_id], |row| {
+            Ok(CreditNoteItem {
+                id: row.get(0)?,
+                invoice_item_id: row.get(1)?,
+                product_name: row.get(2)?,
+                unit_price: row.get(3)?,
+                quantity: row.get(4)?,
+                unit_name: row.get(9)?,
+                tax_rate: row.get(5)?,
+                subtotal: row.get(6)?,
+                tax_amount: row.get(7)?,
+                total: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// A credit note with its lines.
+pub(crate) fn credit_note_with_items(conn: &Connection, id: i32) -> Result<CreditNote, String> {
+    let mut credit_note = conn
+        .query_row(
+            &format!("{} WHERE cn.id = ?1", CREDIT_NOTE_SELECT),
+            [id],
+            credit_note_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الإشعار الدائن غير موجود")?;
+    credit_note.items = credit_note_items(conn, id)?;
+    Ok(credit_note)
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Issues a credit note against an invoice. `lines` lists the quantities
+/// returned per invoice line; without it everything not yet credited is
+/// returned. An invoice without lines is credited for what is left of its
+/// total.
+#[tauri::command]
+pub fn create_credit_note(
+    invoice_id: i32,
+    date: Option<String>,
+    reason: Option<String>,
+    lines: Option<Vec<CreditNoteLine>>,
+) -> Result<CreditNote, CommandError> {
+    let date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let year = parse_date(&date)?.year();
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (customer_id, invoice_subtotal, invoice_tax, state): (i32, f64, f64, String) = tx
+        .query_row(
+            "SELECT customer_id, IFNULL(subtotal, total), IFNULL(tax_total, 0), state FROM invoices WHERE id = ?1 AND deleted_at IS NULL",
+            [invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    // A draft is simply edited
+    match state.as_str() {
+        "draft" => return Err("لا يمكن إصدار إشعار دائن لفاتورة غير مُصدرة".into()),
+        "cancelled" => return Err("لا يمكن إصدار إشعار دائن لفاتورة ملغاة".into()),
+        _ => {}
+    }
+
+    let available = returnable_lines(&tx, invoice_id)?;
+    let items: Vec<CreditNoteItem> = if available.is_empty() {
+        if lines.as_ref().is_some_and(|l| !l.is_empty()) {
+            return Err("الفاتورة لا تحتوي على بنود".into());
+        }
+        Vec::new()
+    } else {
+        // Share of the lines left after the invoice discount
+        let lines_subtotal: f64 = available.iter().map(|l| l.subtotal).sum();
+        let factor = if lines_subtotal > 0.0 {
+            invoice_subtotal / lines_subtotal
+        } else {
+            1.0
+        };
+
+        let requested: Vec<(usize, f64)> = match lines {
+            Some(lines) => lines
+                .iter()
+                .map(|line| {
+                    let index = available
+                        .iter()
+                        .position(|l| l.id == line.invoice_item_id)
+                        .ok_or_else(|| {
+                            format!("البند {} لا ينتمي إلى هذه الفاتورة", line.invoice_item_id)
+                        })?;
+                    Ok((index, line.quantity))
+                })
+                .collect::<Result<_, String>>()?,
+            None => available
+                .iter()
+                .enumerate()
+                .map(|(index, l)| (index, l.quantity - l.credited_quantity))
+                .filter(|(_, quantity)| *quantity > 1e-9)
+                .collect(),
+        };
+
+        let mut items: Vec<CreditNoteItem> = Vec::new();
+        for (index, quantity) in requested {
+            let line = &available[index];
+            if quantity <= 0.0 {
+                return Err("يجب أن تكون الكمية المرتجعة أكبر من صفر".into());
+            }
+            check_quantity(&tx, line.unit_id, quantity)?;
+            // The same line may be listed more than once
+            let listed: f64 = items
+                .iter()
+                .filter(|i| i.invoice_item_id == Some(line.id))
+                .map(|i| i.quantity)
+                .sum();
+            let remaining = line.quantity - line.credited_quantity - listed;
+            if quantity > remaining + 1e-9 {
+                return Err(format!(
+                    "الكمية المرتجعة من {} تتجاوز الكمية المتبقية ({})",
+                    line.product_name, remaining
+                )
+                .into());
+            }
+            items.push(credited_item(line, quantity, factor));
+        }
+        items
+    };
+
+    let (subtotal, tax_total) = if available.is_empty() {
+        let (credited_subtotal, credited_tax): (f64, f64) = tx
+            .query_row(
+                "SELECT IFNULL(SUM(subtotal), 0), IFNULL(SUM(tax_total), 0) FROM credit_notes WHERE invoice_id = ?1",
+                [invoice_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        (
+            round2(invoice_subtotal - credited_subtotal),
+            round2(invoice_tax - credited_tax),
+        )
+    } else {
+        (
+            round2(items.iter().map(|i| i.subtotal).sum()),
+            round2(items.iter().map(|i| i.tax_amount).sum()),
+        )
+    };
+    if subtotal + tax_total <= 0.0 {
+        return Err("لا يوجد ما يمكن إرجاعه من هذه الفاتورة".into());
+    }
+
+    let number = next_document_number(&tx, "credit_notes", "credit_note_number", "AV", year)?;
+    tx.execute(
+        // In the invoice's currency and at its rate, so it cancels the same amount
+        r#"
+        INSERT INTO credit_notes (credit_note_number, invoice_id, customer_id, date, reason, subtotal, tax_total, total, currency, exchange_rate)
+        SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, currency, exchange_rate FROM invoices WHERE id = ?2
+        "#,
+        params![
+            number,
+            invoice_id,
+            customer_id,
+            date,
+            reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()),
+            subtotal,
+            tax_total,
+            round2(subtotal + tax_total)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid() as i32;
+
+    for item in &items {
+        tx.execute(
+            "INSERT INTO credit_note_items (credit_note_id, invoice_item_id, product_name, unit_price, quantity, tax_rate, subtotal, tax_amount, total) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                id,
+                item.invoice_item_id,
+                item.product_name,
+                item.unit_price,
+                item.quantity,
+                item.tax_rate,
+                item.subtotal,
+                item.tax_amount,
+                item.total
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    // Returned goods go back in stock
+    record_credit_note_stock(&tx, id)?;
+    // A fully credited invoice has nothing left to pay
+    sync_invoice_payments(&tx, invoice_id)?;
+
+    let after = snapshot(&tx, "credit_notes", id as i64)?;
+    record_audit(
+        &tx,
+        "credit_note",
+        id as i64,
+        "create",
+        None,
+        after.as_ref(),
+    )?;
+
+    let credit_note = credit_note_with_items(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(credit_note)
+}
+
+/// Credit notes, newest first, optionally for one customer or one invoice.
+#[tauri::command]
+pub fn get_credit_notes(
+    customer_id: Option<i32>,
+    invoice_id: Option<i32>,
+) -> Result<Vec<CreditNote>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut conditions = vec!["i.deleted_at IS NULL".to_string()];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(customer_id) = customer_id {
+        params.push(Box::new(customer_id));
+        conditions.push(format!("cn.customer_id = ?{}", params.len()));
+    }
+    if let Some(invoice_id) = invoice_id {
+        params.push(Box::new(invoice_id));
+        conditions.push(format!("cn.invoice_id = ?{}", params.len()));
+    }
+
+    let sql = format!(
+        "{} WHERE {} ORDER BY cn.date DESC, cn.id DESC",
+        CREDIT_NOTE_SELECT,
+        conditions.join(" AND ")
+    );
+    let params_refs: Vec<&dyn ToSql> = params.iter().map(|b| &**b as &dyn ToSql).collect();
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_refs.as_slice(), credit_note_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_credit_note(id: i32) -> Result<CreditNote, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    credit_note_with_items(&conn, id)
+}
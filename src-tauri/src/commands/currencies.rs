@@ -0,0 +1,223 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::settings::base_currency;
+use crate::currency::{currencies, is_known_currency};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{Currency, ExchangeRate};
+use crate::terms::parse_date;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+//
+// ==================== Helpers ====================
+//
+
+/// Rate of `currency` on `date`: the latest one entered on or before it.
+/// The base currency is always worth 1.
+pub(crate) fn rate_on(conn: &Connection, currency: &str, date: &str) -> Result<f64, String> {
+    if currency == base_currency(conn)? {
+        return Ok(1.0);
+    }
+    conn.query_row(
+        "SELECT rate FROM exchange_rates WHERE currency = ?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+        params![currency, date.get(..10).unwrap_or(date)],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("لا يوجد سعر صرف لـ {} بتاريخ {}", currency, date))
+}
+
+/// Currency of a document: the base currency when none is given.
+pub(crate) fn currency_code(
+    conn: &Connection,
+    currency: Option<&str>,
+) -> Result<String, CommandError> {
+    let currency = match currency {
+        Some(c) => c.trim().to_uppercase(),
+        None => base_currency(conn)?,
+    };
+    if !is_known_currency(&currency) {
+        let mut errors = FieldErrors::default();
+        errors.add("currency", "العملة غير معروفة");
+        errors.into_result()?;
+    }
+    Ok(currency)
+}
+
+/// Currency and rate of a document dated `date`: the base currency when none
+/// is given, and the rate of the day unless one is entered.
+pub(crate) fn document_currency(
+    conn: &Connection,
+    currency: Option<&str>,
+    exchange_rate: Option<f64>,
+    date: &str,
+) -> Result<(String, f64), CommandError> {
+    let currency = currency_code(conn, currency)?;
+    if exchange_rate.is_some_and(|r| r <= 0.0) {
+        let mut errors = FieldErrors::default();
+        errors.add("exchange_rate", "يجب أن يكون سعر الصرف أكبر من صفر");
+        errors.into_result()?;
+    }
+
+    if currency == base_currency(conn)? {
+        return Ok((currency, 1.0));
+    }
+    let rate = match exchange_rate {
+        Some(rate) => rate,
+        None => rate_on(conn, &currency, date)?,
+    };
+    Ok((currency, rate))
+}
+
+fn validate_exchange_rate(
+    conn: &Connection,
+    exchange_rate: &ExchangeRate,
+) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if !is_known_currency(&exchange_rate.currency) {
+        errors.add("currency", "العملة غير معروفة");
+    } else if exchange_rate.currency == base_currency(conn)? {
+        errors.add("currency", "لا حاجة لسعر صرف للعملة الأساسية");
+    }
+    if parse_date(&exchange_rate.date).is_err() {
+        errors.add("date", "التاريخ غير صالح");
+    }
+    if exchange_rate.rate <= 0.0 {
+        errors.add("rate", "يجب أن يكون سعر الصرف أكبر من صفر");
+    }
+    errors.into_result()
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_currencies() -> Result<Vec<Currency>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let base = base_currency(&conn)?;
+
+    Ok(currencies()
+        .map(|(code, symbol, decimals)| Currency {
+            code: code.to_string(),
+            symbol: symbol.to_string(),
+            decimals,
+            is_base: code == base,
+        })
+        .collect())
+}
+
+/// Entered rates, newest first, optionally for one currency.
+#[tauri::command]
+pub fn get_exchange_rates(currency: Option<String>) -> Result<Vec<ExchangeRate>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, currency, date, rate, created_at FROM exchange_rates WHERE ?1 IS NULL OR currency = ?1 ORDER BY date DESC, currency",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([currency], |row| {
+            Ok(ExchangeRate {
+                id: row.get(0)?,
+                currency: row.get(1)?,
+                date: row.get(2)?,
+                rate: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// Enters the rate of a currency from a date, replacing the one already
+/// entered for that day. Documents keep the rate they were issued at.
+#[tauri::command]
+pub fn set_exchange_rate(exchange_rate: ExchangeRate) -> Result<i32, CommandError> {
+    let exchange_rate = ExchangeRate {
+        currency: exchange_rate.currency.trim().to_uppercase(),
+        ..exchange_rate
+    };
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    validate_exchange_rate(&conn, &exchange_rate)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let existing: Option<i32> = tx
+        .query_row(
+            "SELECT id FROM exchange_rates WHERE currency = ?1 AND date = ?2",
+            params![exchange_rate.currency, exchange_rate.date],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let id = match existing {
+        Some(id) => {
+            let before = snapshot(&tx, "exchange_rates", id as i64)?;
+            tx.execute(
+                "UPDATE exchange_rates SET rate = ?1 WHERE id = ?2",
+                params![exchange_rate.rate, id],
+            )
+            .map_err(|e| e.to_string())?;
+            let after = snapshot(&tx, "exchange_rates", id as i64)?;
+            record_audit(
+                &tx,
+                "exchange_rate",
+                id as i64,
+                "update",
+                before.as_ref(),
+                after.as_ref(),
+            )?;
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO exchange_rates (currency, date, rate) VALUES (?1, ?2, ?3)",
+                params![
+                    exchange_rate.currency,
+                    exchange_rate.date,
+                    exchange_rate.rate
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            let id = tx.last_insert_rowid() as i32;
+            let after = snapshot(&tx, "exchange_rates", id as i64)?;
+            record_audit(
+                &tx,
+                "exchange_rate",
+                id as i64,
+                "create",
+                None,
+                after.as_ref(),
+            )?;
+            id
+        }
+    };
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_exchange_rate(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "exchange_rates", id as i64)?;
+    tx.execute("DELETE FROM exchange_rates WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(
+        &tx,
+        "exchange_rate",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        None,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
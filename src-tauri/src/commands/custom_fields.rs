@@ -0,0 +1,323 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::CustomField;
+use crate::normalize::normalize_text;
+use rusqlite::{params, Connection, Result};
+use std::collections::BTreeMap;
+
+//
+// ==================== Helpers ====================
+//
+
+const FIELD_TYPES: &[&str] = &["text", "number", "date", "select"];
+
+fn custom_field_from_row(row: &rusqlite::Row) -> rusqlite::Result<CustomField> {
+    let options: Option<String> = row.get(4)?;
+    Ok(CustomField {
+        id: row.get(0)?,
+        key: row.get(1)?,
+        label: row.get(2)?,
+        field_type: row.get(3)?,
+        options: options
+            .and_then(|o| serde_json::from_str(&o).ok())
+            .unwrap_or_default(),
+        required: row.get(5)?,
+        position: row.get(6)?,
+    })
+}
+
+fn load_custom_fields(conn: &Connection) -> Result<Vec<CustomField>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, key, label, field_type, options, required, position FROM custom_fields ORDER BY position, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], custom_field_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// Parses a value typed for `field`; numbers and dates are stored in a
+/// canonical form so they sort and compare correctly.
+fn parse_field_value(field: &CustomField, value: &str) -> Result<String, String> {
+    match field.field_type.as_str() {
+        "number" => normalize_text(value)
+            .replace(',', ".")
+            .parse::<f64>()
+            .map(|n| n.to_string())
+            .map_err(|_| "يجب أن تكون القيمة رقماً".to_string()),
+        "date" => chrono::NaiveDate::parse_from_str(&normalize_text(value), "%Y-%m-%d")
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map_err(|_| "التاريخ غير صالح".to_string()),
+        "select" => {
+            if field.options.iter().any(|o| o == value) {
+                Ok(value.to_string())
+            } else {
+                Err("القيمة غير موجودة في الخيارات".to_string())
+            }
+        }
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// Validates custom field values of a customer form, keyed by field key.
+/// Errors are reported under the field key. With `check_required`, required
+/// fields missing from `values` are reported too (used on creation).
+/// Returns the values to store; `None` clears a field.
+pub(crate) fn validate_custom_field_values(
+    conn: &Connection,
+    values: &BTreeMap<String, String>,
+    check_required: bool,
+    errors: &mut FieldErrors,
+) -> Result<Vec<(i32, Option<String>)>, String> {
+    let fields = load_custom_fields(conn)?;
+    let mut parsed = vec![];
+
+    for key in values.keys() {
+        if !fields.iter().any(|f| &f.key == key) {
+            errors.add(key, "حقل غير معروف");
+        }
+    }
+
+    for field in &fields {
+        let value = values.get(&field.key).map(|v| v.trim());
+        match value {
+            Some(v) if !v.is_empty() => match parse_field_value(field, v) {
+                Ok(v) => parsed.push((field.id.unwrap_or_default(), Some(v))),
+                Err(e) => errors.add(&field.key, e),
+            },
+            Some(_) | None => {
+                if field.required && (value.is_some() || check_required) {
+                    errors.add(&field.key, format!("{} مطلوب", field.label));
+                } else if value.is_some() {
+                    parsed.push((field.id.unwrap_or_default(), None));
+                }
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+pub(crate) fn write_custom_field_values(
+    conn: &Connection,
+    customer_id: i32,
+    values: &[(i32, Option<String>)],
+) -> Result<(), String> {
+    for (field_id, value) in values {
+        match value {
+            Some(v) => conn.execute(
+                "INSERT INTO customer_field_values (customer_id, field_id, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(customer_id, field_id) DO UPDATE SET value = excluded.value",
+                params![customer_id, field_id, v],
+            ),
+            None => conn.execute(
+                "DELETE FROM customer_field_values WHERE customer_id = ?1 AND field_id = ?2",
+                params![customer_id, field_id],
+            ),
+        }
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn typst_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Fills the custom field placeholders of a Typst template for a customer:
+/// `{custom.key}` becomes the value of that field and `{custom_fields}` a
+/// list of `("label", "value")` pairs for the fields that are set. Without
+/// a customer every placeholder is left empty.
+pub(crate) fn fill_custom_fields(
+    conn: &Connection,
+    template: &str,
+    customer_id: Option<i32>,
+) -> Result<String, String> {
+    let values: BTreeMap<i32, String> = match customer_id {
+        Some(id) => {
+            let mut stmt = conn
+                .prepare("SELECT field_id, value FROM customer_field_values WHERE customer_id = ?1")
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?;
+            rows.filter_map(|x| x.ok()).collect()
+        }
+        None => BTreeMap::new(),
+    };
+
+    let mut content = template.to_string();
+    let mut pairs = String::new();
+    for field in load_custom_fields(conn)? {
+        let value = values.get(&field.id.unwrap_or_default());
+        content = content.replace(
+            &format!("{{custom.{}}}", field.key),
+            &typst_escape(value.map(String::as_str).unwrap_or("")),
+        );
+        if let Some(v) = value {
+            pairs.push_str(&format!(
+                "(\"{}\", \"{}\"),\n",
+                typst_escape(&field.label),
+                typst_escape(v)
+            ));
+        }
+    }
+
+    Ok(content.replace("{custom_fields}", &pairs))
+}
+
+fn validate_custom_field(field: &CustomField) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if field.key.is_empty()
+        || !field
+            .key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        errors.add("key", "المعرف يجب أن يتكون من أحرف لاتينية صغيرة وأرقام و _");
+    }
+    if field.label.trim().is_empty() {
+        errors.add("label", "اسم الحقل مطلوب");
+    }
+    if !FIELD_TYPES.contains(&field.field_type.as_str()) {
+        errors.add("field_type", "نوع الحقل غير صالح");
+    }
+    if field.field_type == "select" && field.options.iter().all(|o| o.trim().is_empty()) {
+        errors.add("options", "يجب إضافة خيار واحد على الأقل");
+    }
+    errors.into_result()
+}
+
+fn options_json(field: &CustomField) -> Option<String> {
+    if field.field_type == "select" {
+        let options: Vec<&str> = field
+            .options
+            .iter()
+            .map(|o| o.trim())
+            .filter(|o| !o.is_empty())
+            .collect();
+        serde_json::to_string(&options).ok()
+    } else {
+        None
+    }
+}
+
+fn map_unique_error(e: rusqlite::Error) -> CommandError {
+    if e.to_string().contains("UNIQUE") {
+        CommandError::Validation {
+            fields: BTreeMap::from([("key".to_string(), "هذا المعرف مستخدم بالفعل".to_string())]),
+        }
+    } else {
+        e.to_string().into()
+    }
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_custom_fields() -> Result<Vec<CustomField>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    load_custom_fields(&conn)
+}
+
+#[tauri::command]
+pub fn create_custom_field(field: CustomField) -> Result<i32, CommandError> {
+    validate_custom_field(&field)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO custom_fields (key, label, field_type, options, required, position) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            field.key,
+            field.label.trim(),
+            field.field_type,
+            options_json(&field),
+            field.required,
+            field.position
+        ],
+    )
+    .map_err(map_unique_error)?;
+
+    let id = tx.last_insert_rowid() as i32;
+
+    let after = snapshot(&tx, "custom_fields", id as i64)?;
+    record_audit(
+        &tx,
+        "custom_field",
+        id as i64,
+        "create",
+        None,
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Updates a field definition. Stored values are kept as they are, so
+/// changing the type of a field does not rewrite existing data.
+#[tauri::command]
+pub fn update_custom_field(id: i32, field: CustomField) -> Result<(), CommandError> {
+    validate_custom_field(&field)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "custom_fields", id as i64)?;
+    tx.execute(
+        "UPDATE custom_fields SET key = ?1, label = ?2, field_type = ?3, options = ?4, required = ?5, position = ?6 WHERE id = ?7",
+        params![
+            field.key,
+            field.label.trim(),
+            field.field_type,
+            options_json(&field),
+            field.required,
+            field.position,
+            id
+        ],
+    )
+    .map_err(map_unique_error)?;
+
+    let after = snapshot(&tx, "custom_fields", id as i64)?;
+    record_audit(
+        &tx,
+        "custom_field",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Deletes a field definition together with the values stored for it.
+#[tauri::command]
+pub fn delete_custom_field(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "custom_fields", id as i64)?;
+    tx.execute("DELETE FROM custom_fields WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(
+        &tx,
+        "custom_field",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        None,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -1,53 +1,103 @@
+use crate::commands::audit::{record_audit, snapshot, snapshot_customer, snapshot_customer_row};
+use crate::commands::custom_fields::{validate_custom_field_values, write_custom_field_values};
+use crate::commands::debts::customer_balance;
+use crate::commands::settings::default_country;
+use crate::commands::tags::tag_filter_sql;
 use crate::db::get_db;
-use rusqlite::{params, Result, OptionalExtension};
-use crate::models::{Customer};
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{Customer, InvalidPhone, PhoneNormalizationReport};
+use crate::normalize::{like_pattern, normalize_text};
+use crate::phone::parse_phone;
+use rusqlite::{params, OptionalExtension, Result};
+use std::collections::BTreeMap;
 
+// Custom field values come back as a JSON object keyed by field key
+pub(crate) const CUSTOMER_COLUMNS: &str =
+    "id, name, phone, address, notes, createdAt, phone_e164, credit_limit, max_days_overdue,
+    payment_terms, payment_terms_days,
+    (SELECT json_group_object(f.key, v.value)
+       FROM customer_field_values v
+       JOIN custom_fields f ON f.id = v.field_id
+      WHERE v.customer_id = customers.id) AS custom_fields";
+
+pub(crate) fn customer_from_row(row: &rusqlite::Row) -> rusqlite::Result<Customer> {
+    Ok(Customer {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        phone: row.get(2)?,
+        address: row.get(3)?,
+        notes: row.get(4)?,
+        created_at: row.get(5)?,
+        phone_e164: row.get(6)?,
+        credit_limit: row.get(7)?,
+        max_days_overdue: row.get(8)?,
+        payment_terms: row.get(9)?,
+        payment_terms_days: row.get(10)?,
+        custom_fields: row
+            .get::<_, Option<String>>(11)?
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default(),
+    })
+}
+
+/// Digits of a search query as they would appear inside an E.164 number,
+/// e.g. "0555 12" → "%55512%".
+fn phone_digits_pattern(query: &str) -> String {
+    let digits: String = normalize_text(query)
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        // `LIKE ''` never matches a stored number
+        String::new()
+    } else {
+        format!("%{}%", digits)
+    }
+}
 
 #[tauri::command]
 pub fn get_all_customers(
     search_query: Option<String>,
     page: Option<i32>,
     page_size: Option<i32>,
+    tag_ids: Option<Vec<i32>>,
 ) -> Result<Vec<Customer>, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
 
-    let mut sql = "SELECT id, name, phone, address, notes, createdAt FROM customers".to_string();
+    let mut sql = format!(
+        "SELECT {} FROM customers WHERE deleted_at IS NULL",
+        CUSTOMER_COLUMNS
+    );
+    if let Some(filter) = tag_filter_sql("id", tag_ids.as_deref()) {
+        sql += &format!(" AND {}", filter);
+    }
 
     let customers = if let Some(search) = search_query {
-        let s = format!("%{}%", search);
-        sql += " WHERE name LIKE ?1 OR phone LIKE ?2 OR address LIKE ?3 OR notes LIKE ?4";
+        let s = like_pattern(&search);
+        let digits = phone_digits_pattern(&search);
+        sql += " AND (name_normalized LIKE ?1 OR normalize_text(phone) LIKE ?2 OR normalize_text(address) LIKE ?3 OR normalize_text(notes) LIKE ?4 OR phone_e164 LIKE ?5
+                  OR id IN (SELECT customer_id FROM customer_field_values WHERE normalize_text(value) LIKE ?4))";
         sql += " ORDER BY createdAt DESC";
 
         if let (Some(page), Some(size)) = (page, page_size) {
-            sql += " LIMIT ?5 OFFSET ?6";
+            sql += " LIMIT ?6 OFFSET ?7";
             let offset = (page - 1) * size;
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![s.clone(), s.clone(), s.clone(), s, size, offset], |row| {
-                    Ok(Customer {
-                        id: row.get(0)?,
-                        name: row.get(1)?,
-                        phone: row.get(2)?,
-                        address: row.get(3)?,
-                        notes: row.get(4)?,
-                        created_at: row.get(5)?,
-                    })
-                })
+                .query_map(
+                    params![s.clone(), s.clone(), s.clone(), s, digits, size, offset],
+                    customer_from_row,
+                )
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|x| x.ok()).collect()
         } else {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![s.clone(), s.clone(), s.clone(), s], |row| {
-                    Ok(Customer {
-                        id: row.get(0)?,
-                        name: row.get(1)?,
-                        phone: row.get(2)?,
-                        address: row.get(3)?,
-                        notes: row.get(4)?,
-                        created_at: row.get(5)?,
-                    })
-                })
+                .query_map(
+                    params![s.clone(), s.clone(), s.clone(), s, digits],
+                    customer_from_row,
+                )
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|x| x.ok()).collect()
         }
@@ -59,31 +109,13 @@ pub fn get_all_customers(
             let offset = (page - 1) * size;
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![size, offset], |row| {
-                    Ok(Customer {
-                        id: row.get(0)?,
-                        name: row.get(1)?,
-                        phone: row.get(2)?,
-                        address: row.get(3)?,
-                        notes: row.get(4)?,
-                        created_at: row.get(5)?,
-                    })
-                })
+                .query_map(params![size, offset], customer_from_row)
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|x| x.ok()).collect()
         } else {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map([], |row| {
-                    Ok(Customer {
-                        id: row.get(0)?,
-                        name: row.get(1)?,
-                        phone: row.get(2)?,
-                        address: row.get(3)?,
-                        notes: row.get(4)?,
-                        created_at: row.get(5)?,
-                    })
-                })
+                .query_map([], customer_from_row)
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|x| x.ok()).collect()
         }
@@ -97,20 +129,14 @@ pub fn get_customer_by_id(id: i32) -> Result<Option<Customer>, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, name, phone, address, notes, createdAt FROM customers WHERE id = ?")
+        .prepare(&format!(
+            "SELECT {} FROM customers WHERE id = ?",
+            CUSTOMER_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
 
     let result = stmt
-        .query_row([id], |row| {
-            Ok(Customer {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                phone: row.get(2)?,
-                address: row.get(3)?,
-                notes: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
+        .query_row([id], customer_from_row)
         .optional()
         .map_err(|e| e.to_string())?;
 
@@ -123,25 +149,47 @@ pub fn create_customer(
     phone: String,
     address: String,
     notes: Option<String>,
-) -> Result<Customer, String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+    custom_fields: Option<BTreeMap<String, String>>,
+) -> Result<Customer, CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut errors = FieldErrors::default();
+    if name.trim().is_empty() {
+        errors.add("name", "الاسم مطلوب");
+    }
+    let parsed = parse_phone(&phone, &default_country(&conn)?);
+    if let Err(e) = &parsed {
+        errors.add("phone", e.clone());
+    }
+    let custom_fields = custom_fields.unwrap_or_default();
+    let field_values = validate_custom_field_values(&conn, &custom_fields, true, &mut errors)?;
+    errors.into_result()?;
+    let parsed = parsed?;
 
-    conn.execute(
-        "INSERT INTO customers (name, phone, address, notes) VALUES (?, ?, ?, ?)",
-        params![name, phone, address, notes],
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO customers (name, phone, phone_e164, address, notes) VALUES (?, ?, ?, ?, ?)",
+        params![name, parsed.display, parsed.e164, address, notes],
     )
     .map_err(|e| e.to_string())?;
 
-    let id = conn.last_insert_rowid() as i32;
+    let id = tx.last_insert_rowid() as i32;
+    write_custom_field_values(&tx, id, &field_values)?;
 
-    Ok(Customer {
-        id: Some(id),
-        name,
-        phone,
-        address,
-        notes,
-        created_at: Some(chrono::Utc::now().to_rfc3339()),
-    })
+    let after = snapshot_customer_row(&tx, id as i64)?;
+    record_audit(&tx, "customer", id as i64, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let customer = conn
+        .query_row(
+            &format!("SELECT {} FROM customers WHERE id = ?", CUSTOMER_COLUMNS),
+            [id],
+            customer_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(customer)
 }
 
 #[tauri::command]
@@ -151,19 +199,31 @@ pub fn update_customer(
     phone: Option<String>,
     address: Option<String>,
     notes: Option<String>,
-) -> Result<(), String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+    custom_fields: Option<BTreeMap<String, String>>,
+) -> Result<(), CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
 
+    let mut errors = FieldErrors::default();
     let mut fields = vec![];
     let mut updates = vec![];
 
     if let Some(v) = name {
+        if v.trim().is_empty() {
+            errors.add("name", "الاسم مطلوب");
+        }
         fields.push("name = ?");
         updates.push(v);
     }
     if let Some(v) = phone {
-        fields.push("phone = ?");
-        updates.push(v);
+        match parse_phone(&v, &default_country(&conn)?) {
+            Ok(parsed) => {
+                fields.push("phone = ?");
+                updates.push(parsed.display);
+                fields.push("phone_e164 = ?");
+                updates.push(parsed.e164);
+            }
+            Err(e) => errors.add("phone", e),
+        }
     }
     if let Some(v) = address {
         fields.push("address = ?");
@@ -173,30 +233,171 @@ pub fn update_customer(
         fields.push("notes = ?");
         updates.push(v);
     }
+    let field_values = match &custom_fields {
+        Some(values) => validate_custom_field_values(&conn, values, false, &mut errors)?,
+        None => vec![],
+    };
 
-    if fields.is_empty() {
+    errors.into_result()?;
+
+    if fields.is_empty() && field_values.is_empty() {
         return Ok(());
     }
 
     let sql = format!("UPDATE customers SET {} WHERE id = ?", fields.join(", "));
 
     // Convert to references for rusqlite
-    let params_refs: Vec<&dyn rusqlite::ToSql> = updates.iter().map(|x| x as &dyn rusqlite::ToSql).collect();
+    let params_refs: Vec<&dyn rusqlite::ToSql> =
+        updates.iter().map(|x| x as &dyn rusqlite::ToSql).collect();
     let mut all_params = params_refs;
     all_params.push(&id);
 
-    conn.execute(&sql, all_params.as_slice())
-        .map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let before = snapshot_customer_row(&tx, id as i64)?;
+
+    if !fields.is_empty() {
+        tx.execute(&sql, all_params.as_slice())
+            .map_err(|e| e.to_string())?;
+    }
+    write_custom_field_values(&tx, id, &field_values)?;
+
+    let after = snapshot_customer_row(&tx, id as i64)?;
+    record_audit(
+        &tx,
+        "customer",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
+/// One-off clean-up: rewrites every stored phone number in its normalized
+/// form and reports the ones that cannot be parsed.
 #[tauri::command]
-pub fn delete_customer(id: i32) -> Result<(), String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+pub fn normalize_customer_phones() -> Result<PhoneNormalizationReport, String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let country = default_country(&conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let customers: Vec<(i32, String, String, Option<String>)> = {
+        let mut stmt = tx
+            .prepare("SELECT id, name, phone, phone_e164 FROM customers ORDER BY id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|x| x.ok()).collect()
+    };
+
+    let mut report = PhoneNormalizationReport {
+        updated: 0,
+        unchanged: 0,
+        invalid: vec![],
+    };
+
+    for (id, name, phone, e164) in customers {
+        match parse_phone(&phone, &country) {
+            Ok(parsed) => {
+                if parsed.display == phone && e164.as_deref() == Some(parsed.e164.as_str()) {
+                    report.unchanged += 1;
+                    continue;
+                }
+                let before = snapshot(&tx, "customers", id as i64)?;
+                tx.execute(
+                    "UPDATE customers SET phone = ?1, phone_e164 = ?2 WHERE id = ?3",
+                    params![parsed.display, parsed.e164, id],
+                )
+                .map_err(|e| e.to_string())?;
+                let after = snapshot(&tx, "customers", id as i64)?;
+                record_audit(
+                    &tx,
+                    "customer",
+                    id as i64,
+                    "update",
+                    before.as_ref(),
+                    after.as_ref(),
+                )?;
+                report.updated += 1;
+            }
+            Err(error) => report.invalid.push(InvalidPhone {
+                customer_id: id,
+                customer_name: name,
+                phone,
+                error,
+            }),
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(report)
+}
 
-    conn.execute("DELETE FROM customers WHERE id = ?", params![id])
+#[tauri::command]
+pub fn delete_customer(id: i32, force: Option<bool>) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let balance = customer_balance(&tx, id)?;
+    if balance.abs() > 0.005 && !force.unwrap_or(false) {
+        return Err(format!(
+            "لا يمكن حذف العميل لأن رصيده غير صفري ({:.2})",
+            balance
+        ));
+    }
+
+    // Issued invoices are only ever voided, never deleted
+    let issued: i64 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM invoices WHERE customer_id = ?1 AND deleted_at IS NULL AND state IN ('issued', 'paid')",
+            [id],
+            |row| row.get(0),
+        )
         .map_err(|e| e.to_string())?;
+    if issued > 0 {
+        return Err(format!(
+            "لا يمكن حذف العميل لأن له {} فاتورة مُصدرة، قم بإلغائها أولاً",
+            issued
+        ));
+    }
+
+    let before = snapshot_customer(&tx, id as i64)?;
+
+    // The customer's draft invoices and payments are moved to the recycle bin
+    // with the same timestamp so that restoring the customer brings them back
+    // too. Voided invoices stay as they are.
+    let now = chrono::Utc::now().to_rfc3339();
+    tx.execute(
+        "UPDATE invoices SET deleted_at = ?1 WHERE customer_id = ?2 AND deleted_at IS NULL AND state = 'draft'",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE payments SET deleted_at = ?1 WHERE customer_id = ?2 AND deleted_at IS NULL",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE customers SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = snapshot(&tx, "customers", id as i64)?;
+    record_audit(
+        &tx,
+        "customer",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(())
 }
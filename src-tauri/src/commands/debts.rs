@@ -1,26 +1,54 @@
+use crate::commands::tags::tag_filter_sql;
 use crate::db::get_db;
-use crate::models::{CustomerDebt, ReportSummary, Transaction, TransactionsResponse};
-use rusqlite::Result;
+use crate::models::{
+    CurrencyBalance, CustomerDebt, ReportSummary, Transaction, TransactionsResponse,
+};
+use crate::normalize::like_pattern;
+use rusqlite::{Connection, Result};
+
+/// Outstanding balance of a customer: live invoices minus live payments and
+/// credit notes.
+pub(crate) fn customer_balance(conn: &Connection, customer_id: i32) -> Result<f64, String> {
+    conn.query_row(
+        r#"
+        SELECT
+            IFNULL((SELECT SUM(total * exchange_rate) FROM invoices WHERE customer_id = ?1 AND deleted_at IS NULL AND state <> 'cancelled'), 0)
+          - IFNULL((SELECT SUM(amount * exchange_rate) FROM payments WHERE customer_id = ?1 AND deleted_at IS NULL), 0)
+          - IFNULL((SELECT SUM(total * exchange_rate) FROM live_credit_notes WHERE customer_id = ?1), 0)
+        "#,
+        [customer_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
 
 #[tauri::command]
 pub fn get_all_debts(
     have_zero: Option<bool>,
     search_query: Option<String>,
+    tag_ids: Option<Vec<i32>>,
 ) -> Result<Vec<CustomerDebt>, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
-    let mut where_clauses: Vec<String> = vec![];
+    let mut where_clauses: Vec<String> = vec!["c.deleted_at IS NULL".to_string()];
+
+    if let Some(filter) = tag_filter_sql("c.id", tag_ids.as_deref()) {
+        where_clauses.push(filter);
+    }
 
     // Filter zero debts if needed
     if have_zero.unwrap_or(false) == false {
         where_clauses.push(
-            "(IFNULL(inv.total_invoices, 0) - IFNULL(pay.total_payments, 0)) <> 0".to_string(),
+            "(IFNULL(inv.total_invoices, 0) - IFNULL(pay.total_payments, 0) - IFNULL(cn.total_credits, 0)) <> 0".to_string(),
         );
     }
 
     // Search by name, phone, notes
     let has_search = search_query.is_some();
     if has_search {
-        where_clauses.push("(c.name LIKE ?1 OR c.phone LIKE ?2 OR c.notes LIKE ?3)".to_string());
+        where_clauses.push(
+            "(c.name_normalized LIKE ?1 OR normalize_text(c.phone) LIKE ?2 OR normalize_text(c.notes) LIKE ?3)"
+                .to_string(),
+        );
     }
 
     let where_sql = if !where_clauses.is_empty() {
@@ -37,18 +65,26 @@ pub fn get_all_debts(
             c.phone,
             IFNULL(inv.total_invoices, 0) AS total_invoices,
             IFNULL(pay.total_payments, 0) AS total_payments,
-            (IFNULL(inv.total_invoices, 0) - IFNULL(pay.total_payments, 0)) AS total_debt
+            IFNULL(cn.total_credits, 0) AS total_credits,
+            (IFNULL(inv.total_invoices, 0) - IFNULL(pay.total_payments, 0) - IFNULL(cn.total_credits, 0)) AS total_debt
         FROM customers c
         LEFT JOIN (
-            SELECT customer_id, SUM(total) AS total_invoices
+            SELECT customer_id, SUM(total * exchange_rate) AS total_invoices
             FROM invoices
+            WHERE deleted_at IS NULL AND state <> 'cancelled'
             GROUP BY customer_id
         ) AS inv ON inv.customer_id = c.id
         LEFT JOIN (
-            SELECT customer_id, SUM(amount) AS total_payments
+            SELECT customer_id, SUM(amount * exchange_rate) AS total_payments
             FROM payments
+            WHERE deleted_at IS NULL
             GROUP BY customer_id
         ) AS pay ON pay.customer_id = c.id
+        LEFT JOIN (
+            SELECT customer_id, SUM(total * exchange_rate) AS total_credits
+            FROM live_credit_notes
+            GROUP BY customer_id
+        ) AS cn ON cn.customer_id = c.id
         {}
         ORDER BY total_debt DESC
         "#,
@@ -58,7 +94,7 @@ pub fn get_all_debts(
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
 
     let debts: Vec<CustomerDebt> = if let Some(search) = search_query {
-        let s = format!("%{}%", search);
+        let s = like_pattern(&search);
         let rows = stmt
             .query_map(rusqlite::params![s.clone(), s.clone(), s], |row| {
                 Ok(CustomerDebt {
@@ -67,7 +103,8 @@ pub fn get_all_debts(
                     phone: row.get(2)?,
                     total_invoices: row.get(3)?,
                     total_payments: row.get(4)?,
-                    total_debt: row.get(5)?,
+                    total_credits: row.get(5)?,
+                    total_debt: row.get(6)?,
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -81,7 +118,8 @@ pub fn get_all_debts(
                     phone: row.get(2)?,
                     total_invoices: row.get(3)?,
                     total_payments: row.get(4)?,
-                    total_debt: row.get(5)?,
+                    total_credits: row.get(5)?,
+                    total_debt: row.get(6)?,
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -98,12 +136,15 @@ pub fn get_customer_debt(customer_id: i32) -> Result<f64, String> {
     let sql = r#"
         SELECT 
             SUM(CASE 
-                WHEN status = 'unpaid' THEN total
-                WHEN status = 'partial' THEN total - COALESCE(paid_amount, 0)
+                WHEN status = 'unpaid' THEN total - IFNULL(cn.credited, 0)
+                WHEN status = 'partial' THEN total - COALESCE(paid_amount, 0) - IFNULL(cn.credited, 0)
                 ELSE 0
-            END) AS debt
+            END * exchange_rate) AS debt
         FROM invoices
-        WHERE customer_id = ?
+        LEFT JOIN (
+            SELECT invoice_id, SUM(total) AS credited FROM live_credit_notes GROUP BY invoice_id
+        ) cn ON cn.invoice_id = invoices.id
+        WHERE customer_id = ? AND deleted_at IS NULL AND state <> 'cancelled'
     "#;
 
     let debt: f64 = conn
@@ -113,27 +154,113 @@ pub fn get_customer_debt(customer_id: i32) -> Result<f64, String> {
     Ok(debt)
 }
 
+/// Live invoices, payments and credit notes per currency, for all customers
+/// or one of them, with each balance converted at the rates of its documents.
+#[tauri::command]
+pub fn get_currency_balances(customer_id: Option<i32>) -> Result<Vec<CurrencyBalance>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT
+              currency,
+              SUM(CASE WHEN kind = 'invoice' THEN amount ELSE 0 END),
+              SUM(CASE WHEN kind = 'payment' THEN amount ELSE 0 END),
+              SUM(CASE WHEN kind = 'credit_note' THEN amount ELSE 0 END),
+              SUM(CASE WHEN kind = 'invoice' THEN amount ELSE -amount END),
+              SUM(CASE WHEN kind = 'invoice' THEN amount ELSE -amount END * exchange_rate)
+            FROM (
+              SELECT 'invoice' AS kind, i.customer_id, i.currency, i.total AS amount, i.exchange_rate
+              FROM invoices i
+              WHERE i.deleted_at IS NULL AND i.state <> 'cancelled'
+              UNION ALL
+              SELECT 'payment', p.customer_id, p.currency, p.amount, p.exchange_rate
+              FROM payments p
+              WHERE p.deleted_at IS NULL
+              UNION ALL
+              SELECT 'credit_note', cn.customer_id, cn.currency, cn.total, cn.exchange_rate
+              FROM live_credit_notes cn
+            ) d
+            JOIN customers c ON c.id = d.customer_id
+            WHERE c.deleted_at IS NULL AND (?1 IS NULL OR d.customer_id = ?1)
+            GROUP BY currency
+            ORDER BY currency
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([customer_id], |row| {
+            Ok(CurrencyBalance {
+                currency: row.get(0)?,
+                total_invoices: row.get(1)?,
+                total_payments: row.get(2)?,
+                total_credits: row.get(3)?,
+                balance: row.get(4)?,
+                base_balance: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
-pub fn get_report_summary() -> Result<ReportSummary, String> {
+pub fn get_report_summary(tag_ids: Option<Vec<i32>>) -> Result<ReportSummary, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
 
+    let tag_filter = |column: &str| {
+        tag_filter_sql(column, tag_ids.as_deref())
+            .map(|f| format!(" AND {}", f))
+            .unwrap_or_default()
+    };
+
     let total_invoices: f64 = conn
-        .query_row("SELECT SUM(total) FROM invoices", [], |row| row.get(0))
+        .query_row(
+            &format!("SELECT SUM(total * exchange_rate) FROM invoices WHERE deleted_at IS NULL AND state <> 'cancelled'{}", tag_filter("customer_id")),
+            [],
+            |row| row.get(0),
+        )
         .unwrap_or(0.0);
 
     let total_payments: f64 = conn
-        .query_row("SELECT SUM(amount) FROM payments", [], |row| row.get(0))
+        .query_row(
+            &format!(
+                "SELECT SUM(amount * exchange_rate) FROM payments WHERE deleted_at IS NULL{}",
+                tag_filter("customer_id")
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0.0);
+
+    let total_credits: f64 = conn
+        .query_row(
+            &format!(
+                "SELECT IFNULL(SUM(total * exchange_rate), 0) FROM live_credit_notes WHERE 1 = 1{}",
+                tag_filter("customer_id")
+            ),
+            [],
+            |row| row.get(0),
+        )
         .unwrap_or(0.0);
 
-    let total_debts = total_invoices - total_payments;
+    let total_debts = total_invoices - total_payments - total_credits;
 
     let customer_count: i32 = conn
-        .query_row("SELECT COUNT(*) FROM customers", [], |row| row.get(0))
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM customers WHERE deleted_at IS NULL{}",
+                tag_filter("id")
+            ),
+            [],
+            |row| row.get(0),
+        )
         .unwrap_or(0);
 
     Ok(ReportSummary {
         total_invoices: total_invoices as i32,
         total_payments: total_payments,
+        total_credits,
         total_debts: total_debts,
         customer_count: customer_count,
     })
@@ -150,15 +277,21 @@ pub fn get_transactions(
     let end_date = to_date.clone().unwrap_or_else(|| "9999-12-31".to_string());
 
     let date_condition_invoice = if to_date.is_some() {
-        "AND i.date BETWEEN ?2 AND ?3"
+        "AND i.deleted_at IS NULL AND i.state <> 'cancelled' AND i.date BETWEEN ?2 AND ?3"
     } else {
-        "AND i.date >= ?2"
+        "AND i.deleted_at IS NULL AND i.state <> 'cancelled' AND i.date >= ?2"
     };
 
     let date_condition_payment = if to_date.is_some() {
-        "AND p.date BETWEEN ?2 AND ?3"
+        "AND p.deleted_at IS NULL AND p.date BETWEEN ?2 AND ?3"
     } else {
-        "AND p.date >= ?2"
+        "AND p.deleted_at IS NULL AND p.date >= ?2"
+    };
+
+    let date_condition_credit_note = if to_date.is_some() {
+        "AND cn.date BETWEEN ?2 AND ?3"
+    } else {
+        "AND cn.date >= ?2"
     };
 
     // Get transactions
@@ -172,8 +305,10 @@ pub fn get_transactions(
             c.name AS customer_name,
             c.phone AS customer_phone,
             i.date,
-            i.total AS amount,
-            i.created_at
+            i.total * i.exchange_rate AS amount,
+            i.created_at,
+            i.currency,
+            i.total
         FROM invoices i
         JOIN customers c ON i.customer_id = c.id
         WHERE i.customer_id = ?1
@@ -189,21 +324,42 @@ pub fn get_transactions(
             c.name AS customer_name,
             c.phone AS customer_phone,
             p.date,
-            p.amount,
-            p.created_at
+            p.amount * p.exchange_rate,
+            p.created_at,
+            p.currency,
+            p.amount
         FROM payments p
         JOIN customers c ON p.customer_id = c.id
         WHERE p.customer_id = ?1
           {}
 
-        ORDER BY date ASC
+        UNION ALL
+
+        SELECT
+            cn.id AS record_id,
+            'credit_note' AS type,
+            cn.credit_note_number AS reference,
+            cn.customer_id,
+            c.name AS customer_name,
+            c.phone AS customer_phone,
+            cn.date,
+            cn.total * cn.exchange_rate,
+            cn.created_at,
+            cn.currency,
+            cn.total
+        FROM live_credit_notes cn
+        JOIN customers c ON cn.customer_id = c.id
+        WHERE cn.customer_id = ?1
+          {}
+
+        ORDER BY date ASC, created_at ASC
         "#,
-        date_condition_invoice, date_condition_payment
+        date_condition_invoice, date_condition_payment, date_condition_credit_note
     );
 
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
 
-    let transactions: Vec<Transaction> = if to_date.is_some() {
+    let mut transactions: Vec<Transaction> = if to_date.is_some() {
         let rows = stmt
             .query_map(
                 rusqlite::params![customer_id, &from_date, &end_date],
@@ -218,6 +374,9 @@ pub fn get_transactions(
                         date: row.get(6)?,
                         amount: row.get(7)?,
                         created_at: row.get(8)?,
+                        currency: row.get(9)?,
+                        currency_amount: row.get(10)?,
+                        running_balance: 0.0,
                     })
                 },
             )
@@ -236,6 +395,9 @@ pub fn get_transactions(
                     date: row.get(6)?,
                     amount: row.get(7)?,
                     created_at: row.get(8)?,
+                    currency: row.get(9)?,
+                    currency_amount: row.get(10)?,
+                    running_balance: 0.0,
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -246,39 +408,68 @@ pub fn get_transactions(
     let summary_sql = if to_date.is_some() {
         r#"
         SELECT
-            IFNULL((SELECT SUM(total) FROM invoices WHERE customer_id = ?1 AND date BETWEEN ?2 AND ?3), 0) AS total_invoices,
-            IFNULL((SELECT SUM(amount) FROM payments WHERE customer_id = ?1 AND date BETWEEN ?2 AND ?3), 0) AS total_payments
+            IFNULL((SELECT SUM(total * exchange_rate) FROM invoices WHERE customer_id = ?1 AND deleted_at IS NULL AND state <> 'cancelled' AND date BETWEEN ?2 AND ?3), 0) AS total_invoices,
+            IFNULL((SELECT SUM(amount * exchange_rate) FROM payments WHERE customer_id = ?1 AND deleted_at IS NULL AND date BETWEEN ?2 AND ?3), 0) AS total_payments,
+            IFNULL((SELECT SUM(total * exchange_rate) FROM live_credit_notes WHERE customer_id = ?1 AND date BETWEEN ?2 AND ?3), 0) AS total_credits
         "#
     } else {
         r#"
         SELECT
-            IFNULL((SELECT SUM(total) FROM invoices WHERE customer_id = ?1 AND date >= ?2), 0) AS total_invoices,
-            IFNULL((SELECT SUM(amount) FROM payments WHERE customer_id = ?1 AND date >= ?2), 0) AS total_payments
+            IFNULL((SELECT SUM(total * exchange_rate) FROM invoices WHERE customer_id = ?1 AND deleted_at IS NULL AND state <> 'cancelled' AND date >= ?2), 0) AS total_invoices,
+            IFNULL((SELECT SUM(amount * exchange_rate) FROM payments WHERE customer_id = ?1 AND deleted_at IS NULL AND date >= ?2), 0) AS total_payments,
+            IFNULL((SELECT SUM(total * exchange_rate) FROM live_credit_notes WHERE customer_id = ?1 AND date >= ?2), 0) AS total_credits
         "#
     };
 
-    let (total_invoices, total_payments): (f64, f64) = if to_date.is_some() {
+    let (total_invoices, total_payments, total_credits): (f64, f64, f64) = if to_date.is_some() {
         conn.query_row(
             summary_sql,
             rusqlite::params![customer_id, &from_date, &end_date],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
-        .unwrap_or((0.0, 0.0))
+        .unwrap_or((0.0, 0.0, 0.0))
     } else {
         conn.query_row(
             summary_sql,
             rusqlite::params![customer_id, &from_date],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
-        .unwrap_or((0.0, 0.0))
+        .unwrap_or((0.0, 0.0, 0.0))
     };
 
-    let remaining_total = total_invoices - total_payments;
+    let remaining_total = total_invoices - total_payments - total_credits;
+
+    // Everything before the period is carried over as the opening balance
+    let opening_balance: f64 = conn
+        .query_row(
+            r#"
+            SELECT
+                IFNULL((SELECT SUM(total * exchange_rate) FROM invoices WHERE customer_id = ?1 AND deleted_at IS NULL AND state <> 'cancelled' AND date < ?2), 0)
+              - IFNULL((SELECT SUM(amount * exchange_rate) FROM payments WHERE customer_id = ?1 AND deleted_at IS NULL AND date < ?2), 0)
+              - IFNULL((SELECT SUM(total * exchange_rate) FROM live_credit_notes WHERE customer_id = ?1 AND date < ?2), 0)
+            "#,
+            rusqlite::params![customer_id, &from_date],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut balance = opening_balance;
+    for t in transactions.iter_mut() {
+        if t.transaction_type == "payment" || t.transaction_type == "credit_note" {
+            balance -= t.amount;
+        } else {
+            balance += t.amount;
+        }
+        t.running_balance = balance;
+    }
 
     Ok(TransactionsResponse {
         data: transactions,
         total_invoices,
         total_payments,
+        total_credits,
         remaining_total,
+        opening_balance,
+        closing_balance: opening_balance + remaining_total,
     })
 }
@@ -0,0 +1,364 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::numbering::next_document_number;
+use crate::commands::units::check_quantity;
+use crate::db::get_db;
+use crate::error::CommandError;
+use crate::models::{DeliveryNote, DeliveryNoteItem, DeliveryNoteLine, DeliveryStatus};
+use crate::terms::parse_date;
+use chrono::Datelike;
+use rusqlite::{params, Connection, OptionalExtension, Result, ToSql};
+
+//
+// ==================== Helpers ====================
+//
+
+const DELIVERY_NOTE_SELECT: &str = r#"
+    SELECT
+      dn.id,
+      dn.delivery_note_number,
+      dn.invoice_id,
+      i.invoice_number,
+      dn.customer_id,
+      c.name,
+      dn.date,
+      dn.notes,
+      dn.created_at
+    FROM delivery_notes dn
+    LEFT JOIN invoices i ON i.id = dn.invoice_id
+    LEFT JOIN customers c ON c.id = dn.customer_id
+"#;
+
+fn delivery_note_from_row(row: &rusqlite::Row) -> rusqlite::Result<DeliveryNote> {
+    Ok(DeliveryNote {
+        id: row.get(0)?,
+        delivery_note_number: row.get(1)?,
+        invoice_id: row.get(2)?,
+        invoice_number: row.get(3)?,
+        customer_id: row.get(4)?,
+        customer_name: row.get(5)?,
+        date: row.get(6)?,
+        notes: row.get(7)?,
+        created_at: row.get(8)?,
+        items: Vec::new(),
+    })
+}
+
+/// Ordered and delivered quantities of each line of an invoice.
+pub(crate) fn delivery_status(
+    conn: &Connection,
+    invoice_id: i32,
+) -> Result<Vec<DeliveryStatus>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT it.id, it.product_name, it.quantity, IFNULL(SUM(di.quantity), 0), it.unit_id, u.name
+            FROM invoice_items it
+            LEFT JOIN delivery_note_items di ON di.invoice_item_id = it.id
+            LEFT JOIN units u ON u.id = it.unit_id
+            WHERE it.invoice_id = ?1
+            GROUP BY it.id
+            ORDER BY it.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([invoice_id], |row| {
+            let ordered: f64 = row.get(2)?;
+            let delivered: f64 = row.get(3)?;
+            Ok(DeliveryStatus {
+                invoice_item_id: row.get(0)?,
+                product_name: row.get(1)?,
+                unit_id: row.get(4)?,
+                unit_name: row.get(5)?,
+                ordered_quantity: ordered,
+                delivered_quantity: delivered,
+                remaining_quantity: (ordered - delivered).max(0.0),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+fn delivery_note_items(
+    conn: &Connection,
+    delivery_note_id: i32,
+) -> Result<Vec<DeliveryNoteItem>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT di.id, di.invoice_item_id, di.product_name, di.quantity, u.name
+            FROM delivery_note_items di
+            LEFT JOIN invoice_items it ON it.id = di.invoice_item_id
+            LEFT JOIN units u ON u.id = it.unit_id
+            WHERE di.delivery_note_id = ?1 ORDER BY di.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([delivery_note_id], |row| {
+            Ok(DeliveryNoteItem {
+                id: row.get(0)?,
+                invoice_item_id: row.get(1)?,
+                product_name: row.get(2)?,
+                quantity: row.get(3)?,
+                unit_name: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// A delivery note with its lines.
+pub(crate) fn delivery_note_with_items(conn: &Connection, id: i32) -> Result<DeliveryNote, String> {
+    let mut delivery_note = conn
+        .query_row(
+            &format!("{} WHERE dn.id = ?1", DELIVERY_NOTE_SELECT),
+            [id],
+            delivery_note_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("وصل التسليم غير موجود")?;
+    delivery_note.items = delivery_note_items(conn, id)?;
+    Ok(delivery_note)
+}
+
+// (product, unit, ordered, delivered, remaining)
+type PrintedLine = (String, Option<String>, f64, f64, f64);
+
+/// Printed quantities of each line of a delivery note, the remaining
+/// quantity counting this note and earlier ones only so reprints stay the
+/// same.
+pub(crate) fn delivery_note_lines(conn: &Connection, id: i32) -> Result<Vec<PrintedLine>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT di.product_name, it.quantity, di.quantity,
+              it.quantity - (
+                SELECT IFNULL(SUM(prev.quantity), 0)
+                FROM delivery_note_items prev
+                WHERE prev.invoice_item_id = di.invoice_item_id AND prev.delivery_note_id <= di.delivery_note_id
+              ),
+              u.name
+            FROM delivery_note_items di
+            LEFT JOIN invoice_items it ON it.id = di.invoice_item_id
+            LEFT JOIN units u ON u.id = it.unit_id
+            WHERE di.delivery_note_id = ?1
+            ORDER BY di.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([id], |row| {
+            let delivered: f64 = row.get(2)?;
+            Ok((
+                row.get(0)?,
+                row.get(4)?,
+                row.get::<_, Option<f64>>(1)?.unwrap_or(delivered),
+                delivered,
+                row.get::<_, Option<f64>>(3)?.unwrap_or(0.0).max(0.0),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Records a delivery against an invoice. `lines` lists the quantities
+/// delivered per invoice line; without it everything not yet delivered is.
+#[tauri::command]
+pub fn create_delivery_note(
+    invoice_id: i32,
+    date: Option<String>,
+    notes: Option<String>,
+    lines: Option<Vec<DeliveryNoteLine>>,
+) -> Result<DeliveryNote, CommandError> {
+    let date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let year = parse_date(&date)?.year();
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (customer_id, state): (i32, String) = tx
+        .query_row(
+            "SELECT customer_id, state FROM invoices WHERE id = ?1 AND deleted_at IS NULL",
+            [invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    // Draft lines may still change under the delivered quantities
+    match state.as_str() {
+        "draft" => return Err("لا يمكن إصدار وصل تسليم لفاتورة غير مُصدرة".into()),
+        "cancelled" => return Err("لا يمكن إصدار وصل تسليم لفاتورة ملغاة".into()),
+        _ => {}
+    }
+
+    let status = delivery_status(&tx, invoice_id)?;
+    let requested: Vec<(usize, f64)> = match lines {
+        Some(lines) => lines
+            .iter()
+            .map(|line| {
+                let index = status
+                    .iter()
+                    .position(|s| s.invoice_item_id == line.invoice_item_id)
+                    .ok_or_else(|| {
+                        format!("البند {} لا ينتمي إلى هذه الفاتورة", line.invoice_item_id)
+                    })?;
+                Ok((index, line.quantity))
+            })
+            .collect::<Result<_, String>>()?,
+        None => status
+            .iter()
+            .enumerate()
+            .map(|(index, s)| (index, s.remaining_quantity))
+            .filter(|(_, quantity)| *quantity > 1e-9)
+            .collect(),
+    };
+    if requested.is_empty() {
+        return Err("لا يوجد ما يمكن تسليمه من هذه الفاتورة".into());
+    }
+
+    let mut items: Vec<DeliveryNoteItem> = Vec::new();
+    for (index, quantity) in requested {
+        let line = &status[index];
+        if quantity <= 0.0 {
+            return Err("يجب أن تكون الكمية المسلمة أكبر من صفر".into());
+        }
+        check_quantity(&tx, line.unit_id, quantity)?;
+        // The same line may be listed more than once
+        let listed: f64 = items
+            .iter()
+            .filter(|i| i.invoice_item_id == Some(line.invoice_item_id))
+            .map(|i| i.quantity)
+            .sum();
+        let remaining = line.remaining_quantity - listed;
+        if quantity > remaining + 1e-9 {
+            return Err(format!(
+                "الكمية المسلمة من {} تتجاوز الكمية المتبقية ({})",
+                line.product_name, remaining
+            )
+            .into());
+        }
+        items.push(DeliveryNoteItem {
+            id: None,
+            invoice_item_id: Some(line.invoice_item_id),
+            product_name: line.product_name.clone(),
+            quantity,
+            unit_name: line.unit_name.clone(),
+        });
+    }
+
+    let number = next_document_number(&tx, "delivery_notes", "delivery_note_number", "BL", year)?;
+    tx.execute(
+        "INSERT INTO delivery_notes (delivery_note_number, invoice_id, customer_id, date, notes) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            number,
+            invoice_id,
+            customer_id,
+            date,
+            notes.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid() as i32;
+
+    for item in &items {
+        tx.execute(
+            "INSERT INTO delivery_note_items (delivery_note_id, invoice_item_id, product_name, quantity) VALUES (?1, ?2, ?3, ?4)",
+            params![id, item.invoice_item_id, item.product_name, item.quantity],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let after = snapshot(&tx, "delivery_notes", id as i64)?;
+    record_audit(
+        &tx,
+        "delivery_note",
+        id as i64,
+        "create",
+        None,
+        after.as_ref(),
+    )?;
+
+    let delivery_note = delivery_note_with_items(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(delivery_note)
+}
+
+/// Delivery notes, newest first, optionally for one customer or one invoice.
+#[tauri::command]
+pub fn get_delivery_notes(
+    customer_id: Option<i32>,
+    invoice_id: Option<i32>,
+) -> Result<Vec<DeliveryNote>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut conditions = vec!["i.deleted_at IS NULL".to_string()];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(customer_id) = customer_id {
+        params.push(Box::new(customer_id));
+        conditions.push(format!("dn.customer_id = ?{}", params.len()));
+    }
+    if let Some(invoice_id) = invoice_id {
+        params.push(Box::new(invoice_id));
+        conditions.push(format!("dn.invoice_id = ?{}", params.len()));
+    }
+
+    let sql = format!(
+        "{} WHERE {} ORDER BY dn.date DESC, dn.id DESC",
+        DELIVERY_NOTE_SELECT,
+        conditions.join(" AND ")
+    );
+    let params_refs: Vec<&dyn ToSql> = params.iter().map(|b| &**b as &dyn ToSql).collect();
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_refs.as_slice(), delivery_note_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_delivery_note(id: i32) -> Result<DeliveryNote, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    delivery_note_with_items(&conn, id)
+}
+
+/// What is left to deliver on each line of an invoice.
+#[tauri::command]
+pub fn get_invoice_delivery_status(invoice_id: i32) -> Result<Vec<DeliveryStatus>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    delivery_status(&conn, invoice_id)
+}
+
+/// Deletes a delivery note entered by mistake; its quantities become
+/// deliverable again.
+#[tauri::command]
+pub fn delete_delivery_note(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "delivery_notes", id as i64)?;
+    if before.is_none() {
+        return Err("وصل التسليم غير موجود".into());
+    }
+    tx.execute("DELETE FROM delivery_notes WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(
+        &tx,
+        "delivery_note",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        None,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
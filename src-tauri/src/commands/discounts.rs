@@ -0,0 +1,116 @@
+use crate::db::get_db;
+use crate::models::DiscountTotal;
+use rusqlite::{Result, ToSql};
+
+//
+// ==================== Helpers ====================
+//
+
+const DISCOUNT_TYPES: &[&str] = &["percent", "fixed"];
+
+pub(crate) fn validate_discount(discount_type: Option<&str>, value: f64) -> Result<(), String> {
+    match discount_type {
+        None => Ok(()),
+        Some(t) if !DISCOUNT_TYPES.contains(&t) => Err(format!("نوع الخصم غير صالح: {}", t)),
+        Some("percent") if !(0.0..=100.0).contains(&value) => {
+            Err("يجب أن تكون نسبة الخصم بين 0 و 100".into())
+        }
+        Some(_) if value < 0.0 => Err("قيمة الخصم لا يمكن أن تكون سالبة".into()),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Amount taken off `amount` by a discount. A fixed discount never exceeds
+/// the amount it applies to.
+pub(crate) fn discount_amount(amount: f64, discount_type: Option<&str>, value: f64) -> f64 {
+    let discount = match discount_type {
+        Some("percent") => amount * value / 100.0,
+        Some("fixed") => value.min(amount),
+        _ => 0.0,
+    };
+    (discount.max(0.0) * 100.0).round() / 100.0
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Discounts given on the invoices of the period, per salesperson or per
+/// customer (`group_by` = "salesperson" | "customer").
+#[tauri::command]
+pub fn get_discounts_report(
+    from_date: String,
+    to_date: Option<String>,
+    group_by: String,
+) -> Result<Vec<DiscountTotal>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let (group_sql, name_sql) = match group_by.as_str() {
+        "salesperson" => ("IFNULL(i.salesperson, '')", "IFNULL(i.salesperson, '')"),
+        "customer" => ("i.customer_id", "c.name"),
+        _ => return Err(format!("طريقة التجميع غير صالحة: {}", group_by)),
+    };
+
+    let mut params: Vec<Box<dyn ToSql>> = vec![Box::new(from_date)];
+    let mut date_sql = "i.date >= ?1".to_string();
+    if let Some(to_date) = to_date {
+        params.push(Box::new(to_date));
+        date_sql = "i.date BETWEEN ?1 AND ?2".to_string();
+    }
+
+    let sql = format!(
+        r#"
+        SELECT
+          {group} AS group_key,
+          {name} AS name,
+          COUNT(*) AS invoice_count,
+          SUM(IFNULL(l.gross, IFNULL(i.subtotal, i.total) + i.discount_amount) * i.exchange_rate) AS gross_amount,
+          IFNULL(SUM(l.discounts * i.exchange_rate), 0) AS line_discounts,
+          SUM(i.discount_amount * i.exchange_rate) AS invoice_discounts
+        FROM invoices i
+        LEFT JOIN customers c ON c.id = i.customer_id
+        LEFT JOIN (
+          SELECT
+            invoice_id,
+            SUM(ROUND(unit_price * quantity, 2)) AS gross,
+            SUM(discount_amount) AS discounts
+          FROM invoice_items
+          GROUP BY invoice_id
+        ) l ON l.invoice_id = i.id
+        WHERE i.deleted_at IS NULL AND i.state <> 'cancelled' AND {date}
+        GROUP BY group_key
+        HAVING line_discounts > 0 OR invoice_discounts > 0
+        ORDER BY line_discounts + invoice_discounts DESC
+        "#,
+        group = group_sql,
+        name = name_sql,
+        date = date_sql
+    );
+
+    let params_refs: Vec<&dyn ToSql> = params.iter().map(|b| &**b as &dyn ToSql).collect();
+    let by_customer = group_by == "customer";
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_refs.as_slice(), |row| {
+            let line_discounts: f64 = row.get(4)?;
+            let invoice_discounts: f64 = row.get(5)?;
+            Ok(DiscountTotal {
+                customer_id: if by_customer { row.get(0)? } else { None },
+                salesperson: if by_customer {
+                    None
+                } else {
+                    row.get::<_, Option<String>>(0)?.filter(|s| !s.is_empty())
+                },
+                name: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                invoice_count: row.get(2)?,
+                gross_amount: row.get(3)?,
+                line_discounts,
+                invoice_discounts,
+                total_discount: line_discounts + invoice_discounts,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
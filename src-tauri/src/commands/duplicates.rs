@@ -0,0 +1,267 @@
+use crate::commands::audit::{record_audit, snapshot, snapshot_customer};
+use crate::commands::customers::{customer_from_row, CUSTOMER_COLUMNS};
+use crate::db::get_db;
+use crate::models::{Customer, DuplicateGroup};
+use crate::normalize::normalize_text;
+use rusqlite::{params, Result};
+use serde_json::json;
+use std::collections::HashMap;
+
+//
+// ==================== Helpers ====================
+//
+
+/// Comparable form of a phone number: its last nine digits, which drops
+/// the leading 0 or country code.
+fn phone_key(phone: &str) -> Option<String> {
+    let digits: String = normalize_text(phone)
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+    if digits.len() < 6 {
+        return None;
+    }
+    Some(digits[digits.len().saturating_sub(9)..].to_string())
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Similarity of two normalized names in [0, 1], ignoring word order and
+/// repeated spaces.
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let sorted = |s: &str| {
+        let mut words: Vec<&str> = s.split_whitespace().collect();
+        words.sort_unstable();
+        words.join(" ").chars().collect::<Vec<char>>()
+    };
+    let (a, b) = (sorted(a), sorted(b));
+    let len = a.len().max(b.len());
+    if len == 0 {
+        return 0.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / len as f64
+}
+
+fn find_root(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[i] = root;
+    root
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn find_duplicate_customers(min_score: Option<f64>) -> Result<Vec<DuplicateGroup>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let min_score = min_score.unwrap_or(0.8);
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}, name_normalized FROM customers WHERE deleted_at IS NULL ORDER BY id",
+            CUSTOMER_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(Customer, String)> = stmt
+        .query_map([], |row| {
+            Ok((
+                customer_from_row(row)?,
+                row.get::<_, Option<String>>("name_normalized")?
+                    .unwrap_or_default(),
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|x| x.ok())
+        .collect();
+
+    let phones: Vec<Option<String>> = rows
+        .iter()
+        .map(|(c, _)| phone_key(c.phone_e164.as_deref().unwrap_or(&c.phone)))
+        .collect();
+
+    // Link every pair scoring above the threshold; linked customers form a group
+    let mut parent: Vec<usize> = (0..rows.len()).collect();
+    let mut best: HashMap<(usize, usize), f64> = HashMap::new();
+    for i in 0..rows.len() {
+        for j in (i + 1)..rows.len() {
+            let same_phone = phones[i].is_some() && phones[i] == phones[j];
+            let score = if same_phone {
+                1.0
+            } else {
+                name_similarity(&rows[i].1, &rows[j].1)
+            };
+            if score >= min_score {
+                best.insert((i, j), score);
+                let (ri, rj) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                parent[rj] = ri;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..rows.len() {
+        let root = find_root(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+
+    let mut scores: HashMap<usize, f64> = HashMap::new();
+    for ((i, _), score) in &best {
+        let root = find_root(&mut parent, *i);
+        let entry = scores.entry(root).or_insert(0.0);
+        *entry = entry.max(*score);
+    }
+
+    let mut rows: Vec<Option<Customer>> = rows.into_iter().map(|(c, _)| Some(c)).collect();
+    let mut result: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| DuplicateGroup {
+            score: scores.get(&root).copied().unwrap_or(0.0),
+            customers: members.iter().filter_map(|i| rows[*i].take()).collect(),
+        })
+        .collect();
+
+    result.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn merge_customers(keep_id: i32, merge_ids: Vec<i32>) -> Result<(), String> {
+    let merge_ids: Vec<i32> = merge_ids.into_iter().filter(|id| *id != keep_id).collect();
+    if merge_ids.is_empty() {
+        return Err("لم يتم تحديد عملاء للدمج".into());
+    }
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let keep_before = snapshot(&tx, "customers", keep_id as i64)?
+        .ok_or_else(|| "العميل غير موجود".to_string())?;
+
+    let mut notes: Vec<String> = keep_before["notes"]
+        .as_str()
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| vec![n.to_string()])
+        .unwrap_or_default();
+    let mut merged = vec![];
+
+    for id in &merge_ids {
+        let before = snapshot_customer(&tx, *id as i64)?
+            .ok_or_else(|| format!("العميل رقم {} غير موجود", id))?;
+
+        if let Some(n) = before["notes"].as_str() {
+            if !n.trim().is_empty() && !notes.iter().any(|x| x == n) {
+                notes.push(n.to_string());
+            }
+        }
+
+        tx.execute(
+            "UPDATE invoices SET customer_id = ?1 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE payments SET customer_id = ?1 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE credit_notes SET customer_id = ?1 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE quotes SET customer_id = ?1 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE delivery_notes SET customer_id = ?1 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE recurring_invoices SET customer_id = ?1 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        // Contacts and addresses move over too; the kept customer's primaries stay
+        tx.execute(
+            "UPDATE customer_contacts SET customer_id = ?1, is_primary = 0 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE customer_addresses SET customer_id = ?1, is_primary = 0 WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT OR IGNORE INTO customer_tags (customer_id, tag_id) SELECT ?1, tag_id FROM customer_tags WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        // Custom field values fill in the ones the kept customer doesn't have
+        tx.execute(
+            "INSERT OR IGNORE INTO customer_field_values (customer_id, field_id, value) SELECT ?1, field_id, value FROM customer_field_values WHERE customer_id = ?2",
+            params![keep_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM customers WHERE id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+
+        record_audit(
+            &tx,
+            "customer",
+            *id as i64,
+            "merge",
+            Some(&before),
+            Some(&json!({ "merged_into": keep_id })),
+        )?;
+        merged.push(before);
+    }
+
+    let merged_notes = if notes.is_empty() {
+        None
+    } else {
+        Some(notes.join("\n"))
+    };
+    tx.execute(
+        "UPDATE customers SET notes = ?1 WHERE id = ?2",
+        params![merged_notes, keep_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = snapshot(&tx, "customers", keep_id as i64)?;
+    record_audit(
+        &tx,
+        "customer",
+        keep_id as i64,
+        "merge",
+        Some(&json!({ "customer": keep_before, "merged": merged })),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -1,7 +1,152 @@
-use rusqlite::{params, OptionalExtension, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 
+use crate::commands::audit::{record_audit, snapshot, snapshot_invoice, snapshot_rows};
+use crate::commands::contacts::{check_address_owner, primary_address_id};
+use crate::commands::credit::{check_credit, check_invoice_credit, customer_due_date};
+use crate::commands::currencies::document_currency;
+use crate::commands::discounts::validate_discount;
+use crate::commands::lifecycle::ensure_draft;
+use crate::commands::numbering::new_invoice_number;
+use crate::commands::products::{catalog_price, catalog_unit};
+use crate::commands::tags::tag_filter_sql;
+use crate::commands::taxes::{
+    document_totals, line_amounts, line_tax_rate, refresh_invoice_totals,
+};
+use crate::commands::units::{check_quantity, convert};
+use crate::currency::currency_decimals;
 use crate::db::get_db;
+use crate::error::CommandError;
 use crate::models::{Invoice, InvoiceItem, InvoiceWithCustomer, InvoicesResponse, Product};
+use crate::normalize::{like_pattern, normalize_text};
+use crate::terms::parse_date;
+
+// Invoice columns plus customer details. The printed address is the
+// invoice's billing address, falling back to the customer's own address.
+// An invoice is overdue when it is not fully paid after its due date.
+// Voided invoices are listed but never count towards totals. An invoice is
+// fully delivered once delivery notes cover every line.
+const INVOICE_WITH_CUSTOMER_SELECT: &str = r#"
+    SELECT
+      i.id,
+      i.invoice_number,
+      i.customer_id,
+      i.date,
+      i.total,
+      i.status,
+      i.paid_amount,
+      i.created_at,
+      c.name AS customer_name,
+      c.phone AS customer_phone,
+      COALESCE(ba.address, c.address) AS customer_address,
+      da.address AS delivery_address,
+      i.billing_address_id,
+      i.delivery_address_id,
+      i.due_date,
+      (i.state <> 'cancelled' AND IFNULL(i.status, 'unpaid') <> 'paid' AND i.due_date < date('now', 'localtime')) AS overdue,
+      IFNULL(i.subtotal, i.total) AS subtotal,
+      i.tax_total,
+      i.discount_type,
+      i.discount_value,
+      i.discount_amount,
+      i.salesperson,
+      i.state,
+      i.issued_at,
+      i.void_reason,
+      i.quote_id,
+      (EXISTS (SELECT 1 FROM invoice_items it WHERE it.invoice_id = i.id)
+       AND NOT EXISTS (
+         SELECT 1 FROM invoice_items it
+         WHERE it.invoice_id = i.id
+           AND it.quantity > (SELECT IFNULL(SUM(dn.quantity), 0) FROM delivery_note_items dn WHERE dn.invoice_item_id = it.id) + 1e-9
+       )) AS fully_delivered,
+      i.currency,
+      i.exchange_rate
+    FROM invoices i
+    LEFT JOIN customers c ON i.customer_id = c.id
+    LEFT JOIN customer_addresses ba ON ba.id = i.billing_address_id
+    LEFT JOIN customer_addresses da ON da.id = i.delivery_address_id
+"#;
+
+fn invoice_with_customer_from_row(row: &rusqlite::Row) -> rusqlite::Result<InvoiceWithCustomer> {
+    Ok(InvoiceWithCustomer {
+        id: row.get(0)?,
+        invoice_number: row.get(1)?,
+        customer_id: row.get(2)?,
+        customer_name: row.get(8)?,
+        customer_phone: row.get(9)?,
+        customer_address: row.get(10)?,
+        delivery_address: row.get(11)?,
+        billing_address_id: row.get(12)?,
+        delivery_address_id: row.get(13)?,
+        date: row.get(3)?,
+        due_date: row.get(14)?,
+        subtotal: row.get(16)?,
+        tax_total: row.get(17)?,
+        discount_type: row.get(18)?,
+        discount_value: row.get(19)?,
+        discount_amount: row.get(20)?,
+        salesperson: row.get(21)?,
+        total: row.get(4)?,
+        status: row.get(5)?,
+        state: row.get(22)?,
+        issued_at: row.get(23)?,
+        void_reason: row.get(24)?,
+        quote_id: row.get(25)?,
+        currency: row.get(27)?,
+        exchange_rate: row.get(28)?,
+        overdue: row.get(15)?,
+        fully_delivered: row.get(26)?,
+        paid_amount: row.get(6)?,
+        remaining_amount: None,
+        created_at: row.get(7)?,
+    })
+}
+
+pub(crate) fn invoice_with_customer(
+    conn: &rusqlite::Connection,
+    id: i32,
+) -> Result<Option<InvoiceWithCustomer>, String> {
+    conn.query_row(
+        &format!("{} WHERE i.id = ?1", INVOICE_WITH_CUSTOMER_SELECT),
+        [id],
+        invoice_with_customer_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Filters of the invoices report, as an SQL condition on `i` and its
+/// parameters.
+pub(crate) fn report_conditions(
+    from_date: String,
+    to_date: Option<String>,
+    customer_id: Option<i32>,
+    tag_ids: Option<Vec<i32>>,
+) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    // قاعدة التاريخ
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(from_date)];
+    let mut conditions = vec!["i.deleted_at IS NULL".to_string()];
+
+    if let Some(end_date) = to_date {
+        params.push(Box::new(end_date));
+        conditions.push("i.date BETWEEN ?1 AND ?2".to_string());
+    } else {
+        conditions.push("i.date >= ?1".to_string());
+    }
+
+    // قاعدة العميل (اختيارية)
+    if let Some(cid) = customer_id {
+        params.push(Box::new(cid));
+        conditions.push(format!("i.customer_id = ?{}", params.len()));
+    }
+
+    // الوسوم (اختيارية)
+    if let Some(filter) = tag_filter_sql("i.customer_id", tag_ids.as_deref()) {
+        conditions.push(filter);
+    }
+
+    (conditions.join(" AND "), params)
+}
 
 //
 // ==================== Commands ====================
@@ -12,42 +157,49 @@ pub fn get_all_invoices(
     search_query: Option<String>,
     page: Option<i32>,
     page_size: Option<i32>,
+    tag_ids: Option<Vec<i32>>,
 ) -> Result<InvoicesResponse, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
 
     // Build WHERE clause and params
-    let mut where_clause = String::new();
+    let mut where_clause = "WHERE i.deleted_at IS NULL".to_string();
+
+    if let Some(filter) = tag_filter_sql("i.customer_id", tag_ids.as_deref()) {
+        where_clause.push_str(&format!(" AND {}", filter));
+    }
 
     if search_query.is_some() {
-        where_clause =
-            "WHERE i.invoice_number LIKE ?1 OR c.name LIKE ?2 OR i.date LIKE ?3".to_string();
+        where_clause.push_str(
+            " AND (normalize_text(i.invoice_number) LIKE ?1 OR c.name_normalized LIKE ?2 OR i.date LIKE ?3)",
+        );
     }
 
     // 1) SUM total (no LIMIT/OFFSET)
     let sum_sql = format!(
-        "SELECT IFNULL(SUM(i.total), 0) as sumTotal FROM invoices i LEFT JOIN customers c ON i.customer_id = c.id {}",
+        "SELECT IFNULL(SUM(i.total * i.exchange_rate), 0) as sumTotal, IFNULL(SUM(IFNULL(i.subtotal, i.total) * i.exchange_rate), 0), IFNULL(SUM(i.tax_total * i.exchange_rate), 0) FROM invoices i LEFT JOIN customers c ON i.customer_id = c.id {} AND i.state <> 'cancelled'",
         where_clause
     );
+    let sums = |row: &rusqlite::Row| -> rusqlite::Result<(f64, f64, f64)> {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    };
 
-    let sum_total: f64 = if let Some(ref s) = search_query {
-        let s1 = format!("%{}%", s);
-        let s2 = format!("%{}%", s);
-        let s3 = format!("{}%", s);
-        conn.query_row(&sum_sql, params![s1, s2, s3], |row| row.get::<_, f64>(0))
+    let (sum_total, sum_subtotal, sum_tax) = if let Some(ref s) = search_query {
+        let s1 = like_pattern(s);
+        let s2 = like_pattern(s);
+        let s3 = format!("{}%", normalize_text(s));
+        conn.query_row(&sum_sql, params![s1, s2, s3], sums)
             .map_err(|e| e.to_string())?
     } else {
-        conn.query_row(&sum_sql, params![], |row| row.get::<_, f64>(0))
+        conn.query_row(&sum_sql, params![], sums)
             .map_err(|e| e.to_string())?
     };
 
     // 2) Paginated query
     let mut sql = format!(
-        "SELECT i.id, i.invoice_number, i.customer_id, i.date, i.total, i.status, i.paid_amount, i.created_at, c.name as customer_name, c.phone as customer_phone, c.address as customer_address
-         FROM invoices i
-         LEFT JOIN customers c ON i.customer_id = c.id
+        "{}
          {}
          ORDER BY i.created_at DESC",
-        where_clause
+        INVOICE_WITH_CUSTOMER_SELECT, where_clause
     );
 
     let mut invoices: Vec<InvoiceWithCustomer> = Vec::new();
@@ -57,27 +209,15 @@ pub fn get_all_invoices(
         let offset = (p - 1) * size;
         if let Some(ref s) = search_query {
             sql.push_str(" LIMIT ?4 OFFSET ?5");
-            let s1 = format!("%{}%", s);
-            let s2 = format!("%{}%", s);
-            let s3 = format!("{}%", s);
+            let s1 = like_pattern(s);
+            let s2 = like_pattern(s);
+            let s3 = format!("{}%", normalize_text(s));
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![s1, s2, s3, size, offset], |row| {
-                    Ok(InvoiceWithCustomer {
-                        id: row.get(0)?,
-                        invoice_number: row.get(1)?,
-                        customer_id: row.get(2)?,
-                        customer_name: row.get(8)?,
-                        customer_phone: row.get(9)?,
-                        date: row.get(3)?,
-                        total: row.get(4)?,
-                        status: row.get::<_, Option<String>>(5)?,
-                        paid_amount: row.get::<_, Option<f64>>(6)?,
-                        remaining_amount: None,
-                        created_at: row.get::<_, Option<String>>(7)?,
-                        customer_address: row.get::<_, Option<String>>(10)?,
-                    })
-                })
+                .query_map(
+                    params![s1, s2, s3, size, offset],
+                    invoice_with_customer_from_row,
+                )
                 .map_err(|e| e.to_string())?;
 
             for r in rows {
@@ -89,22 +229,7 @@ pub fn get_all_invoices(
             sql.push_str(" LIMIT ?1 OFFSET ?2");
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![size, offset], |row| {
-                    Ok(InvoiceWithCustomer {
-                        id: row.get(0)?,
-                        invoice_number: row.get(1)?,
-                        customer_id: row.get(2)?,
-                        customer_name: row.get(8)?,
-                        customer_phone: row.get(9)?,
-                        date: row.get(3)?,
-                        total: row.get(4)?,
-                        status: row.get::<_, Option<String>>(5)?,
-                        paid_amount: row.get::<_, Option<f64>>(6)?,
-                        remaining_amount: None,
-                        created_at: row.get::<_, Option<String>>(7)?,
-                        customer_address: row.get::<_, Option<String>>(10)?,
-                    })
-                })
+                .query_map(params![size, offset], invoice_with_customer_from_row)
                 .map_err(|e| e.to_string())?;
 
             for r in rows {
@@ -116,27 +241,12 @@ pub fn get_all_invoices(
     } else {
         // no pagination
         if let Some(ref s) = search_query {
-            let s1 = format!("%{}%", s);
-            let s2 = format!("%{}%", s);
-            let s3 = format!("{}%", s);
+            let s1 = like_pattern(s);
+            let s2 = like_pattern(s);
+            let s3 = format!("{}%", normalize_text(s));
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![s1, s2, s3], |row| {
-                    Ok(InvoiceWithCustomer {
-                        id: row.get(0)?,
-                        invoice_number: row.get(1)?,
-                        customer_id: row.get(2)?,
-                        customer_name: row.get(8)?,
-                        customer_phone: row.get(9)?,
-                        date: row.get(3)?,
-                        total: row.get(4)?,
-                        status: row.get::<_, Option<String>>(5)?,
-                        paid_amount: row.get::<_, Option<f64>>(6)?,
-                        remaining_amount: None,
-                        created_at: row.get::<_, Option<String>>(7)?,
-                        customer_address: row.get::<_, Option<String>>(10)?,
-                    })
-                })
+                .query_map(params![s1, s2, s3], invoice_with_customer_from_row)
                 .map_err(|e| e.to_string())?;
 
             for r in rows {
@@ -147,22 +257,7 @@ pub fn get_all_invoices(
         } else {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map([], |row| {
-                    Ok(InvoiceWithCustomer {
-                        id: row.get(0)?,
-                        invoice_number: row.get(1)?,
-                        customer_id: row.get(2)?,
-                        customer_name: row.get(8)?,
-                        customer_phone: row.get(9)?,
-                        date: row.get(3)?,
-                        total: row.get(4)?,
-                        status: row.get::<_, Option<String>>(5)?,
-                        paid_amount: row.get::<_, Option<f64>>(6)?,
-                        remaining_amount: None,
-                        created_at: row.get::<_, Option<String>>(7)?,
-                        customer_address: row.get::<_, Option<String>>(10)?,
-                    })
-                })
+                .query_map([], invoice_with_customer_from_row)
                 .map_err(|e| e.to_string())?;
 
             for r in rows {
@@ -178,7 +273,11 @@ pub fn get_all_invoices(
         .into_iter()
         .map(|mut inv| {
             let paid = inv.paid_amount.unwrap_or(0.0);
-            inv.remaining_amount = Some(inv.total - paid);
+            inv.remaining_amount = if inv.state.as_deref() == Some("cancelled") {
+                Some(0.0)
+            } else {
+                Some(inv.total - paid)
+            };
             inv
         })
         .collect();
@@ -186,6 +285,8 @@ pub fn get_all_invoices(
     Ok(InvoicesResponse {
         data: invoices,
         total: sum_total,
+        subtotal: sum_subtotal,
+        tax_total: sum_tax,
     })
 }
 
@@ -194,7 +295,7 @@ pub fn get_invoice_by_id(id: i32) -> Result<Option<Invoice>, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, invoice_number, customer_id, date, total, status, paid_amount, created_at FROM invoices WHERE id = ?")
+        .prepare("SELECT id, invoice_number, customer_id, date, total, status, paid_amount, created_at, billing_address_id, delivery_address_id, due_date, IFNULL(subtotal, total), tax_total, discount_type, discount_value, discount_amount, salesperson, state, issued_at, void_reason, quote_id, currency, exchange_rate FROM invoices WHERE id = ?")
         .map_err(|e| e.to_string())?;
 
     let res = stmt
@@ -205,11 +306,26 @@ pub fn get_invoice_by_id(id: i32) -> Result<Option<Invoice>, String> {
                 customer_id: row.get(2)?,
                 customer_name: None,
                 date: row.get(3)?,
+                due_date: row.get(10)?,
+                subtotal: row.get(11)?,
+                tax_total: row.get(12)?,
+                discount_type: row.get(13)?,
+                discount_value: row.get(14)?,
+                discount_amount: row.get(15)?,
+                salesperson: row.get(16)?,
                 total: row.get(4)?,
                 status: row.get::<_, Option<String>>(5)?,
+                state: row.get(17)?,
+                issued_at: row.get(18)?,
+                void_reason: row.get(19)?,
+                quote_id: row.get(20)?,
+                currency: row.get(21)?,
+                exchange_rate: row.get(22)?,
                 paid_amount: row.get::<_, Option<f64>>(6)?,
                 remaining_amount: None,
                 created_at: row.get::<_, Option<String>>(7)?,
+                billing_address_id: row.get(8)?,
+                delivery_address_id: row.get(9)?,
             })
         })
         .optional()
@@ -229,19 +345,40 @@ pub fn get_invoices_by_customer_id(customer_id: i32) -> Result<Vec<Invoice>, Str
       i.date,
       i.total,
       IFNULL(p.paid, 0) AS paid_amount,
-      (i.total - IFNULL(p.paid, 0)) AS remaining_amount,
+      CASE WHEN i.state = 'cancelled' THEN 0
+           ELSE i.total - IFNULL(p.paid, 0) - IFNULL(cn.credited, 0) END AS remaining_amount,
       i.customer_id,
       i.status,
       i.paid_amount,
-      i.created_at
+      i.created_at,
+      i.billing_address_id,
+      i.delivery_address_id,
+      i.due_date,
+      IFNULL(i.subtotal, i.total) AS subtotal,
+      i.tax_total,
+      i.discount_type,
+      i.discount_value,
+      i.discount_amount,
+      i.salesperson,
+      i.state,
+      i.issued_at,
+      i.void_reason,
+      i.quote_id,
+      i.currency,
+      i.exchange_rate
     FROM invoices i
     LEFT JOIN (
       SELECT invoice_id, SUM(amount) AS paid
       FROM payments
-      WHERE invoice_id IS NOT NULL
+      WHERE invoice_id IS NOT NULL AND deleted_at IS NULL
       GROUP BY invoice_id
     ) p ON p.invoice_id = i.id
-    WHERE i.customer_id = ?
+    LEFT JOIN (
+      SELECT invoice_id, SUM(total) AS credited
+      FROM live_credit_notes
+      GROUP BY invoice_id
+    ) cn ON cn.invoice_id = i.id
+    WHERE i.customer_id = ? AND i.deleted_at IS NULL
     ORDER BY i.date DESC;
     "#;
 
@@ -254,11 +391,26 @@ pub fn get_invoices_by_customer_id(customer_id: i32) -> Result<Vec<Invoice>, Str
                 customer_id: row.get::<_, Option<i32>>(6)?.unwrap_or(customer_id),
                 customer_name: None,
                 date: row.get(2)?,
+                due_date: row.get(12)?,
+                subtotal: row.get(13)?,
+                tax_total: row.get(14)?,
+                discount_type: row.get(15)?,
+                discount_value: row.get(16)?,
+                discount_amount: row.get(17)?,
+                salesperson: row.get(18)?,
                 total: row.get(3)?,
                 status: row.get::<_, Option<String>>(7)?,
+                state: row.get(19)?,
+                issued_at: row.get(20)?,
+                void_reason: row.get(21)?,
+                quote_id: row.get(22)?,
+                currency: row.get(23)?,
+                exchange_rate: row.get(24)?,
                 paid_amount: row.get::<_, Option<f64>>(4)?,
                 remaining_amount: row.get::<_, Option<f64>>(5)?,
                 created_at: row.get::<_, Option<String>>(9)?,
+                billing_address_id: row.get(10)?,
+                delivery_address_id: row.get(11)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -275,7 +427,7 @@ pub fn get_invoices_by_customer_id(customer_id: i32) -> Result<Vec<Invoice>, Str
 #[tauri::command]
 pub fn get_invoice_items(invoiceId: i32) -> Result<Vec<InvoiceItem>, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
-    let mut stmt = conn.prepare("SELECT id, invoice_id, product_name, unit_price, quantity, total FROM invoice_items WHERE invoice_id = ?")
+    let mut stmt = conn.prepare("SELECT it.id, it.invoice_id, it.product_name, it.unit_price, it.quantity, it.total, it.tax_rate, it.price_includes_tax, IFNULL(it.subtotal, it.total), it.tax_amount, it.discount_type, it.discount_value, it.discount_amount, it.unit_id, u.name FROM invoice_items it LEFT JOIN units u ON u.id = it.unit_id WHERE it.invoice_id = ? ORDER BY it.id")
         .map_err(|e| e.to_string())?;
     let rows = stmt
         .query_map(params![invoiceId], |row| {
@@ -285,6 +437,15 @@ pub fn get_invoice_items(invoiceId: i32) -> Result<Vec<InvoiceItem>, String> {
                 product_name: row.get(2)?,
                 unit_price: row.get(3)?,
                 quantity: row.get(4)?,
+                unit_id: row.get(13)?,
+                unit_name: row.get(14)?,
+                tax_rate: row.get(6)?,
+                price_includes_tax: row.get(7)?,
+                discount_type: row.get(10)?,
+                discount_value: row.get(11)?,
+                discount_amount: row.get(12)?,
+                subtotal: row.get(8)?,
+                tax_amount: row.get(9)?,
                 total: row.get(5)?,
             })
         })
@@ -306,9 +467,9 @@ pub fn get_products_grouped(search_query: Option<String>) -> Result<Vec<Product>
     let mut sql = "SELECT MIN(id) AS id, product_name AS name FROM invoice_items".to_string();
 
     if let Some(ref s) = search_query {
-        sql.push_str(" WHERE product_name LIKE ?1");
+        sql.push_str(" WHERE normalize_text(product_name) LIKE ?1");
         sql.push_str(" GROUP BY product_name ORDER BY name");
-        let pattern = format!("%{}%", s);
+        let pattern = like_pattern(s);
         let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
         let rows = stmt
             .query_map(params![pattern], |row| {
@@ -316,6 +477,12 @@ pub fn get_products_grouped(search_query: Option<String>) -> Result<Vec<Product>
                     id: row.get(0)?,
                     name: row.get(1)?,
                     unit_price: None,
+                    unit_id: None,
+                    unit_name: None,
+                    track_stock: false,
+                    low_stock_threshold: None,
+                    cost_price: None,
+                    stock: None,
                     created_at: None,
                 })
             })
@@ -337,6 +504,12 @@ pub fn get_products_grouped(search_query: Option<String>) -> Result<Vec<Product>
                     id: row.get(0)?,
                     name: row.get(1)?,
                     unit_price: None,
+                    unit_id: None,
+                    unit_name: None,
+                    track_stock: false,
+                    low_stock_threshold: None,
+                    cost_price: None,
+                    stock: None,
                     created_at: None,
                 })
             })
@@ -352,68 +525,424 @@ pub fn get_products_grouped(search_query: Option<String>) -> Result<Vec<Product>
     }
 }
 
-#[tauri::command]
-pub fn create_invoice(invoice: Invoice) -> Result<i32, String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+/// Inserts a draft invoice inside the caller's transaction, with the same
+/// checks and defaults as `create_invoice`.
+pub(crate) fn insert_invoice(
+    tx: &Connection,
+    invoice: Invoice,
+    override_credit_limit: bool,
+    override_reason: Option<String>,
+) -> Result<i32, CommandError> {
+    validate_discount(invoice.discount_type.as_deref(), invoice.discount_value)?;
+    let (currency, exchange_rate) = document_currency(
+        tx,
+        invoice.currency.as_deref(),
+        invoice.exchange_rate,
+        &invoice.date,
+    )?;
 
-    conn.execute(
-        "INSERT INTO invoices (invoice_number, customer_id, date, total, status, paid_amount) VALUES (?, ?, ?, ?, ?, ?)",
+    // Whatever is not paid up front is added to the customer's debt
+    let credit_override = check_credit(
+        tx,
+        invoice.customer_id,
+        (invoice.total - invoice.paid_amount.unwrap_or(0.0)) * exchange_rate,
+        override_credit_limit,
+        override_reason,
+    )?;
+    let override_reason = credit_override
+        .as_ref()
+        .and_then(|v| v["reason"].as_str().map(String::from));
+
+    // Without an explicit choice the customer's primary addresses are used
+    let billing_address_id = match invoice.billing_address_id {
+        Some(id) => Some(check_address_owner(tx, id, invoice.customer_id)?),
+        None => primary_address_id(tx, invoice.customer_id, "billing")?,
+    };
+    let delivery_address_id = match invoice.delivery_address_id {
+        Some(id) => Some(check_address_owner(tx, id, invoice.customer_id)?),
+        None => primary_address_id(tx, invoice.customer_id, "delivery")?,
+    };
+
+    // Without an explicit due date the customer's payment terms apply
+    let due_date = match invoice.due_date {
+        Some(d) => parse_date(&d)?.format("%Y-%m-%d").to_string(),
+        None => customer_due_date(tx, invoice.customer_id, &invoice.date)?,
+    };
+
+    tx.execute(
+        "INSERT INTO invoices (invoice_number, customer_id, date, due_date, subtotal, tax_total, discount_type, discount_value, discount_amount, salesperson, total, status, paid_amount, billing_address_id, delivery_address_id, credit_override_reason, quote_id, currency, exchange_rate, state) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft')",
         params![
             invoice.invoice_number,
             invoice.customer_id,
             invoice.date,
+            due_date,
+            invoice.subtotal.unwrap_or(invoice.total),
+            invoice.tax_total.unwrap_or(0.0),
+            invoice.discount_type,
+            invoice.discount_value,
+            invoice.discount_amount.unwrap_or(0.0),
+            invoice.salesperson.as_deref().map(str::trim).filter(|s| !s.is_empty()),
             invoice.total,
             invoice.status.unwrap_or("unpaid".to_string()),
-            invoice.paid_amount.unwrap_or(0.0)
+            invoice.paid_amount.unwrap_or(0.0),
+            billing_address_id,
+            delivery_address_id,
+            override_reason,
+            invoice.quote_id,
+            currency,
+            exchange_rate
         ],
     ).map_err(|e| e.to_string())?;
 
-    let id = conn.last_insert_rowid() as i32;
+    let id = tx.last_insert_rowid() as i32;
+
+    let after = snapshot(tx, "invoices", id as i64)?;
+    record_audit(tx, "invoice", id as i64, "create", None, after.as_ref())?;
+    if let Some(details) = credit_override {
+        record_audit(
+            tx,
+            "invoice",
+            id as i64,
+            "credit_override",
+            None,
+            Some(&details),
+        )?;
+    }
+
     Ok(id)
 }
 
 #[tauri::command]
-pub fn create_invoice_item(item: InvoiceItem) -> Result<InvoiceItem, String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+pub fn create_invoice(
+    invoice: Invoice,
+    override_credit_limit: Option<bool>,
+    override_reason: Option<String>,
+) -> Result<i32, CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let id = insert_invoice(
+        &tx,
+        invoice,
+        override_credit_limit.unwrap_or(false),
+        override_reason,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Adds a line to an invoice inside the caller's transaction and refreshes
+/// the invoice totals.
+pub(crate) fn insert_invoice_item(
+    tx: &Connection,
+    item: InvoiceItem,
+) -> Result<InvoiceItem, String> {
+    validate_discount(item.discount_type.as_deref(), item.discount_value)?;
+    let unit_id = match item.unit_id {
+        Some(id) => Some(id),
+        None => catalog_unit(tx, &item.product_name)?,
+    };
+    check_quantity(tx, unit_id, item.quantity)?;
+    let tax_rate = line_tax_rate(tx, item.tax_rate)?;
+
+    // Amounts are computed here so the lines and the invoice always agree
+    let amounts = line_amounts(
+        item.unit_price,
+        item.quantity,
+        item.discount_type.as_deref(),
+        item.discount_value,
+        tax_rate,
+        item.price_includes_tax,
+    );
 
-    conn.execute(
-        "INSERT INTO invoice_items (invoice_id, product_name, unit_price, quantity, total) VALUES (?, ?, ?, ?, ?)",
-        params![item.invoice_id, item.product_name, item.unit_price, item.quantity, item.total],
+    tx.execute(
+        "INSERT INTO invoice_items (invoice_id, product_name, unit_price, quantity, unit_id, discount_type, discount_value, discount_amount, tax_rate, price_includes_tax, subtotal, tax_amount, total) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            item.invoice_id,
+            item.product_name,
+            item.unit_price,
+            item.quantity,
+            unit_id,
+            item.discount_type,
+            item.discount_value,
+            amounts.discount,
+            tax_rate,
+            item.price_includes_tax,
+            amounts.subtotal,
+            amounts.tax,
+            amounts.total
+        ],
     ).map_err(|e| e.to_string())?;
 
-    let id = conn.last_insert_rowid() as i32;
+    let id = tx.last_insert_rowid() as i32;
+    refresh_invoice_totals(tx, item.invoice_id)?;
+
+    // Line changes are recorded against the parent invoice so they show up
+    // in its history
+    let after = snapshot(tx, "invoice_items", id as i64)?;
+    record_audit(
+        tx,
+        "invoice",
+        item.invoice_id as i64,
+        "add_item",
+        None,
+        after.as_ref(),
+    )?;
     Ok(InvoiceItem {
         id: Some(id),
         invoice_id: item.invoice_id,
         product_name: item.product_name,
         unit_price: item.unit_price,
         quantity: item.quantity,
-        total: item.total,
+        unit_name: match unit_id {
+            Some(id) => tx
+                .query_row("SELECT name FROM units WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .map_err(|e| e.to_string())?,
+            None => None,
+        },
+        unit_id,
+        tax_rate: Some(tax_rate),
+        price_includes_tax: item.price_includes_tax,
+        discount_type: item.discount_type,
+        discount_value: item.discount_value,
+        discount_amount: Some(amounts.discount),
+        subtotal: Some(amounts.subtotal),
+        tax_amount: Some(amounts.tax),
+        total: amounts.total,
     })
 }
 
+#[tauri::command]
+pub fn create_invoice_item(item: InvoiceItem) -> Result<InvoiceItem, String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    ensure_draft(&tx, item.invoice_id)?;
+
+    let item = insert_invoice_item(&tx, item)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(item)
+}
+
+/// Copies an invoice and its lines into a new draft dated `new_date` with a
+/// fresh number, for repeat orders. It can be made out to another customer
+/// and take its prices from the product catalog; products missing from the
+/// catalog keep their old price. Returns the new invoice id.
+#[tauri::command]
+pub fn duplicate_invoice(
+    id: i32,
+    new_date: String,
+    customer_id: Option<i32>,
+    refresh_prices: Option<bool>,
+    override_credit_limit: Option<bool>,
+    override_reason: Option<String>,
+) -> Result<i32, CommandError> {
+    let new_date = parse_date(&new_date)?.format("%Y-%m-%d").to_string();
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Invoices in the recycle bin cannot be copied, nor voided ones
+    let state: String = tx
+        .query_row(
+            "SELECT state FROM invoices WHERE id = ?1 AND deleted_at IS NULL",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    if state == "cancelled" {
+        return Err("لا يمكن نسخ فاتورة ملغاة".into());
+    }
+    let source = invoice_with_customer(&tx, id)?.ok_or("الفاتورة غير موجودة")?;
+    let same_customer = customer_id.map_or(true, |c| c == source.customer_id);
+
+    let mut stmt = tx
+        .prepare(
+            "SELECT product_name, unit_price, quantity, tax_rate, price_includes_tax, discount_type, discount_value, unit_id FROM invoice_items WHERE invoice_id = ?1 ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut items = stmt
+        .query_map([id], |row| {
+            Ok(InvoiceItem {
+                id: None,
+                invoice_id: 0,
+                product_name: row.get(0)?,
+                unit_price: row.get(1)?,
+                quantity: row.get(2)?,
+                unit_id: row.get(7)?,
+                unit_name: None,
+                tax_rate: row.get(3)?,
+                price_includes_tax: row.get(4)?,
+                discount_type: row.get(5)?,
+                discount_value: row.get(6)?,
+                discount_amount: None,
+                subtotal: None,
+                tax_amount: None,
+                total: 0.0,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+
+    // The copy is in the same currency, at the rate of its own date
+    let (currency, exchange_rate) =
+        document_currency(&tx, Some(&source.currency), None, &new_date)?;
+
+    if refresh_prices.unwrap_or(false) {
+        // Catalog prices are in the base currency and per the product's
+        // unit; a line in another unit of the same base is priced pro rata
+        let scale = 10f64.powi(currency_decimals(&currency) as i32);
+        for item in &mut items {
+            let Some(price) = catalog_price(&tx, &item.product_name)? else {
+                continue;
+            };
+            let per_line_unit = match (item.unit_id, catalog_unit(&tx, &item.product_name)?) {
+                (Some(line_unit), Some(product_unit)) => {
+                    match convert(&tx, 1.0, line_unit, product_unit) {
+                        Ok(ratio) => ratio,
+                        Err(_) => continue,
+                    }
+                }
+                _ => 1.0,
+            };
+            item.unit_price = (price * per_line_unit / exchange_rate * scale).round() / scale;
+        }
+    }
+
+    // The credit check needs the new total before the lines exist; rates
+    // are resolved the way insert_invoice_item will resolve them
+    let (mut lines_subtotal, mut lines_tax) = (0.0, 0.0);
+    for item in &items {
+        let amounts = line_amounts(
+            item.unit_price,
+            item.quantity,
+            item.discount_type.as_deref(),
+            item.discount_value,
+            line_tax_rate(&tx, item.tax_rate)?,
+            item.price_includes_tax,
+        );
+        lines_subtotal += amounts.subtotal;
+        lines_tax += amounts.tax;
+    }
+    let total = if items.is_empty() {
+        source.total
+    } else {
+        document_totals(
+            lines_subtotal,
+            lines_tax,
+            source.discount_type.as_deref(),
+            source.discount_value,
+        )
+        .total
+    };
+
+    let new_id = insert_invoice(
+        &tx,
+        Invoice {
+            invoice_number: new_invoice_number(&tx)?,
+            customer_id: customer_id.unwrap_or(source.customer_id),
+            date: new_date,
+            subtotal: if items.is_empty() {
+                source.subtotal
+            } else {
+                None
+            },
+            tax_total: if items.is_empty() {
+                source.tax_total
+            } else {
+                None
+            },
+            discount_type: source.discount_type,
+            discount_value: source.discount_value,
+            salesperson: source.salesperson,
+            total,
+            currency: Some(currency),
+            exchange_rate: Some(exchange_rate),
+            // Another customer gets their own primary addresses
+            billing_address_id: source.billing_address_id.filter(|_| same_customer),
+            delivery_address_id: source.delivery_address_id.filter(|_| same_customer),
+            ..Default::default()
+        },
+        override_credit_limit.unwrap_or(false),
+        override_reason,
+    )?;
+    for item in items {
+        insert_invoice_item(
+            &tx,
+            InvoiceItem {
+                invoice_id: new_id,
+                ..item
+            },
+        )?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(new_id)
+}
+
 #[tauri::command]
 pub fn delete_invoice_items(invoiceId: i32) -> Result<(), String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
-    conn.execute(
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    ensure_draft(&tx, invoiceId)?;
+
+    let before = snapshot_rows(
+        &tx,
+        "SELECT * FROM invoice_items WHERE invoice_id = ?1 ORDER BY id",
+        &[&invoiceId],
+    )?;
+
+    tx.execute(
         "DELETE FROM invoice_items WHERE invoice_id = ?",
         params![invoiceId],
     )
     .map_err(|e| e.to_string())?;
+
+    if !before.is_empty() {
+        let before = serde_json::Value::Array(before);
+        record_audit(
+            &tx,
+            "invoice",
+            invoiceId as i64,
+            "delete_items",
+            Some(&before),
+            None,
+        )?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn update_invoice(
     id: i32,
     invoice_number: Option<String>,
     customer_id: Option<i32>,
     date: Option<String>,
-    total: Option<f64>,
-    status: Option<String>,
-    paid_amount: Option<f64>,
-) -> Result<(), String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+    billing_address_id: Option<i32>,
+    delivery_address_id: Option<i32>,
+    due_date: Option<String>,
+    discount_type: Option<String>,
+    discount_value: Option<f64>,
+    salesperson: Option<String>,
+    currency: Option<String>,
+    exchange_rate: Option<f64>,
+    override_credit_limit: Option<bool>,
+    override_reason: Option<String>,
+) -> Result<(), CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Issued invoices are frozen; their payments set what was paid
+    ensure_draft(&tx, id)?;
 
     let mut fields: Vec<String> = vec![];
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![];
@@ -426,21 +955,83 @@ pub fn update_invoice(
         fields.push("customer_id = ?".to_string());
         params_vec.push(Box::new(v));
     }
+    // A new date takes the rate of that day unless a rate is entered
+    if currency.is_some() || exchange_rate.is_some() || date.is_some() {
+        let (current_currency, current_date): (String, String) = tx
+            .query_row(
+                "SELECT currency, date FROM invoices WHERE id = ?",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        let (currency, exchange_rate) = document_currency(
+            &tx,
+            Some(currency.as_deref().unwrap_or(&current_currency)),
+            exchange_rate,
+            date.as_deref().unwrap_or(&current_date),
+        )?;
+        // Payments and credit notes are kept in the invoice's currency
+        if currency != current_currency {
+            let linked: bool = tx
+                .query_row(
+                    r#"
+                    SELECT EXISTS (SELECT 1 FROM payments WHERE invoice_id = ?1 AND deleted_at IS NULL)
+                        OR EXISTS (SELECT 1 FROM live_credit_notes WHERE invoice_id = ?1)
+                    "#,
+                    [id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            if linked {
+                return Err("لا يمكن تغيير عملة فاتورة لها دفعات أو إشعارات دائنة".into());
+            }
+        }
+        fields.push("currency = ?".to_string());
+        params_vec.push(Box::new(currency));
+        fields.push("exchange_rate = ?".to_string());
+        params_vec.push(Box::new(exchange_rate));
+    }
     if let Some(v) = date {
         fields.push("date = ?".to_string());
         params_vec.push(Box::new(v));
     }
-    if let Some(v) = total {
-        fields.push("total = ?".to_string());
-        params_vec.push(Box::new(v));
+    if let Some(v) = due_date {
+        fields.push("due_date = ?".to_string());
+        params_vec.push(Box::new(parse_date(&v)?.format("%Y-%m-%d").to_string()));
     }
-    if let Some(v) = status {
-        fields.push("status = ?".to_string());
-        params_vec.push(Box::new(v));
+    // Type and value are replaced together; a missing value clears the discount
+    if discount_type.is_some() || discount_value.is_some() {
+        let value = discount_value.unwrap_or(0.0);
+        validate_discount(discount_type.as_deref(), value)?;
+        fields.push("discount_type = ?".to_string());
+        params_vec.push(Box::new(discount_type));
+        fields.push("discount_value = ?".to_string());
+        params_vec.push(Box::new(value));
     }
-    if let Some(v) = paid_amount {
-        fields.push("paid_amount = ?".to_string());
-        params_vec.push(Box::new(v));
+    if let Some(v) = salesperson {
+        let v = v.trim().to_string();
+        fields.push("salesperson = ?".to_string());
+        params_vec.push(Box::new(if v.is_empty() { None } else { Some(v) }));
+    }
+    if billing_address_id.is_some() || delivery_address_id.is_some() {
+        let owner: i32 = match customer_id {
+            Some(v) => v,
+            None => tx
+                .query_row(
+                    "SELECT customer_id FROM invoices WHERE id = ?",
+                    [id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?,
+        };
+        if let Some(v) = billing_address_id {
+            fields.push("billing_address_id = ?".to_string());
+            params_vec.push(Box::new(check_address_owner(&tx, v, owner)?));
+        }
+        if let Some(v) = delivery_address_id {
+            fields.push("delivery_address_id = ?".to_string());
+            params_vec.push(Box::new(check_address_owner(&tx, v, owner)?));
+        }
     }
 
     if fields.is_empty() {
@@ -457,25 +1048,72 @@ pub fn update_invoice(
         .map(|b| &**b as &dyn rusqlite::ToSql)
         .collect();
 
-    conn.execute(&sql, params_refs.as_slice())
+    let before = snapshot(&tx, "invoices", id as i64)?;
+
+    tx.execute(&sql, params_refs.as_slice())
         .map_err(|e| e.to_string())?;
+    // Amounts always come from the lines, never from the client
+    refresh_invoice_totals(&tx, id)?;
+    // The new customer takes on the invoice's debt
+    let customer_changed = customer_id.is_some()
+        && before.as_ref().and_then(|b| b["customer_id"].as_i64()) != customer_id.map(i64::from);
+    if customer_changed {
+        check_invoice_credit(
+            &tx,
+            id,
+            override_credit_limit.unwrap_or(false),
+            override_reason,
+        )?;
+    }
+
+    let after = snapshot(&tx, "invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "invoice",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
 pub fn delete_invoice(id: i32) -> Result<(), String> {
-    let conn = get_db().map_err(|e| e.to_string())?;
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    // Delete invoice items first due to foreign key constraint
-    conn.execute(
-        "DELETE FROM invoice_items WHERE invoice_id = ?",
-        params![id],
+    // Issued invoices keep their number: they are voided, not deleted
+    let state: String = tx
+        .query_row("SELECT state FROM invoices WHERE id = ?1", [id], |row| {
+            row.get(0)
+        })
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    if state != "draft" {
+        return Err("لا يمكن حذف فاتورة مُصدرة، قم بإلغائها بدلاً من ذلك".into());
+    }
+    let before = snapshot_invoice(&tx, id as i64)?;
+
+    // Items are kept so the invoice can be restored from the recycle bin
+    tx.execute(
+        "UPDATE invoices SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![chrono::Utc::now().to_rfc3339(), id],
     )
     .map_err(|e| e.to_string())?;
 
-    // Then delete the invoice
-    conn.execute("DELETE FROM invoices WHERE id = ?", params![id])
-        .map_err(|e| e.to_string())?;
+    let after = snapshot(&tx, "invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "invoice",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -485,137 +1123,51 @@ pub fn get_invoices(
     from_date: String,
     to_date: Option<String>,
     customer_id: Option<i32>, // ← إضافة هذا
+    tag_ids: Option<Vec<i32>>,
 ) -> Result<InvoicesResponse, String> {
     let conn = get_db().map_err(|e| e.to_string())?;
 
-    let end_date = to_date.clone().unwrap_or_else(|| "9999-12-31".to_string());
-
-    // قاعدة التاريخ
-    let mut conditions = String::new();
-
-    if to_date.is_some() {
-        conditions.push_str("i.date BETWEEN ?1 AND ?2");
-    } else {
-        conditions.push_str("i.date >= ?1");
-    }
-
-    // قاعدة العميل (اختيارية)
-    if customer_id.is_some() {
-        conditions.push_str(" AND i.customer_id = ?3");
-    }
+    let (where_sql, params) = report_conditions(from_date, to_date, customer_id, tag_ids);
 
     // SQL النهائي
     let sql = format!(
         r#"
-        SELECT 
-          i.id,
-          i.invoice_number,
-          i.customer_id,
-          i.date,
-          i.total,
-          i.status,
-          i.paid_amount,
-          i.created_at,
-          c.name AS customer_name,
-          c.phone AS customer_phone,
-          c.address AS customer_address
-        FROM invoices i
-        LEFT JOIN customers c ON i.customer_id = c.id
+        {}
         WHERE {}
         ORDER BY i.date ASC
         "#,
-        conditions
+        INVOICE_WITH_CUSTOMER_SELECT, where_sql
     );
 
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
 
-    // Build params for query
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-    params.push(Box::new(from_date.clone()));
-    if to_date.is_some() {
-        params.push(Box::new(end_date.clone()));
-    }
-    if let Some(cid) = customer_id {
-        params.push(Box::new(cid));
-    }
     let params_refs: Vec<&dyn rusqlite::ToSql> = params
         .iter()
         .map(|b| &**b as &dyn rusqlite::ToSql)
         .collect();
 
     let invoices = stmt
-        .query_map(params_refs.as_slice(), |row| {
-            Ok(InvoiceWithCustomer {
-                id: row.get(0)?,
-                invoice_number: row.get(1)?,
-                customer_id: row.get::<_, i32>(2)?,
-                customer_name: row.get::<_, Option<String>>(8)?,
-                customer_phone: row.get::<_, Option<String>>(9)?,
-                date: row.get(3)?,
-                total: row.get(4)?,
-                status: row.get::<_, Option<String>>(5)?,
-                paid_amount: row.get::<_, Option<f64>>(6)?,
-                remaining_amount: None,
-                created_at: row.get::<_, Option<String>>(7)?,
-                customer_address: row.get::<_, Option<String>>(10)?,
-            })
-        })
+        .query_map(params_refs.as_slice(), invoice_with_customer_from_row)
         .map_err(|e| e.to_string())?
         .filter_map(|x| x.ok())
         .collect::<Vec<_>>();
 
-    // ملخص (بدون تغيير)
-    let summary_sql = if to_date.is_some() {
-        if customer_id.is_some() {
-            r#"
-            SELECT IFNULL(SUM(total), 0)
-            FROM invoices
-            WHERE date BETWEEN ?1 AND ?2 AND customer_id = ?3
-            "#
-        } else {
-            r#"
-            SELECT IFNULL(SUM(total), 0)
-            FROM invoices
-            WHERE date BETWEEN ?1 AND ?2
-            "#
-        }
-    } else {
-        if customer_id.is_some() {
-            r#"
-            SELECT IFNULL(SUM(total), 0)
-            FROM invoices
-            WHERE date >= ?1 AND customer_id = ?2
-            "#
-        } else {
-            r#"
-            SELECT IFNULL(SUM(total), 0)
-            FROM invoices
-            WHERE date >= ?1
-            "#
-        }
-    };
-
-    let mut total_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-    total_params.push(Box::new(from_date.clone()));
-    if to_date.is_some() {
-        total_params.push(Box::new(end_date.clone()));
-    }
-    if let Some(cid) = customer_id {
-        total_params.push(Box::new(cid));
-    }
-    let total_params_refs: Vec<&dyn rusqlite::ToSql> = total_params
-        .iter()
-        .map(|b| &**b as &dyn rusqlite::ToSql)
-        .collect();
+    // ملخص
+    let summary_sql = format!(
+        "SELECT IFNULL(SUM(i.total * i.exchange_rate), 0), IFNULL(SUM(IFNULL(i.subtotal, i.total) * i.exchange_rate), 0), IFNULL(SUM(i.tax_total * i.exchange_rate), 0) FROM invoices i WHERE {} AND i.state <> 'cancelled'",
+        where_sql
+    );
 
-    let total_invoices: f64 = conn
-        .query_row(summary_sql, total_params_refs.as_slice(), |row| {
-            Ok(row.get(0)?)
+    let (total_invoices, subtotal, tax_total): (f64, f64, f64) = conn
+        .query_row(&summary_sql, params_refs.as_slice(), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
         })
-        .unwrap_or(0.0);
+        .unwrap_or((0.0, 0.0, 0.0));
 
     Ok(InvoicesResponse {
         data: invoices,
         total: total_invoices,
+        subtotal,
+        tax_total,
     })
 }
@@ -0,0 +1,290 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::credit::check_invoice_credit;
+use crate::commands::stock::{record_invoice_stock, reverse_invoice_stock};
+use crate::commands::taxes::refresh_invoice_totals;
+use crate::db::get_db;
+use crate::error::CommandError;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use sha2::{Digest, Sha256};
+
+//
+// ==================== Helpers ====================
+//
+
+/// Fails unless the invoice is still a draft. Issued invoices are only
+/// corrected through credit notes.
+pub(crate) fn ensure_draft(conn: &Connection, invoice_id: i32) -> Result<(), String> {
+    let (number, state): (String, String) = conn
+        .query_row(
+            "SELECT invoice_number, state FROM invoices WHERE id = ?1",
+            [invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+
+    match state.as_str() {
+        "draft" => Ok(()),
+        "cancelled" => Err(format!("الفاتورة {} ملغاة", number)),
+        _ => Err(format!(
+            "الفاتورة {} مُصدرة ولا يمكن تعديلها، استخدم إشعاراً دائناً",
+            number
+        )),
+    }
+}
+
+/// Moves an issued invoice between "issued" and "paid" to follow its
+/// payment status. Drafts are left alone.
+pub(crate) fn sync_paid_state(conn: &Connection, invoice_id: i32) -> Result<(), String> {
+    conn.execute(
+        r#"
+        UPDATE invoices
+        SET state = CASE WHEN status = 'paid' THEN 'paid' ELSE 'issued' END
+        WHERE id = ?1 AND state IN ('issued', 'paid')
+        "#,
+        [invoice_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Sets what was paid on an issued invoice from its live payments, and its
+/// status and state from that. Drafts keep the amounts they were given.
+pub(crate) fn sync_invoice_payments(conn: &Connection, invoice_id: i32) -> Result<(), String> {
+    let paid: f64 = conn
+        .query_row(
+            "SELECT IFNULL(SUM(amount), 0) FROM payments WHERE invoice_id = ?1 AND deleted_at IS NULL",
+            [invoice_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        r#"
+        UPDATE invoices
+        SET paid_amount = ?1,
+            status = CASE
+              WHEN ?1 + IFNULL((SELECT SUM(total) FROM live_credit_notes WHERE invoice_id = ?2), 0)
+                   >= total - 0.005 THEN 'paid'
+              WHEN ?1 > 0.005 THEN 'partial'
+              ELSE 'unpaid'
+            END
+        WHERE id = ?2 AND state IN ('issued', 'paid')
+        "#,
+        params![paid, invoice_id],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_paid_state(conn, invoice_id)
+}
+
+/// SHA-256 of what was issued: number, dates, amounts and lines. The
+/// customer is left out so merging duplicate customers keeps it valid.
+pub(crate) fn invoice_content_hash(conn: &Connection, invoice_id: i32) -> Result<String, String> {
+    let invoice: serde_json::Value = conn
+        .query_row(
+            r#"
+            SELECT invoice_number, date, due_date, discount_type, discount_value,
+                   discount_amount, IFNULL(subtotal, total), IFNULL(tax_total, 0), total
+            FROM invoices WHERE id = ?1
+            "#,
+            [invoice_id],
+            |row| {
+                Ok(serde_json::json!({
+                    "invoice_number": row.get::<_, String>(0)?,
+                    "date": row.get::<_, String>(1)?,
+                    "due_date": row.get::<_, Option<String>>(2)?,
+                    "discount_type": row.get::<_, Option<String>>(3)?,
+                    "discount_value": row.get::<_, f64>(4)?,
+                    "discount_amount": row.get::<_, f64>(5)?,
+                    "subtotal": row.get::<_, f64>(6)?,
+                    "tax_total": row.get::<_, f64>(7)?,
+                    "total": row.get::<_, f64>(8)?,
+                }))
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT product_name, unit_price, quantity, discount_type, discount_value,
+                   tax_rate, price_includes_tax, subtotal, tax_amount, total
+            FROM invoice_items WHERE invoice_id = ?1 ORDER BY id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let items = stmt
+        .query_map([invoice_id], |row| {
+            Ok(serde_json::json!([
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, f64>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, Option<f64>>(7)?,
+                row.get::<_, f64>(8)?,
+                row.get::<_, f64>(9)?,
+            ]))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    let content = serde_json::json!({ "invoice": invoice, "items": items }).to_string();
+    Ok(format!("{:x}", Sha256::digest(content.as_bytes())))
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Issues a draft invoice: its totals are recomputed one last time and
+/// checked against the customer's credit, then frozen along with a hash of
+/// its content, and its lines leave the stock. From then on its payments
+/// set what was paid. Returns the hash.
+#[tauri::command]
+pub fn finalize_invoice(
+    id: i32,
+    override_credit_limit: Option<bool>,
+    override_reason: Option<String>,
+) -> Result<String, CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let deleted: bool = tx
+        .query_row(
+            "SELECT deleted_at IS NOT NULL FROM invoices WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    if deleted {
+        return Err("الفاتورة محذوفة".into());
+    }
+    ensure_draft(&tx, id)?;
+
+    let before = snapshot(&tx, "invoices", id as i64)?;
+    refresh_invoice_totals(&tx, id)?;
+    check_invoice_credit(
+        &tx,
+        id,
+        override_credit_limit.unwrap_or(false),
+        override_reason,
+    )?;
+    let hash = invoice_content_hash(&tx, id)?;
+    tx.execute(
+        r#"
+        UPDATE invoices
+        SET state = CASE WHEN status = 'paid' THEN 'paid' ELSE 'issued' END,
+            issued_at = ?1,
+            content_hash = ?2
+        WHERE id = ?3
+        "#,
+        params![chrono::Utc::now().to_rfc3339(), hash, id],
+    )
+    .map_err(|e| e.to_string())?;
+    sync_invoice_payments(&tx, id)?;
+    record_invoice_stock(&tx, id)?;
+
+    let after = snapshot(&tx, "invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "invoice",
+        id as i64,
+        "finalize",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(hash)
+}
+
+/// Voids an invoice instead of deleting it: the record and its number stay,
+/// but it no longer counts towards any balance or total. What it took out of
+/// stock is put back. Invoices with live payments are refused; the payments
+/// have to be deleted or moved first.
+#[tauri::command]
+pub fn void_invoice(id: i32, reason: String) -> Result<(), String> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("سبب الإلغاء مطلوب".into());
+    }
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (number, state, deleted): (String, String, bool) = tx
+        .query_row(
+            "SELECT invoice_number, state, deleted_at IS NOT NULL FROM invoices WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    if deleted {
+        return Err("الفاتورة محذوفة".into());
+    }
+    if state == "cancelled" {
+        return Err(format!("الفاتورة {} ملغاة بالفعل", number));
+    }
+    let paid: bool = tx
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM payments WHERE invoice_id = ?1 AND deleted_at IS NULL)",
+            [id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if paid {
+        return Err(format!(
+            "للفاتورة {} دفعات مسجلة، احذفها أو انقلها قبل إلغاء الفاتورة",
+            number
+        ));
+    }
+
+    let before = snapshot(&tx, "invoices", id as i64)?;
+    tx.execute(
+        "UPDATE invoices SET state = 'cancelled', void_reason = ?1, voided_at = ?2 WHERE id = ?3",
+        params![reason, chrono::Utc::now().to_rfc3339(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    reverse_invoice_stock(&tx, id)?;
+
+    let after = snapshot(&tx, "invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "invoice",
+        id as i64,
+        "void",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Whether an issued invoice still matches the hash recorded when it was
+/// finalized.
+#[tauri::command]
+pub fn verify_invoice(id: i32) -> Result<bool, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT content_hash FROM invoices WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة غير موجودة")?;
+    let stored = stored.ok_or("الفاتورة لم تُصدر بعد")?;
+
+    Ok(invoice_content_hash(&conn, id)? == stored)
+}
@@ -1,10 +1,53 @@
+pub mod aging;
+pub mod audit;
+pub mod contacts;
+pub mod credit;
+pub mod credit_notes;
+pub mod currencies;
+pub mod custom_fields;
 pub mod customers;
 pub mod debts;
+pub mod delivery_notes;
+pub mod discounts;
+pub mod duplicates;
 pub mod invoices;
+pub mod lifecycle;
+pub mod numbering;
 pub mod payments;
+pub mod products;
+pub mod quotes;
+pub mod recurring_invoices;
+pub mod recycle_bin;
+pub mod search;
+pub mod settings;
+pub mod stock;
+pub mod tags;
+pub mod taxes;
+pub mod units;
+pub use aging::*;
+pub use audit::*;
+pub use contacts::*;
+pub use credit::*;
+pub use credit_notes::*;
+pub use currencies::*;
+pub use custom_fields::*;
 pub use customers::*;
 pub use debts::*;
+pub use delivery_notes::*;
+pub use discounts::*;
+pub use duplicates::*;
 pub use invoices::*;
+pub use lifecycle::*;
 pub use payments::*;
+pub use products::*;
+pub use quotes::*;
+pub use recurring_invoices::*;
+pub use recycle_bin::*;
+pub use search::*;
+pub use settings::*;
+pub use stock::*;
+pub use tags::*;
+pub use taxes::*;
+pub use units::*;
 pub mod prints;
-pub use prints::*;
\ No newline at end of file
+pub use prints::*;
@@ -0,0 +1,46 @@
+use rusqlite::{params, Connection, Result};
+
+/// Next number of a yearly document sequence, e.g. "AV-2026-0001" for the
+/// credit notes of 2026. Each document type has its own prefix.
+pub(crate) fn next_document_number(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    prefix: &str,
+    year: i32,
+) -> Result<String, String> {
+    let prefix = format!("{}-{}-", prefix, year);
+    let sql = format!(
+        "SELECT IFNULL(MAX(CAST(substr({col}, ?1) AS INTEGER)), 0) FROM {table} WHERE {col} LIKE ?2",
+        col = column,
+        table = table
+    );
+    let last: i64 = conn
+        .query_row(
+            &sql,
+            params![prefix.len() as i64 + 1, format!("{}%", prefix)],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(format!("{}{:04}", prefix, last + 1))
+}
+
+/// A fresh invoice number for invoices created without one, in the same
+/// "INV-<milliseconds>" form as the invoice form.
+pub(crate) fn new_invoice_number(conn: &Connection) -> Result<String, String> {
+    let mut stamp = chrono::Utc::now().timestamp_millis();
+    loop {
+        let number = format!("INV-{}", stamp);
+        let taken: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM invoices WHERE invoice_number = ?1)",
+                [&number],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !taken {
+            return Ok(number);
+        }
+        stamp += 1;
+    }
+}
@@ -1,13 +1,46 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::currencies::{currency_code, document_currency};
+use crate::commands::lifecycle::sync_invoice_payments;
 use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
 use crate::models::Payment;
-use rusqlite::{params, Result};
+use crate::normalize::like_pattern;
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::Serialize;
 // ===================== MODELS =====================
 
 #[derive(Serialize)]
 pub struct PaymentsResult {
     pub data: Vec<Payment>,
-    pub sum_amount: f64,
+    pub sum_amount: f64, // in the base currency
+}
+
+// ===================== HELPERS =====================
+
+/// Currency and rate of a payment. A payment of an invoice is in the
+/// invoice's currency; the rate is the one of the payment date.
+fn payment_currency(conn: &Connection, payment: &Payment) -> Result<(String, f64), CommandError> {
+    let invoice_currency: Option<String> = match payment.invoice_id {
+        Some(invoice_id) => conn
+            .query_row(
+                "SELECT currency FROM invoices WHERE id = ?1",
+                [invoice_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let currency = currency_code(
+        conn,
+        payment.currency.as_deref().or(invoice_currency.as_deref()),
+    )?;
+    if invoice_currency.is_some_and(|c| c != currency) {
+        let mut errors = FieldErrors::default();
+        errors.add("currency", "يجب أن تكون الدفعة بعملة الفاتورة");
+        errors.into_result()?;
+    }
+    document_currency(conn, Some(&currency), payment.exchange_rate, &payment.date)
 }
 
 // ===================== GET ALL PAYMENTS =====================
@@ -20,19 +53,21 @@ pub fn get_all_payments(
 ) -> Result<PaymentsResult, String> {
     let db = get_db().map_err(|e| e.to_string())?;
 
-    let mut where_sql = String::new();
+    let mut where_sql = "WHERE p.deleted_at IS NULL".to_string();
     let mut params_vec: Vec<String> = vec![];
 
     if let Some(q) = search {
-        let like = format!("%{}%", q);
-        where_sql = "WHERE c.name LIKE ?1 OR i.invoice_number LIKE ?1 OR p.date LIKE ?1".into();
+        let like = like_pattern(&q);
+        where_sql.push_str(
+            " AND (c.name_normalized LIKE ?1 OR normalize_text(i.invoice_number) LIKE ?1 OR p.date LIKE ?1)",
+        );
         params_vec.push(like);
     }
 
     // SUM
     let sum_sql = format!(
         "
-        SELECT SUM(p.amount)
+        SELECT SUM(p.amount * p.exchange_rate)
         FROM payments p
         LEFT JOIN customers c ON p.customer_id = c.id
         LEFT JOIN invoices i ON p.invoice_id = i.id
@@ -63,7 +98,9 @@ pub fn get_all_payments(
           p.id, p.customer_id, p.invoice_id, p.amount, p.date, p.notes, p.created_at,
           c.name AS customer_name,
           i.invoice_number,
-          p.payment_number
+          p.payment_number,
+          p.currency,
+          p.exchange_rate
         FROM payments p
         LEFT JOIN customers c ON p.customer_id = c.id
         LEFT JOIN invoices i ON p.invoice_id = i.id
@@ -83,6 +120,8 @@ pub fn get_all_payments(
                 customer_id: r.get(1)?,
                 invoice_id: r.get(2)?,
                 amount: r.get(3)?,
+                currency: r.get(10)?,
+                exchange_rate: r.get(11)?,
                 date: r.get(4)?,
                 notes: r.get(5)?,
                 created_at: r.get(6)?,
@@ -101,6 +140,8 @@ pub fn get_all_payments(
                 customer_id: r.get(1)?,
                 invoice_id: r.get(2)?,
                 amount: r.get(3)?,
+                currency: r.get(10)?,
+                exchange_rate: r.get(11)?,
                 date: r.get(4)?,
                 notes: r.get(5)?,
                 created_at: r.get(6)?,
@@ -123,14 +164,16 @@ pub fn get_all_payments(
 // ===================== CREATE PAYMENT =====================
 
 #[tauri::command]
-pub fn create_payment(payment: Payment) -> Result<Payment, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
+pub fn create_payment(payment: Payment) -> Result<Payment, CommandError> {
+    let mut db = get_db().map_err(|e| e.to_string())?;
+    let tx = db.transaction().map_err(|e| e.to_string())?;
     let now = chrono::Utc::now().to_rfc3339();
+    let (currency, exchange_rate) = payment_currency(&tx, &payment)?;
 
-    db.execute(
+    tx.execute(
         "
-        INSERT INTO payments (customer_id, invoice_id, amount, date, notes, created_at, payment_number)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        INSERT INTO payments (customer_id, invoice_id, amount, date, notes, created_at, payment_number, currency, exchange_rate)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
         ",
         params![
             payment.customer_id,
@@ -139,16 +182,27 @@ pub fn create_payment(payment: Payment) -> Result<Payment, String> {
             payment.date,
             payment.notes,
             now,
-            payment.payment_number
+            payment.payment_number,
+            currency,
+            exchange_rate
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    let id = db.last_insert_rowid();
+    let id = tx.last_insert_rowid();
+    if let Some(invoice_id) = payment.invoice_id {
+        sync_invoice_payments(&tx, invoice_id)?;
+    }
+
+    let after = snapshot(&tx, "payments", id)?;
+    record_audit(&tx, "payment", id, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(Payment {
         id: Some(id.try_into().unwrap()),
         created_at: Some(now),
+        currency: Some(currency),
+        exchange_rate: Some(exchange_rate),
         ..payment
     })
 }
@@ -156,23 +210,55 @@ pub fn create_payment(payment: Payment) -> Result<Payment, String> {
 // ===================== UPDATE PAYMENT =====================
 
 #[tauri::command]
-pub fn update_payment(id: i64, p: Payment) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
+pub fn update_payment(id: i64, p: Payment) -> Result<(), CommandError> {
+    let mut db = get_db().map_err(|e| e.to_string())?;
+    let tx = db.transaction().map_err(|e| e.to_string())?;
+    let (currency, exchange_rate) = payment_currency(&tx, &p)?;
 
-    db.execute(
+    let before = snapshot(&tx, "payments", id)?;
+
+    tx.execute(
         "
         UPDATE payments SET
           customer_id = ?1,
           invoice_id = ?2,
           amount = ?3,
           date = ?4,
-          notes = ?5
-          payment_number = ?6
-        WHERE id = ?7
+          notes = ?5,
+          payment_number = ?6,
+          currency = ?7,
+          exchange_rate = ?8
+        WHERE id = ?9
         ",
-        params![p.customer_id, p.invoice_id, p.amount, p.date, p.notes,p.payment_number, id],
+        params![
+            p.customer_id,
+            p.invoice_id,
+            p.amount,
+            p.date,
+            p.notes,
+            p.payment_number,
+            currency,
+            exchange_rate,
+            id
+        ],
     )
     .map_err(|e| e.to_string())?;
+    // Both the invoice it left and the one it now pays
+    let previous = before.as_ref().and_then(|b| b["invoice_id"].as_i64());
+    for invoice_id in previous.map(|i| i as i32).into_iter().chain(p.invoice_id) {
+        sync_invoice_payments(&tx, invoice_id)?;
+    }
+
+    let after = snapshot(&tx, "payments", id)?;
+    record_audit(
+        &tx,
+        "payment",
+        id,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -181,9 +267,30 @@ pub fn update_payment(id: i64, p: Payment) -> Result<(), String> {
 
 #[tauri::command]
 pub fn delete_payment(id: i64) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    db.execute("DELETE FROM payments WHERE id = ?", params![id])
-        .map_err(|e| e.to_string())?;
+    let mut db = get_db().map_err(|e| e.to_string())?;
+    let tx = db.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "payments", id)?;
+
+    tx.execute(
+        "UPDATE payments SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![chrono::Utc::now().to_rfc3339(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(invoice_id) = before.as_ref().and_then(|b| b["invoice_id"].as_i64()) {
+        sync_invoice_payments(&tx, invoice_id as i32)?;
+    }
+
+    let after = snapshot(&tx, "payments", id)?;
+    record_audit(
+        &tx,
+        "payment",
+        id,
+        "delete",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -1,11 +1,27 @@
-use crate::{get_invoices, get_transactions};
+use crate::commands::aging::{aging_report_csv, bucket_title, build_aging_report};
+use crate::commands::credit_notes::credit_note_with_items;
+use crate::commands::custom_fields::fill_custom_fields;
+use crate::commands::delivery_notes::{delivery_note_lines, delivery_note_with_items};
+use crate::commands::invoices::invoice_with_customer;
+use crate::commands::quotes::quote_with_items;
+use crate::commands::settings::base_currency;
+use crate::commands::taxes::invoice_tax_breakdown;
+use crate::commands::units::quantity_label;
+use crate::currency::{format_amount, format_number};
+use crate::db::get_db;
+use crate::models::TaxSummary;
+use crate::{get_invoice_items, get_invoices, get_tax_summary, get_transactions};
+use rusqlite::OptionalExtension;
 use std::fs;
 use tauri::Manager;
 // use tauri::{AppHandle, Manager};
 use typst_as_library::TypstWrapperWorld;
 use typst_pdf::PdfOptions;
 
-fn get_template_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+fn resource_template_path(
+    app_handle: &tauri::AppHandle,
+    file_name: &str,
+) -> Result<std::path::PathBuf, String> {
     let base_dir = app_handle
         .path()
         .resource_dir()
@@ -13,38 +29,65 @@ fn get_template_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf
 
     // في Tauri v2، سواء في dev أو build، الملفات تكون داخل `resources/`
     // لكن في dev، `resource_dir()` يُرجع src-tauri/، لذا نحتاج إضافة "resources"
-    let template_path = base_dir
-        .join("resources")
-        .join("templates")
-        .join("invoices.typ");
+    let template_path = base_dir.join("resources").join("templates").join(file_name);
 
     if !template_path.exists() {
         eprintln!("⚠️  لم يُعثر على ملف القالب في: {}", template_path.display());
-        return Err("ملف القالب (invoices.typ) غير موجود في resources/templates/".into());
+        return Err(format!(
+            "ملف القالب ({}) غير موجود في resources/templates/",
+            file_name
+        ));
     }
 
     Ok(template_path)
 }
 
+fn get_template_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    resource_template_path(app_handle, "invoices.typ")
+}
+
 fn get_transactions_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
-    let base_dir = app_handle
+    resource_template_path(app_handle, "transactions.typ")
+}
+
+fn output_dir(app_handle: &tauri::AppHandle, name: &str) -> Result<std::path::PathBuf, String> {
+    let out_dir = app_handle
         .path()
-        .resource_dir()
-        .map_err(|e| format!("فشل في تحديد مجلد الموارد: {}", e))?;
+        .app_data_dir()
+        .map_err(|e| format!("فشل في تحديد مجلد البيانات: {}", e))?
+        .join(name);
 
-    // في Tauri v2، سواء في dev أو build، الملفات تكون داخل `resources/`
-    // لكن في dev، `resource_dir()` يُرجع src-tauri/، لذا نحتاج إضافة "resources"
-    let template_path = base_dir
-        .join("resources")
-        .join("templates")
-        .join("transactions.typ");
+    std::fs::create_dir_all(&out_dir).map_err(|e| format!("فشل في إنشاء مجلد الإخراج: {}", e))?;
+    Ok(out_dir)
+}
 
-    if !template_path.exists() {
-        eprintln!("⚠️  لم يُعثر على ملف القالب في: {}", template_path.display());
-        return Err("ملف القالب (transactions.typ) غير موجود في resources/templates/".into());
+fn typst_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Printed discount, e.g. "10% (25.00)" or "25.00"; empty without one.
+fn discount_label(discount_type: Option<&str>, value: f64, amount: f64, currency: &str) -> String {
+    match discount_type {
+        _ if amount <= 0.0 => String::new(),
+        Some("percent") => format!("{}% ({})", value, format_number(amount, currency)),
+        _ => format_number(amount, currency),
     }
+}
 
-    Ok(template_path)
+/// `(tax, taxable amount, rate)` rows of a tax breakdown table.
+fn tax_rows(taxes: &[TaxSummary], currency: &str) -> String {
+    taxes
+        .iter()
+        .map(|t| {
+            format!(
+                "(\"{}\", \"{}\", \"{}%\"),",
+                format_number(t.tax_amount, currency),
+                format_number(t.subtotal, currency),
+                t.tax_rate
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
 #[tauri::command]
@@ -53,8 +96,17 @@ pub fn generate_invoices_pdf(
     from_date: String,
     to_date: Option<String>,
     customer_id: Option<i32>, // ← إضافة هذا
+    tag_ids: Option<Vec<i32>>,
 ) -> Result<String, String> {
-    let invoices_response = get_invoices(from_date.clone(), to_date.clone(), customer_id)?;
+    let invoices_response = get_invoices(
+        from_date.clone(),
+        to_date.clone(),
+        customer_id,
+        tag_ids.clone(),
+    )?;
+    let taxes = get_tax_summary(from_date.clone(), to_date.clone(), customer_id, tag_ids)?;
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let base = base_currency(&conn)?;
 
     let template_path = get_template_path(&app_handle)?;
 
@@ -65,9 +117,18 @@ pub fn generate_invoices_pdf(
     let rows: String = invoices
         .iter()
         .map(|inv| {
+            let settlement = if inv.state.as_deref() == Some("cancelled") {
+                "ملغاة"
+            } else {
+                ""
+            };
+            // Each invoice in its own currency, the totals in the base one
             format!(
-                r#"("", "{:.2}", "{}", "{}", "{}")"#,
-                inv.total,
+                r#"("{}", "{}", "{}", "{}", "{}", "{}", "{}")"#,
+                settlement,
+                format_amount(inv.total, &inv.currency),
+                format_amount(inv.tax_total.unwrap_or(0.0), &inv.currency),
+                format_amount(inv.subtotal.unwrap_or(inv.total), &inv.currency),
                 inv.customer_phone.as_deref().unwrap_or(""),
                 inv.customer_address.as_deref().unwrap_or(""),
                 inv.customer_name.as_deref().unwrap_or("")
@@ -78,12 +139,22 @@ pub fn generate_invoices_pdf(
     // This is a placeholder function.
     let content = template
         .replace("{rows}", &rows)
-        .replace("{total}", &format!("{:.2}", invoices_response.total))
+        .replace("{total}", &format_amount(invoices_response.total, &base))
+        .replace(
+            "{subtotal}",
+            &format_amount(invoices_response.subtotal, &base),
+        )
+        .replace(
+            "{tax_total}",
+            &format_amount(invoices_response.tax_total, &base),
+        )
+        .replace("{taxes}", &tax_rows(&taxes, &base))
         .replace("{company_name}", "شركة المثال")
         .replace("{phone}", "0551234567")
         .replace("{address}", "الرياض")
         .replace("{from_date}", &from_date.as_str())
         .replace("{to_date}", to_date.as_deref().unwrap_or(""));
+    let content = fill_custom_fields(&conn, &content, customer_id)?;
 
     // Create Typst world with content
     let world = TypstWrapperWorld::new("../".to_string(), content);
@@ -123,6 +194,9 @@ pub fn generate_transactions_pdf(
     to_date: Option<String>,
 ) -> Result<String, String> {
     let transactions_response = get_transactions(customer_id, from_date.clone(), to_date.clone())?;
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let base = base_currency(&conn)?;
+    let money = |amount: f64| format_amount(amount, &base);
     let template_path = get_transactions_path(&app_handle)?;
 
     println!("Template path: {:?}", template_path);
@@ -132,14 +206,25 @@ pub fn generate_transactions_pdf(
     let rows: String = transactions
         .iter()
         .map(|inv| {
-            let transaction_label = if inv.transaction_type == "payment" {
-                "دفعة"
+            let transaction_label = match inv.transaction_type.as_str() {
+                "payment" => "دفعة",
+                "credit_note" => "إشعار دائن",
+                _ => "فاتورة",
+            };
+            // Documents in another currency also show their own amount
+            let amount = if inv.currency == base {
+                money(inv.amount)
             } else {
-                "فاتورة"
+                format!(
+                    "{} ({})",
+                    money(inv.amount),
+                    format_amount(inv.currency_amount, &inv.currency)
+                )
             };
             format!(
-                r#"("{:.2}", "{}", "{}", "{}")"#,
-                inv.amount,
+                r#"("{}", "{}", "{}", "{}", "{}")"#,
+                money(inv.running_balance),
+                amount,
                 transaction_label,
                 inv.date,
                 inv.reference.as_deref().unwrap_or("")
@@ -151,21 +236,34 @@ pub fn generate_transactions_pdf(
         .replace("{rows}", &rows)
         .replace(
             "{total_invoices}",
-            &format!("{:.2}", transactions_response.total_invoices),
+            &money(transactions_response.total_invoices),
         )
         .replace(
             "{total_payments}",
-            &format!("{:.2}", transactions_response.total_payments),
+            &money(transactions_response.total_payments),
+        )
+        .replace(
+            "{total_credits}",
+            &money(transactions_response.total_credits),
         )
         .replace(
             "{remaining_total}",
-            &format!("{:.2}", transactions_response.remaining_total),
+            &money(transactions_response.remaining_total),
+        )
+        .replace(
+            "{opening_balance}",
+            &money(transactions_response.opening_balance),
+        )
+        .replace(
+            "{closing_balance}",
+            &money(transactions_response.closing_balance),
         )
         .replace("{name}", &customer_name)
         .replace("{phone}", &customer_phone)
         .replace("{address}", &customer_address)
         .replace("{from_date}", &from_date.as_str())
         .replace("{to_date}", to_date.as_deref().unwrap_or(""));
+    let content = fill_custom_fields(&conn, &content, Some(customer_id))?;
 
     // Create Typst world with content
     let world = TypstWrapperWorld::new("../".to_string(), content);
@@ -193,3 +291,510 @@ pub fn generate_transactions_pdf(
 
     Ok(out_path.to_string_lossy().to_string())
 }
+
+#[tauri::command]
+pub fn generate_aging_pdf(
+    app_handle: tauri::AppHandle,
+    as_of_date: Option<String>,
+    bucket_edges: Option<Vec<i64>>,
+    basis: Option<String>,
+) -> Result<String, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let report = build_aging_report(&conn, as_of_date, bucket_edges, basis)?;
+    let base = base_currency(&conn)?;
+
+    let template_path = resource_template_path(&app_handle, "aging.typ")?;
+    let template = fs::read_to_string(&template_path)
+        .map_err(|e| format!("Error reading {}: {}", template_path.display(), e))?;
+
+    // Columns are listed left to right, so the customer comes last and the
+    // oldest bucket sits next to the total
+    let header: String = report
+        .buckets
+        .iter()
+        .rev()
+        .map(|b| format!("[{}], ", bucket_title(b)))
+        .collect();
+    let amounts = |values: &[f64]| -> String {
+        values
+            .iter()
+            .rev()
+            .map(|a| format!("\"{}\", ", format_number(*a, &base)))
+            .collect()
+    };
+    let rows: String = report
+        .rows
+        .iter()
+        .map(|row| {
+            format!(
+                "(\"{}\", {}{}, {}),",
+                format_number(row.total, &base),
+                amounts(&row.amounts),
+                typst_str(&row.customer_phone),
+                typst_str(&row.customer_name)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let content = template
+        .replace("{columns}", &(report.buckets.len() + 3).to_string())
+        .replace("{header}", &header)
+        .replace("{rows}", &rows)
+        .replace("{totals}", &amounts(&report.totals))
+        .replace("{total}", &format_amount(report.total, &base))
+        .replace("{as_of_date}", &report.as_of_date)
+        .replace(
+            "{basis}",
+            if report.basis == "due_date" {
+                "تاريخ الاستحقاق"
+            } else {
+                "تاريخ الفاتورة"
+            },
+        );
+
+    let world = TypstWrapperWorld::new("../".to_string(), content);
+    let document = typst::compile(&world)
+        .output
+        .map_err(|e| format!("Error compiling Typst: {:?}", e))?;
+    let pdf = typst_pdf::pdf(&document, &PdfOptions::default())
+        .map_err(|e| format!("Error exporting PDF: {:?}", e))?;
+
+    let out_path = output_dir(&app_handle, "reports")?.join("aging_report.pdf");
+    std::fs::write(&out_path, pdf).map_err(|e| format!("فشل في حفظ ملف PDF: {}", e))?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn export_aging_csv(
+    app_handle: tauri::AppHandle,
+    as_of_date: Option<String>,
+    bucket_edges: Option<Vec<i64>>,
+    basis: Option<String>,
+) -> Result<String, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let report = build_aging_report(&conn, as_of_date, bucket_edges, basis)?;
+
+    let out_path = output_dir(&app_handle, "reports")?.join("aging_report.csv");
+    std::fs::write(&out_path, aging_report_csv(&report))
+        .map_err(|e| format!("فشل في حفظ ملف CSV: {}", e))?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Prints one invoice with its lines and the tax breakdown per rate.
+#[tauri::command]
+pub fn generate_invoice_pdf(
+    app_handle: tauri::AppHandle,
+    invoice_id: i32,
+) -> Result<String, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let invoice = invoice_with_customer(&conn, invoice_id)?.ok_or("الفاتورة غير موجودة")?;
+    let items = get_invoice_items(invoice_id)?;
+    let taxes = invoice_tax_breakdown(&conn, invoice_id)?;
+    let currency = invoice.currency.as_str();
+    let number = |amount: f64| format_number(amount, currency);
+
+    let template_path = resource_template_path(&app_handle, "invoice.typ")?;
+    let template = fs::read_to_string(&template_path)
+        .map_err(|e| format!("Error reading {}: {}", template_path.display(), e))?;
+
+    let rows: String = items
+        .iter()
+        .map(|item| {
+            format!(
+                "(\"{}\", \"{}%\", \"{}\", \"{}\", {}, \"{}\", {}),",
+                number(item.total),
+                item.tax_rate.unwrap_or_default(),
+                number(item.subtotal.unwrap_or(item.total)),
+                discount_label(
+                    item.discount_type.as_deref(),
+                    item.discount_value,
+                    item.discount_amount.unwrap_or(0.0),
+                    currency
+                ),
+                typst_str(&quantity_label(item.quantity, item.unit_name.as_deref())),
+                number(item.unit_price),
+                typst_str(&item.product_name)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let subtotal = invoice.subtotal.unwrap_or(invoice.total);
+    let content = template
+        .replace("{rows}", &rows)
+        .replace("{taxes}", &tax_rows(&taxes, currency))
+        .replace(
+            "{gross_total}",
+            &format_amount(subtotal + invoice.discount_amount, currency),
+        )
+        .replace(
+            "{discount}",
+            &discount_label(
+                invoice.discount_type.as_deref(),
+                invoice.discount_value,
+                invoice.discount_amount,
+                currency,
+            ),
+        )
+        .replace("{subtotal}", &format_amount(subtotal, currency))
+        .replace(
+            "{tax_total}",
+            &format_amount(invoice.tax_total.unwrap_or(0.0), currency),
+        )
+        .replace("{total}", &format_amount(invoice.total, currency))
+        .replace("{company_name}", "شركة المثال")
+        .replace("{phone}", "0551234567")
+        .replace("{address}", "الرياض")
+        .replace("{invoice_number}", &typst_str(&invoice.invoice_number))
+        .replace(
+            "{voided}",
+            &(invoice.state.as_deref() == Some("cancelled")).to_string(),
+        )
+        .replace(
+            "{void_reason}",
+            &typst_str(invoice.void_reason.as_deref().unwrap_or("")),
+        )
+        .replace("{date}", &invoice.date)
+        .replace("{due_date}", invoice.due_date.as_deref().unwrap_or(""))
+        .replace(
+            "{customer_name}",
+            &typst_str(invoice.customer_name.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_phone}",
+            &typst_str(invoice.customer_phone.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_address}",
+            &typst_str(invoice.customer_address.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{delivery_address}",
+            &typst_str(invoice.delivery_address.as_deref().unwrap_or("")),
+        );
+    let content = fill_custom_fields(&conn, &content, Some(invoice.customer_id))?;
+
+    let world = TypstWrapperWorld::new("../".to_string(), content);
+    let document = typst::compile(&world)
+        .output
+        .map_err(|e| format!("Error compiling Typst: {:?}", e))?;
+    let pdf = typst_pdf::pdf(&document, &PdfOptions::default())
+        .map_err(|e| format!("Error exporting PDF: {:?}", e))?;
+
+    let out_path = output_dir(&app_handle, "invoices")?.join(format!("invoice_{}.pdf", invoice_id));
+    std::fs::write(&out_path, pdf).map_err(|e| format!("فشل في حفظ ملف PDF: {}", e))?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn generate_credit_note_pdf(
+    app_handle: tauri::AppHandle,
+    credit_note_id: i32,
+) -> Result<String, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let credit_note = credit_note_with_items(&conn, credit_note_id)?;
+    let invoice =
+        invoice_with_customer(&conn, credit_note.invoice_id)?.ok_or("الفاتورة غير موجودة")?;
+    let currency = credit_note.currency.as_deref().unwrap_or(&invoice.currency);
+    let number = |amount: f64| format_number(amount, currency);
+
+    let template_path = resource_template_path(&app_handle, "credit_note.typ")?;
+    let template = fs::read_to_string(&template_path)
+        .map_err(|e| format!("Error reading {}: {}", template_path.display(), e))?;
+
+    let rows: String = credit_note
+        .items
+        .iter()
+        .map(|item| {
+            format!(
+                "(\"{}\", \"{}%\", \"{}\", {}, \"{}\", {}),",
+                number(item.total),
+                item.tax_rate,
+                number(item.subtotal),
+                typst_str(&quantity_label(item.quantity, item.unit_name.as_deref())),
+                number(item.unit_price),
+                typst_str(&item.product_name)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // Returned amounts per tax rate
+    let mut taxes: Vec<TaxSummary> = Vec::new();
+    for item in &credit_note.items {
+        match taxes.iter_mut().find(|t| t.tax_rate == item.tax_rate) {
+            Some(t) => {
+                t.subtotal += item.subtotal;
+                t.tax_amount += item.tax_amount;
+                t.total += item.total;
+            }
+            None => taxes.push(TaxSummary {
+                tax_rate: item.tax_rate,
+                subtotal: item.subtotal,
+                tax_amount: item.tax_amount,
+                total: item.total,
+                invoice_count: 1,
+            }),
+        }
+    }
+    taxes.sort_by(|a, b| a.tax_rate.total_cmp(&b.tax_rate));
+
+    let content = template
+        .replace("{rows}", &rows)
+        .replace("{taxes}", &tax_rows(&taxes, currency))
+        .replace("{subtotal}", &format_amount(credit_note.subtotal, currency))
+        .replace(
+            "{tax_total}",
+            &format_amount(credit_note.tax_total, currency),
+        )
+        .replace("{total}", &format_amount(credit_note.total, currency))
+        .replace("{company_name}", "شركة المثال")
+        .replace("{phone}", "0551234567")
+        .replace("{address}", "الرياض")
+        .replace(
+            "{credit_note_number}",
+            &typst_str(&credit_note.credit_note_number),
+        )
+        .replace("{invoice_number}", &typst_str(&invoice.invoice_number))
+        .replace("{date}", &credit_note.date)
+        .replace(
+            "{reason}",
+            &typst_str(credit_note.reason.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_name}",
+            &typst_str(invoice.customer_name.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_phone}",
+            &typst_str(invoice.customer_phone.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_address}",
+            &typst_str(invoice.customer_address.as_deref().unwrap_or("")),
+        );
+    let content = fill_custom_fields(&conn, &content, Some(credit_note.customer_id))?;
+
+    let world = TypstWrapperWorld::new("../".to_string(), content);
+    let document = typst::compile(&world)
+        .output
+        .map_err(|e| format!("Error compiling Typst: {:?}", e))?;
+    let pdf = typst_pdf::pdf(&document, &PdfOptions::default())
+        .map_err(|e| format!("Error exporting PDF: {:?}", e))?;
+
+    let out_path = output_dir(&app_handle, "credit_notes")?
+        .join(format!("credit_note_{}.pdf", credit_note_id));
+    std::fs::write(&out_path, pdf).map_err(|e| format!("فشل في حفظ ملف PDF: {}", e))?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn generate_quote_pdf(app_handle: tauri::AppHandle, quote_id: i32) -> Result<String, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let quote = quote_with_items(&conn, quote_id)?;
+    let (customer_phone, customer_address): (String, String) = conn
+        .query_row(
+            "SELECT phone, address FROM customers WHERE id = ?1",
+            [quote.customer_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let currency = match quote.currency.clone() {
+        Some(currency) => currency,
+        None => base_currency(&conn)?,
+    };
+    let currency = currency.as_str();
+    let number = |amount: f64| format_number(amount, currency);
+
+    let template_path = resource_template_path(&app_handle, "quote.typ")?;
+    let template = fs::read_to_string(&template_path)
+        .map_err(|e| format!("Error reading {}: {}", template_path.display(), e))?;
+
+    let rows: String = quote
+        .items
+        .iter()
+        .map(|item| {
+            format!(
+                "(\"{}\", \"{}%\", \"{}\", \"{}\", {}, \"{}\", {}),",
+                number(item.total.unwrap_or(0.0)),
+                item.tax_rate,
+                number(item.subtotal.unwrap_or(0.0)),
+                discount_label(
+                    item.discount_type.as_deref(),
+                    item.discount_value,
+                    item.discount_amount.unwrap_or(0.0),
+                    currency
+                ),
+                typst_str(&quantity_label(item.quantity, item.unit_name.as_deref())),
+                number(item.unit_price),
+                typst_str(&item.product_name)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // Tax per rate, after the quote discount is spread over the lines
+    let subtotal = quote.subtotal.unwrap_or(0.0);
+    let discount_amount = quote.discount_amount.unwrap_or(0.0);
+    let lines_subtotal = subtotal + discount_amount;
+    let factor = if lines_subtotal > 0.0 {
+        subtotal / lines_subtotal
+    } else {
+        1.0
+    };
+    let mut taxes: Vec<TaxSummary> = Vec::new();
+    for item in &quote.items {
+        let (base, tax) = (
+            item.subtotal.unwrap_or(0.0) * factor,
+            item.tax_amount.unwrap_or(0.0) * factor,
+        );
+        match taxes.iter_mut().find(|t| t.tax_rate == item.tax_rate) {
+            Some(t) => {
+                t.subtotal += base;
+                t.tax_amount += tax;
+                t.total += base + tax;
+            }
+            None => taxes.push(TaxSummary {
+                tax_rate: item.tax_rate,
+                subtotal: base,
+                tax_amount: tax,
+                total: base + tax,
+                invoice_count: 1,
+            }),
+        }
+    }
+    taxes.sort_by(|a, b| a.tax_rate.total_cmp(&b.tax_rate));
+
+    let content = template
+        .replace("{rows}", &rows)
+        .replace("{taxes}", &tax_rows(&taxes, currency))
+        .replace("{gross_total}", &format_amount(lines_subtotal, currency))
+        .replace(
+            "{discount}",
+            &discount_label(
+                quote.discount_type.as_deref(),
+                quote.discount_value,
+                discount_amount,
+                currency,
+            ),
+        )
+        .replace("{subtotal}", &format_amount(subtotal, currency))
+        .replace(
+            "{tax_total}",
+            &format_amount(quote.tax_total.unwrap_or(0.0), currency),
+        )
+        .replace(
+            "{total}",
+            &format_amount(quote.total.unwrap_or(0.0), currency),
+        )
+        .replace("{company_name}", "شركة المثال")
+        .replace("{phone}", "0551234567")
+        .replace("{address}", "الرياض")
+        .replace(
+            "{quote_number}",
+            &typst_str(quote.quote_number.as_deref().unwrap_or("")),
+        )
+        .replace("{date}", &quote.date)
+        .replace("{valid_until}", quote.valid_until.as_deref().unwrap_or(""))
+        .replace("{notes}", &typst_str(quote.notes.as_deref().unwrap_or("")))
+        .replace(
+            "{customer_name}",
+            &typst_str(quote.customer_name.as_deref().unwrap_or("")),
+        )
+        .replace("{customer_phone}", &typst_str(&customer_phone))
+        .replace("{customer_address}", &typst_str(&customer_address));
+    let content = fill_custom_fields(&conn, &content, Some(quote.customer_id))?;
+
+    let world = TypstWrapperWorld::new("../".to_string(), content);
+    let document = typst::compile(&world)
+        .output
+        .map_err(|e| format!("Error compiling Typst: {:?}", e))?;
+    let pdf = typst_pdf::pdf(&document, &PdfOptions::default())
+        .map_err(|e| format!("Error exporting PDF: {:?}", e))?;
+
+    let out_path = output_dir(&app_handle, "quotes")?.join(format!("quote_{}.pdf", quote_id));
+    std::fs::write(&out_path, pdf).map_err(|e| format!("فشل في حفظ ملف PDF: {}", e))?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn generate_delivery_note_pdf(
+    app_handle: tauri::AppHandle,
+    delivery_note_id: i32,
+) -> Result<String, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    let delivery_note = delivery_note_with_items(&conn, delivery_note_id)?;
+    let invoice =
+        invoice_with_customer(&conn, delivery_note.invoice_id)?.ok_or("الفاتورة غير موجودة")?;
+
+    let template_path = resource_template_path(&app_handle, "delivery_note.typ")?;
+    let template = fs::read_to_string(&template_path)
+        .map_err(|e| format!("Error reading {}: {}", template_path.display(), e))?;
+
+    let rows: String = delivery_note_lines(&conn, delivery_note_id)?
+        .iter()
+        .map(|(product_name, unit_name, ordered, delivered, remaining)| {
+            format!(
+                "({}, {}, {}, {}),",
+                typst_str(&quantity_label(*remaining, unit_name.as_deref())),
+                typst_str(&quantity_label(*delivered, unit_name.as_deref())),
+                typst_str(&quantity_label(*ordered, unit_name.as_deref())),
+                typst_str(product_name)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // Delivered to the invoice's delivery address when it has one
+    let delivery_address = invoice
+        .delivery_address
+        .as_deref()
+        .or(invoice.customer_address.as_deref())
+        .unwrap_or("");
+    let content = template
+        .replace("{rows}", &rows)
+        .replace("{company_name}", "شركة المثال")
+        .replace("{phone}", "0551234567")
+        .replace("{address}", "الرياض")
+        .replace(
+            "{delivery_note_number}",
+            &typst_str(&delivery_note.delivery_note_number),
+        )
+        .replace("{invoice_number}", &typst_str(&invoice.invoice_number))
+        .replace("{date}", &delivery_note.date)
+        .replace(
+            "{notes}",
+            &typst_str(delivery_note.notes.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_name}",
+            &typst_str(invoice.customer_name.as_deref().unwrap_or("")),
+        )
+        .replace(
+            "{customer_phone}",
+            &typst_str(invoice.customer_phone.as_deref().unwrap_or("")),
+        )
+        .replace("{delivery_address}", &typst_str(delivery_address));
+    let content = fill_custom_fields(&conn, &content, Some(delivery_note.customer_id))?;
+
+    let world = TypstWrapperWorld::new("../".to_string(), content);
+    let document = typst::compile(&world)
+        .output
+        .map_err(|e| format!("Error compiling Typst: {:?}", e))?;
+    let pdf = typst_pdf::pdf(&document, &PdfOptions::default())
+        .map_err(|e| format!("Error exporting PDF: {:?}", e))?;
+
+    let out_path = output_dir(&app_handle, "delivery_notes")?
+        .join(format!("delivery_note_{}.pdf", delivery_note_id));
+    std::fs::write(&out_path, pdf).map_err(|e| format!("فشل في حفظ ملف PDF: {}", e))?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
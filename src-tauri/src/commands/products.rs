@@ -0,0 +1,203 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::Product;
+use crate::normalize::like_pattern;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::BTreeMap;
+
+//
+// ==================== Helpers ====================
+//
+
+/// Catalog price of a product, matched by its normalized name.
+pub(crate) fn catalog_price(conn: &Connection, product_name: &str) -> Result<Option<f64>, String> {
+    conn.query_row(
+        "SELECT unit_price FROM products WHERE name_normalized = normalize_text(?1)",
+        [product_name],
+        |row| row.get::<_, Option<f64>>(0),
+    )
+    .optional()
+    .map(Option::flatten)
+    .map_err(|e| e.to_string())
+}
+
+/// Catalog unit of a product, matched by its normalized name.
+pub(crate) fn catalog_unit(conn: &Connection, product_name: &str) -> Result<Option<i32>, String> {
+    conn.query_row(
+        "SELECT unit_id FROM products WHERE name_normalized = normalize_text(?1)",
+        [product_name],
+        |row| row.get::<_, Option<i32>>(0),
+    )
+    .optional()
+    .map(Option::flatten)
+    .map_err(|e| e.to_string())
+}
+
+fn validate_product(product: &Product) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if product.name.trim().is_empty() {
+        errors.add("name", "اسم المنتج مطلوب");
+    }
+    if product.unit_price.is_some_and(|p| p < 0.0) {
+        errors.add("unit_price", "لا يمكن أن يكون السعر سالباً");
+    }
+    if product.cost_price.is_some_and(|p| p < 0.0) {
+        errors.add("cost_price", "لا يمكن أن تكون التكلفة سالبة");
+    }
+    if product.low_stock_threshold.is_some_and(|t| t < 0.0) {
+        errors.add("low_stock_threshold", "لا يمكن أن يكون حد التنبيه سالباً");
+    }
+    errors.into_result()
+}
+
+fn map_unique_error(e: rusqlite::Error) -> CommandError {
+    if e.to_string().contains("UNIQUE") {
+        CommandError::Validation {
+            fields: BTreeMap::from([("name".to_string(), "هذا المنتج موجود بالفعل".to_string())]),
+        }
+    } else {
+        e.to_string().into()
+    }
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_products(search_query: Option<String>) -> Result<Vec<Product>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT p.id, p.name, p.unit_price, p.created_at, p.unit_id, u.name,
+                   p.track_stock, p.low_stock_threshold, p.cost_price,
+                   CASE WHEN p.track_stock THEN
+                     (SELECT IFNULL(SUM(m.quantity), 0) FROM stock_movements m WHERE m.product_id = p.id)
+                   END
+            FROM products p
+            LEFT JOIN units u ON u.id = p.unit_id
+            WHERE ?1 IS NULL OR p.name_normalized LIKE ?1
+            ORDER BY p.name
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([search_query.as_deref().map(like_pattern)], |row| {
+            Ok(Product {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                unit_price: row.get(2)?,
+                unit_id: row.get(4)?,
+                unit_name: row.get(5)?,
+                track_stock: row.get(6)?,
+                low_stock_threshold: row.get(7)?,
+                cost_price: row.get(8)?,
+                stock: row.get(9)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_product(product: Product) -> Result<i32, CommandError> {
+    validate_product(&product)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO products (name, unit_price, unit_id, track_stock, low_stock_threshold, cost_price) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            product.name.trim(),
+            product.unit_price,
+            product.unit_id,
+            product.track_stock,
+            product.low_stock_threshold,
+            product.cost_price
+        ],
+    )
+    .map_err(map_unique_error)?;
+    let id = tx.last_insert_rowid() as i32;
+
+    let after = snapshot(&tx, "products", id as i64)?;
+    record_audit(&tx, "product", id as i64, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Updates a product. Invoice lines keep the price they were sold at.
+#[tauri::command]
+pub fn update_product(id: i32, product: Product) -> Result<(), CommandError> {
+    validate_product(&product)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Stock is counted in the product's unit
+    let unit_locked: bool = tx
+        .query_row(
+            r#"
+            SELECT unit_id IS NOT ?2 AND EXISTS (SELECT 1 FROM stock_movements WHERE product_id = ?1)
+            FROM products WHERE id = ?1
+            "#,
+            params![id, product.unit_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if unit_locked {
+        let mut errors = FieldErrors::default();
+        errors.add("unit_id", "لا يمكن تغيير وحدة منتج له حركات مخزون");
+        errors.into_result()?;
+    }
+
+    let before = snapshot(&tx, "products", id as i64)?;
+    tx.execute(
+        "UPDATE products SET name = ?1, unit_price = ?2, unit_id = ?3, track_stock = ?4, low_stock_threshold = ?5, cost_price = ?6 WHERE id = ?7",
+        params![
+            product.name.trim(),
+            product.unit_price,
+            product.unit_id,
+            product.track_stock,
+            product.low_stock_threshold,
+            product.cost_price,
+            id
+        ],
+    )
+    .map_err(map_unique_error)?;
+
+    let after = snapshot(&tx, "products", id as i64)?;
+    record_audit(
+        &tx,
+        "product",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_product(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "products", id as i64)?;
+    tx.execute("DELETE FROM products WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(&tx, "product", id as i64, "delete", before.as_ref(), None)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
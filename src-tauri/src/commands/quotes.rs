@@ -0,0 +1,516 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::currencies::currency_code;
+use crate::commands::discounts::validate_discount;
+use crate::commands::invoices::{insert_invoice, insert_invoice_item};
+use crate::commands::numbering::{new_invoice_number, next_document_number};
+use crate::commands::products::catalog_unit;
+use crate::commands::taxes::{document_totals, line_amounts};
+use crate::commands::units::check_quantity;
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{Invoice, InvoiceItem, Quote, QuoteItem};
+use crate::terms::parse_date;
+use chrono::{Datelike, Duration};
+use rusqlite::{params, Connection, OptionalExtension, Result, ToSql};
+
+//
+// ==================== Helpers ====================
+//
+
+const QUOTE_STATUSES: &[&str] = &["draft", "sent", "accepted", "rejected", "expired"];
+const DEFAULT_VALIDITY_DAYS: i64 = 30;
+
+// Quotes still open after their validity date are reported as expired. A
+// quote is linked to the live invoice it was converted to, if any.
+const QUOTE_SELECT: &str = r#"
+    SELECT
+      q.id,
+      q.quote_number,
+      q.customer_id,
+      c.name AS customer_name,
+      q.date,
+      q.valid_until,
+      CASE WHEN q.status IN ('draft', 'sent') AND q.valid_until < date('now', 'localtime')
+           THEN 'expired' ELSE q.status END AS status,
+      q.notes,
+      q.discount_type,
+      q.discount_value,
+      q.discount_amount,
+      q.subtotal,
+      q.tax_total,
+      q.total,
+      (SELECT MAX(i.id) FROM invoices i WHERE i.quote_id = q.id AND i.deleted_at IS NULL) AS invoice_id,
+      q.created_at,
+      q.currency
+    FROM quotes q
+    LEFT JOIN customers c ON c.id = q.customer_id
+"#;
+
+fn quote_from_row(row: &rusqlite::Row) -> rusqlite::Result<Quote> {
+    Ok(Quote {
+        id: row.get(0)?,
+        quote_number: row.get(1)?,
+        customer_id: row.get(2)?,
+        customer_name: row.get(3)?,
+        date: row.get(4)?,
+        valid_until: row.get(5)?,
+        status: row.get(6)?,
+        notes: row.get(7)?,
+        discount_type: row.get(8)?,
+        discount_value: row.get(9)?,
+        discount_amount: row.get(10)?,
+        subtotal: row.get(11)?,
+        tax_total: row.get(12)?,
+        total: row.get(13)?,
+        currency: row.get(16)?,
+        invoice_id: row.get(14)?,
+        created_at: row.get(15)?,
+        items: Vec::new(),
+    })
+}
+
+fn quote_items(conn: &Connection, quote_id: i32) -> Result<Vec<QuoteItem>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT qi.id, qi.product_name, qi.unit_price, qi.quantity, qi.tax_rate, qi.price_includes_tax,
+                   qi.discount_type, qi.discount_value, qi.discount_amount, qi.subtotal, qi.tax_amount, qi.total,
+                   qi.unit_id, u.name
+            FROM quote_items qi
+            LEFT JOIN units u ON u.id = qi.unit_id
+            WHERE qi.quote_id = ?1 ORDER BY qi.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([quote_id], |row| {
+            Ok(QuoteItem {
+                id: row.get(0)?,
+                product_name: row.get(1)?,
+                unit_price: row.get(2)?,
+                quantity: row.get(3)?,
+                unit_id: row.get(12)?,
+                unit_name: row.get(13)?,
+                tax_rate: row.get(4)?,
+                price_includes_tax: row.get(5)?,
+                discount_type: row.get(6)?,
+                discount_value: row.get(7)?,
+                discount_amount: row.get(8)?,
+                subtotal: row.get(9)?,
+                tax_amount: row.get(10)?,
+                total: row.get(11)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// A quote with its lines.
+pub(crate) fn quote_with_items(conn: &Connection, id: i32) -> Result<Quote, String> {
+    let mut quote = conn
+        .query_row(
+            &format!("{} WHERE q.id = ?1", QUOTE_SELECT),
+            [id],
+            quote_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("عرض السعر غير موجود")?;
+    quote.items = quote_items(conn, id)?;
+    Ok(quote)
+}
+
+/// Checks a quote and returns its validity date, 30 days after the quote
+/// date unless given.
+fn validate_quote(quote: &Quote) -> Result<String, CommandError> {
+    let mut errors = FieldErrors::default();
+
+    let date = parse_date(&quote.date);
+    if let Err(e) = &date {
+        errors.add("date", e.clone());
+    }
+    let valid_until = match (&date, quote.valid_until.as_deref()) {
+        (Ok(date), None) => Some(*date + Duration::days(DEFAULT_VALIDITY_DAYS)),
+        (date, Some(v)) => match parse_date(v) {
+            Ok(v) if date.as_ref().is_ok_and(|d| v < *d) => {
+                errors.add("valid_until", "تاريخ الصلاحية يسبق تاريخ العرض");
+                None
+            }
+            Ok(v) => Some(v),
+            Err(e) => {
+                errors.add("valid_until", e);
+                None
+            }
+        },
+        (Err(_), None) => None,
+    };
+
+    if let Some(status) = quote.status.as_deref() {
+        if !QUOTE_STATUSES.contains(&status) {
+            errors.add("status", format!("حالة غير صالحة: {}", status));
+        }
+    }
+    if let Err(e) = validate_discount(quote.discount_type.as_deref(), quote.discount_value) {
+        errors.add("discount_value", e);
+    }
+    if quote.items.is_empty() {
+        errors.add("items", "يجب أن يحتوي عرض السعر على بند واحد على الأقل");
+    }
+    for item in &quote.items {
+        if item.product_name.trim().is_empty() {
+            errors.add("items", "اسم المنتج مطلوب");
+        }
+        if item.quantity <= 0.0 {
+            errors.add("items", "يجب أن تكون الكمية أكبر من صفر");
+        }
+        if let Err(e) = validate_discount(item.discount_type.as_deref(), item.discount_value) {
+            errors.add("items", e);
+        }
+    }
+
+    errors.into_result()?;
+    Ok(valid_until
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default())
+}
+
+/// Replaces the lines of a quote and sets its totals from them.
+fn save_quote_items(conn: &Connection, quote_id: i32, quote: &Quote) -> Result<(), String> {
+    conn.execute("DELETE FROM quote_items WHERE quote_id = ?1", [quote_id])
+        .map_err(|e| e.to_string())?;
+
+    let (mut lines_subtotal, mut lines_tax) = (0.0, 0.0);
+    for item in &quote.items {
+        let unit_id = match item.unit_id {
+            Some(id) => Some(id),
+            None => catalog_unit(conn, &item.product_name)?,
+        };
+        check_quantity(conn, unit_id, item.quantity)?;
+        let amounts = line_amounts(
+            item.unit_price,
+            item.quantity,
+            item.discount_type.as_deref(),
+            item.discount_value,
+            item.tax_rate,
+            item.price_includes_tax,
+        );
+        conn.execute(
+            r#"
+            INSERT INTO quote_items (quote_id, product_name, unit_price, quantity, discount_type, discount_value,
+                                     discount_amount, tax_rate, price_includes_tax, subtotal, tax_amount, total, unit_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            "#,
+            params![
+                quote_id,
+                item.product_name.trim(),
+                item.unit_price,
+                item.quantity,
+                item.discount_type,
+                item.discount_value,
+                amounts.discount,
+                item.tax_rate,
+                item.price_includes_tax,
+                amounts.subtotal,
+                amounts.tax,
+                amounts.total,
+                unit_id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        lines_subtotal += amounts.subtotal;
+        lines_tax += amounts.tax;
+    }
+
+    let totals = document_totals(
+        lines_subtotal,
+        lines_tax,
+        quote.discount_type.as_deref(),
+        quote.discount_value,
+    );
+    conn.execute(
+        "UPDATE quotes SET discount_amount = ?1, subtotal = ?2, tax_total = ?3, total = ?4 WHERE id = ?5",
+        params![totals.discount, totals.subtotal, totals.tax, totals.total, quote_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Converted quotes are kept as they were accepted
+fn ensure_not_converted(conn: &Connection, id: i32) -> Result<(), String> {
+    let invoice_id: Option<i32> = conn
+        .query_row(
+            &format!("SELECT invoice_id FROM ({}) WHERE id = ?1", QUOTE_SELECT),
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("عرض السعر غير موجود")?;
+    match invoice_id {
+        Some(_) => Err("تم تحويل عرض السعر إلى فاتورة ولا يمكن تعديله".into()),
+        None => Ok(()),
+    }
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Quotes, newest first, optionally with one status or for one customer.
+#[tauri::command]
+pub fn get_quotes(status: Option<String>, customer_id: Option<i32>) -> Result<Vec<Quote>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut conditions = vec!["1 = 1".to_string()];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(status) = status {
+        params.push(Box::new(status));
+        conditions.push(format!("status = ?{}", params.len()));
+    }
+    if let Some(customer_id) = customer_id {
+        params.push(Box::new(customer_id));
+        conditions.push(format!("customer_id = ?{}", params.len()));
+    }
+
+    let sql = format!(
+        "SELECT * FROM ({}) WHERE {} ORDER BY date DESC, id DESC",
+        QUOTE_SELECT,
+        conditions.join(" AND ")
+    );
+    let params_refs: Vec<&dyn ToSql> = params.iter().map(|b| &**b as &dyn ToSql).collect();
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_refs.as_slice(), quote_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_quote(id: i32) -> Result<Quote, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    quote_with_items(&conn, id)
+}
+
+#[tauri::command]
+pub fn create_quote(quote: Quote) -> Result<Quote, CommandError> {
+    let valid_until = validate_quote(&quote)?;
+    let year = parse_date(&quote.date)?.year();
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let currency = currency_code(&tx, quote.currency.as_deref())?;
+
+    let number = next_document_number(&tx, "quotes", "quote_number", "DEV", year)?;
+    tx.execute(
+        r#"
+        INSERT INTO quotes (quote_number, customer_id, date, valid_until, status, notes, discount_type, discount_value, currency)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+        params![
+            number,
+            quote.customer_id,
+            quote.date,
+            valid_until,
+            quote.status.as_deref().unwrap_or("draft"),
+            quote.notes,
+            quote.discount_type,
+            quote.discount_value,
+            currency
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid() as i32;
+    save_quote_items(&tx, id, &quote)?;
+
+    let after = snapshot(&tx, "quotes", id as i64)?;
+    record_audit(&tx, "quote", id as i64, "create", None, after.as_ref())?;
+
+    let quote = quote_with_items(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(quote)
+}
+
+/// Replaces a quote's details and lines. Its number stays.
+#[tauri::command]
+pub fn update_quote(id: i32, quote: Quote) -> Result<Quote, CommandError> {
+    let valid_until = validate_quote(&quote)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let currency = currency_code(&tx, quote.currency.as_deref())?;
+    ensure_not_converted(&tx, id)?;
+
+    let before = snapshot(&tx, "quotes", id as i64)?;
+    tx.execute(
+        r#"
+        UPDATE quotes
+        SET customer_id = ?1, date = ?2, valid_until = ?3, status = IFNULL(?4, status), notes = ?5,
+            discount_type = ?6, discount_value = ?7, currency = ?8
+        WHERE id = ?9
+        "#,
+        params![
+            quote.customer_id,
+            quote.date,
+            valid_until,
+            quote.status,
+            quote.notes,
+            quote.discount_type,
+            quote.discount_value,
+            currency,
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    save_quote_items(&tx, id, &quote)?;
+
+    let after = snapshot(&tx, "quotes", id as i64)?;
+    record_audit(
+        &tx,
+        "quote",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+
+    let quote = quote_with_items(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(quote)
+}
+
+#[tauri::command]
+pub fn set_quote_status(id: i32, status: String) -> Result<(), String> {
+    if !QUOTE_STATUSES.contains(&status.as_str()) {
+        return Err(format!("حالة غير صالحة: {}", status));
+    }
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    ensure_not_converted(&tx, id)?;
+
+    let before = snapshot(&tx, "quotes", id as i64)?;
+    tx.execute(
+        "UPDATE quotes SET status = ?1 WHERE id = ?2",
+        params![status, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = snapshot(&tx, "quotes", id as i64)?;
+    record_audit(
+        &tx,
+        "quote",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_quote(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    ensure_not_converted(&tx, id)?;
+
+    let before = snapshot(&tx, "quotes", id as i64)?;
+    tx.execute("DELETE FROM quotes WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(&tx, "quote", id as i64, "delete", before.as_ref(), None)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Creates a draft invoice from a quote, copying its lines and discount, and
+/// marks the quote accepted. The invoice keeps a link to the quote. Without
+/// a number one is generated; without a date the invoice is dated today.
+#[tauri::command]
+pub fn convert_quote_to_invoice(
+    id: i32,
+    invoice_number: Option<String>,
+    date: Option<String>,
+    override_credit_limit: Option<bool>,
+    override_reason: Option<String>,
+) -> Result<i32, CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let quote = quote_with_items(&tx, id)?;
+    if quote.invoice_id.is_some() {
+        return Err("تم تحويل عرض السعر إلى فاتورة بالفعل".into());
+    }
+    if quote.status.as_deref() == Some("rejected") {
+        return Err("لا يمكن تحويل عرض سعر مرفوض".into());
+    }
+
+    let invoice_number = match invoice_number
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+    {
+        Some(n) => n,
+        None => new_invoice_number(&tx)?,
+    };
+    let date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    parse_date(&date)?;
+
+    let invoice_id = insert_invoice(
+        &tx,
+        Invoice {
+            invoice_number,
+            customer_id: quote.customer_id,
+            date,
+            subtotal: quote.subtotal,
+            tax_total: quote.tax_total,
+            discount_type: quote.discount_type.clone(),
+            discount_value: quote.discount_value,
+            discount_amount: quote.discount_amount,
+            total: quote.total.unwrap_or(0.0),
+            currency: quote.currency.clone(),
+            quote_id: Some(id),
+            ..Default::default()
+        },
+        override_credit_limit.unwrap_or(false),
+        override_reason,
+    )?;
+    for item in quote.items {
+        insert_invoice_item(
+            &tx,
+            InvoiceItem {
+                id: None,
+                invoice_id,
+                product_name: item.product_name,
+                unit_price: item.unit_price,
+                quantity: item.quantity,
+                unit_id: item.unit_id,
+                unit_name: None,
+                tax_rate: Some(item.tax_rate),
+                price_includes_tax: item.price_includes_tax,
+                discount_type: item.discount_type,
+                discount_value: item.discount_value,
+                discount_amount: None,
+                subtotal: None,
+                tax_amount: None,
+                total: 0.0,
+            },
+        )?;
+    }
+
+    let before = snapshot(&tx, "quotes", id as i64)?;
+    tx.execute("UPDATE quotes SET status = 'accepted' WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    let after = snapshot(&tx, "quotes", id as i64)?;
+    record_audit(
+        &tx,
+        "quote",
+        id as i64,
+        "convert",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(invoice_id)
+}
@@ -0,0 +1,692 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::currencies::currency_code;
+use crate::commands::discounts::validate_discount;
+use crate::commands::invoices::{insert_invoice, insert_invoice_item};
+use crate::commands::numbering::new_invoice_number;
+use crate::commands::products::catalog_unit;
+use crate::commands::taxes::{document_totals, line_amounts};
+use crate::commands::units::check_quantity;
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{
+    Invoice, InvoiceItem, RecurringInvoice, RecurringInvoiceItem, RecurringInvoiceRun,
+};
+use crate::terms::parse_date;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Result, TransactionBehavior};
+use std::sync::{Condvar, Mutex};
+
+//
+// ==================== Helpers ====================
+//
+
+/// Outcome of the catch-up run started with the app: `None` while it runs,
+/// then the error it ended with, if any.
+static STARTUP_RUN: Mutex<Option<Option<String>>> = Mutex::new(None);
+static STARTUP_RUN_DONE: Condvar = Condvar::new();
+
+const FREQUENCIES: &[&str] = &["weekly", "monthly", "quarterly", "yearly"];
+
+const RECURRING_INVOICE_SELECT: &str = r#"
+    SELECT
+      r.id,
+      r.customer_id,
+      c.name,
+      r.frequency,
+      r.start_date,
+      r.end_date,
+      r.next_run,
+      r.active,
+      r.notes,
+      r.discount_type,
+      r.discount_value,
+      r.total,
+      r.last_error,
+      r.created_at,
+      r.currency
+    FROM recurring_invoices r
+    LEFT JOIN customers c ON c.id = r.customer_id
+"#;
+
+fn recurring_invoice_from_row(row: &rusqlite::Row) -> rusqlite::Result<RecurringInvoice> {
+    Ok(RecurringInvoice {
+        id: row.get(0)?,
+        customer_id: row.get(1)?,
+        customer_name: row.get(2)?,
+        frequency: row.get(3)?,
+        start_date: row.get(4)?,
+        end_date: row.get(5)?,
+        next_run: row.get(6)?,
+        active: row.get(7)?,
+        notes: row.get(8)?,
+        discount_type: row.get(9)?,
+        discount_value: row.get(10)?,
+        total: row.get(11)?,
+        currency: row.get(14)?,
+        last_error: row.get(12)?,
+        created_at: row.get(13)?,
+        items: Vec::new(),
+    })
+}
+
+fn recurring_invoice_items(
+    conn: &Connection,
+    id: i32,
+) -> Result<Vec<RecurringInvoiceItem>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT ri.id, ri.product_name, ri.unit_price, ri.quantity, ri.tax_rate, ri.price_includes_tax,
+                   ri.discount_type, ri.discount_value, ri.unit_id, u.name
+            FROM recurring_invoice_items ri
+            LEFT JOIN units u ON u.id = ri.unit_id
+            WHERE ri.recurring_invoice_id = ?1 ORDER BY ri.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([id], |row| {
+            Ok(RecurringInvoiceItem {
+                id: row.get(0)?,
+                product_name: row.get(1)?,
+                unit_price: row.get(2)?,
+                quantity: row.get(3)?,
+                unit_id: row.get(8)?,
+                unit_name: row.get(9)?,
+                tax_rate: row.get(4)?,
+                price_includes_tax: row.get(5)?,
+                discount_type: row.get(6)?,
+                discount_value: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+fn recurring_invoice_with_items(conn: &Connection, id: i32) -> Result<RecurringInvoice, String> {
+    let mut schedule = conn
+        .query_row(
+            &format!("{} WHERE r.id = ?1", RECURRING_INVOICE_SELECT),
+            [id],
+            recurring_invoice_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الفاتورة الدورية غير موجودة")?;
+    schedule.items = recurring_invoice_items(conn, id)?;
+    Ok(schedule)
+}
+
+/// The run after `current`. Monthly periods count from `start` so a
+/// schedule starting on the 31st comes back to it after shorter months.
+fn next_run_after(start: NaiveDate, current: NaiveDate, frequency: &str) -> NaiveDate {
+    let step = match frequency {
+        "weekly" => return current + Duration::days(7),
+        "quarterly" => 3,
+        "yearly" => 12,
+        _ => 1,
+    };
+    let months =
+        (current.year() - start.year()) * 12 + current.month() as i32 - start.month() as i32 + step;
+    start
+        .checked_add_months(Months::new(months.max(step) as u32))
+        .unwrap_or(current)
+}
+
+/// Invoice total of a schedule's lines, as insert_invoice_item will compute it.
+fn schedule_total(schedule: &RecurringInvoice) -> f64 {
+    let (mut lines_subtotal, mut lines_tax) = (0.0, 0.0);
+    for item in &schedule.items {
+        let amounts = line_amounts(
+            item.unit_price,
+            item.quantity,
+            item.discount_type.as_deref(),
+            item.discount_value,
+            item.tax_rate,
+            item.price_includes_tax,
+        );
+        lines_subtotal += amounts.subtotal;
+        lines_tax += amounts.tax;
+    }
+    document_totals(
+        lines_subtotal,
+        lines_tax,
+        schedule.discount_type.as_deref(),
+        schedule.discount_value,
+    )
+    .total
+}
+
+fn validate_schedule(schedule: &RecurringInvoice) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+
+    if !FREQUENCIES.contains(&schedule.frequency.as_str()) {
+        errors.add(
+            "frequency",
+            format!("دورية غير صالحة: {}", schedule.frequency),
+        );
+    }
+    let start = parse_date(&schedule.start_date);
+    if let Err(e) = &start {
+        errors.add("start_date", e.clone());
+    }
+    if let Some(end) = schedule.end_date.as_deref() {
+        match parse_date(end) {
+            Ok(end) if start.as_ref().is_ok_and(|s| end < *s) => {
+                errors.add("end_date", "تاريخ الانتهاء يسبق تاريخ البدء");
+            }
+            Ok(_) => {}
+            Err(e) => errors.add("end_date", e),
+        }
+    }
+    if let Some(next_run) = schedule.next_run.as_deref() {
+        if let Err(e) = parse_date(next_run) {
+            errors.add("next_run", e);
+        }
+    }
+    if let Err(e) = validate_discount(schedule.discount_type.as_deref(), schedule.discount_value) {
+        errors.add("discount_value", e);
+    }
+    if schedule.items.is_empty() {
+        errors.add(
+            "items",
+            "يجب أن تحتوي الفاتورة الدورية على بند واحد على الأقل",
+        );
+    }
+    for item in &schedule.items {
+        if item.product_name.trim().is_empty() {
+            errors.add("items", "اسم المنتج مطلوب");
+        }
+        if item.quantity <= 0.0 {
+            errors.add("items", "يجب أن تكون الكمية أكبر من صفر");
+        }
+        if let Err(e) = validate_discount(item.discount_type.as_deref(), item.discount_value) {
+            errors.add("items", e);
+        }
+    }
+
+    errors.into_result()
+}
+
+fn save_schedule_items(
+    conn: &Connection,
+    id: i32,
+    schedule: &RecurringInvoice,
+) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM recurring_invoice_items WHERE recurring_invoice_id = ?1",
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    for item in &schedule.items {
+        let unit_id = match item.unit_id {
+            Some(id) => Some(id),
+            None => catalog_unit(conn, &item.product_name)?,
+        };
+        check_quantity(conn, unit_id, item.quantity)?;
+        conn.execute(
+            r#"
+            INSERT INTO recurring_invoice_items (recurring_invoice_id, product_name, unit_price, quantity, tax_rate,
+                                                 price_includes_tax, discount_type, discount_value, unit_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            params![
+                id,
+                item.product_name.trim(),
+                item.unit_price,
+                item.quantity,
+                item.tax_rate,
+                item.price_includes_tax,
+                item.discount_type,
+                item.discount_value,
+                unit_id
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "UPDATE recurring_invoices SET total = ?1 WHERE id = ?2",
+        params![schedule_total(schedule), id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Creates the draft invoice of one run of a schedule and logs it.
+fn generate_run(
+    conn: &Connection,
+    schedule: &RecurringInvoice,
+    run_date: &str,
+) -> Result<RecurringInvoiceRun, CommandError> {
+    let id = schedule.id.ok_or("الفاتورة الدورية غير موجودة")?;
+    let invoice_number = new_invoice_number(conn)?;
+    let invoice_id = insert_invoice(
+        conn,
+        Invoice {
+            invoice_number: invoice_number.clone(),
+            customer_id: schedule.customer_id,
+            date: run_date.to_string(),
+            discount_type: schedule.discount_type.clone(),
+            discount_value: schedule.discount_value,
+            total: schedule_total(schedule),
+            // At the rate of the run date
+            currency: schedule.currency.clone(),
+            ..Default::default()
+        },
+        false,
+        None,
+    )?;
+    for item in &schedule.items {
+        insert_invoice_item(
+            conn,
+            InvoiceItem {
+                id: None,
+                invoice_id,
+                product_name: item.product_name.clone(),
+                unit_price: item.unit_price,
+                quantity: item.quantity,
+                unit_id: item.unit_id,
+                unit_name: None,
+                tax_rate: Some(item.tax_rate),
+                price_includes_tax: item.price_includes_tax,
+                discount_type: item.discount_type.clone(),
+                discount_value: item.discount_value,
+                discount_amount: None,
+                subtotal: None,
+                tax_amount: None,
+                total: 0.0,
+            },
+        )?;
+    }
+
+    conn.execute(
+        "INSERT INTO recurring_invoice_runs (recurring_invoice_id, invoice_id, run_date) VALUES (?1, ?2, ?3)",
+        params![id, invoice_id, run_date],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(RecurringInvoiceRun {
+        id: conn.last_insert_rowid() as i32,
+        recurring_invoice_id: id,
+        invoice_id: Some(invoice_id),
+        invoice_number: Some(invoice_number),
+        run_date: run_date.to_string(),
+        created_at: None,
+    })
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_recurring_invoices(customer_id: Option<i32>) -> Result<Vec<RecurringInvoice>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE (?1 IS NULL OR r.customer_id = ?1) ORDER BY r.active DESC, r.next_run, r.id",
+            RECURRING_INVOICE_SELECT
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([customer_id], recurring_invoice_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_recurring_invoice(id: i32) -> Result<RecurringInvoice, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    recurring_invoice_with_items(&conn, id)
+}
+
+#[tauri::command]
+pub fn create_recurring_invoice(
+    schedule: RecurringInvoice,
+) -> Result<RecurringInvoice, CommandError> {
+    validate_schedule(&schedule)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let currency = currency_code(&tx, schedule.currency.as_deref())?;
+
+    tx.execute(
+        r#"
+        INSERT INTO recurring_invoices (customer_id, frequency, start_date, end_date, next_run, active, notes,
+                                        discount_type, discount_value, currency)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        "#,
+        params![
+            schedule.customer_id,
+            schedule.frequency,
+            schedule.start_date,
+            schedule.end_date,
+            schedule.next_run.as_deref().unwrap_or(&schedule.start_date),
+            schedule.active.unwrap_or(true),
+            schedule.notes,
+            schedule.discount_type,
+            schedule.discount_value,
+            currency
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid() as i32;
+    save_schedule_items(&tx, id, &schedule)?;
+
+    let after = snapshot(&tx, "recurring_invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "recurring_invoice",
+        id as i64,
+        "create",
+        None,
+        after.as_ref(),
+    )?;
+
+    let schedule = recurring_invoice_with_items(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(schedule)
+}
+
+/// Replaces a schedule's details and lines. Invoices already generated are
+/// left as they are.
+#[tauri::command]
+pub fn update_recurring_invoice(
+    id: i32,
+    schedule: RecurringInvoice,
+) -> Result<RecurringInvoice, CommandError> {
+    validate_schedule(&schedule)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let currency = currency_code(&tx, schedule.currency.as_deref())?;
+    let before = snapshot(&tx, "recurring_invoices", id as i64)?;
+    if before.is_none() {
+        return Err("الفاتورة الدورية غير موجودة".into());
+    }
+    tx.execute(
+        r#"
+        UPDATE recurring_invoices
+        SET customer_id = ?1, frequency = ?2, start_date = ?3, end_date = ?4, next_run = IFNULL(?5, next_run),
+            active = IFNULL(?6, active), notes = ?7, discount_type = ?8, discount_value = ?9, currency = ?10,
+            last_error = NULL
+        WHERE id = ?11
+        "#,
+        params![
+            schedule.customer_id,
+            schedule.frequency,
+            schedule.start_date,
+            schedule.end_date,
+            schedule.next_run,
+            schedule.active,
+            schedule.notes,
+            schedule.discount_type,
+            schedule.discount_value,
+            currency,
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    save_schedule_items(&tx, id, &schedule)?;
+
+    let after = snapshot(&tx, "recurring_invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "recurring_invoice",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+
+    let schedule = recurring_invoice_with_items(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(schedule)
+}
+
+/// Pauses or resumes a schedule.
+#[tauri::command]
+pub fn set_recurring_invoice_active(id: i32, active: bool) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "recurring_invoices", id as i64)?;
+    if before.is_none() {
+        return Err("الفاتورة الدورية غير موجودة".into());
+    }
+    tx.execute(
+        "UPDATE recurring_invoices SET active = ?1 WHERE id = ?2",
+        params![active, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = snapshot(&tx, "recurring_invoices", id as i64)?;
+    record_audit(
+        &tx,
+        "recurring_invoice",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Deletes a schedule and its log. Invoices it generated are kept.
+#[tauri::command]
+pub fn delete_recurring_invoice(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "recurring_invoices", id as i64)?;
+    if before.is_none() {
+        return Err("الفاتورة الدورية غير موجودة".into());
+    }
+    tx.execute("DELETE FROM recurring_invoices WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(
+        &tx,
+        "recurring_invoice",
+        id as i64,
+        "delete",
+        before.as_ref(),
+        None,
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Invoices generated from a schedule, newest first.
+#[tauri::command]
+pub fn get_recurring_invoice_runs(
+    recurring_invoice_id: i32,
+) -> Result<Vec<RecurringInvoiceRun>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT r.id, r.recurring_invoice_id, r.invoice_id, i.invoice_number, r.run_date, r.created_at
+            FROM recurring_invoice_runs r
+            LEFT JOIN invoices i ON i.id = r.invoice_id
+            WHERE r.recurring_invoice_id = ?1
+            ORDER BY r.run_date DESC, r.id DESC
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([recurring_invoice_id], |row| {
+            Ok(RecurringInvoiceRun {
+                id: row.get(0)?,
+                recurring_invoice_id: row.get(1)?,
+                invoice_id: row.get(2)?,
+                invoice_number: row.get(3)?,
+                run_date: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// Catches up on the runs that fell due while the app was closed, keeping
+/// the outcome for `get_recurring_startup_error`.
+pub fn run_startup_generation() {
+    let error = std::panic::catch_unwind(generate_recurring_invoices)
+        .unwrap_or_else(|_| Err("تعذر إنشاء الفواتير الدورية".into()))
+        .err();
+    let mut outcome = STARTUP_RUN.lock().unwrap_or_else(|e| e.into_inner());
+    *outcome = Some(error);
+    STARTUP_RUN_DONE.notify_all();
+}
+
+/// Error of the catch-up run started with the app, once it has ended. The UI
+/// asks for it when it is ready to show it, so a failure cannot be missed.
+#[tauri::command(async)]
+pub fn get_recurring_startup_error() -> Result<Option<String>, String> {
+    let outcome = STARTUP_RUN.lock().unwrap_or_else(|e| e.into_inner());
+    let outcome = STARTUP_RUN_DONE
+        .wait_while(outcome, |o| o.is_none())
+        .unwrap_or_else(|e| e.into_inner());
+    Ok(outcome.clone().flatten())
+}
+
+/// Generates a draft invoice for every run of the active schedules due up to
+/// today, catching up on runs missed while the app was closed. Called on
+/// startup and on demand. A run that fails (e.g. over the credit limit) is
+/// retried next time and its error kept on the schedule.
+#[tauri::command]
+pub fn generate_recurring_invoices() -> Result<Vec<RecurringInvoiceRun>, String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let today = chrono::Local::now().date_naive();
+
+    let due: Vec<i32> = {
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT r.id FROM recurring_invoices r
+                JOIN customers c ON c.id = r.customer_id AND c.deleted_at IS NULL
+                WHERE r.active = 1 AND r.next_run <= ?1 AND (r.end_date IS NULL OR r.next_run <= r.end_date)
+                ORDER BY r.next_run, r.id
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([today.format("%Y-%m-%d").to_string()], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut runs = Vec::new();
+    for id in due {
+        // A schedule that cannot be read is skipped with its error kept
+        let loaded = recurring_invoice_with_items(&conn, id).and_then(|schedule| {
+            let start = parse_date(&schedule.start_date)?;
+            let end = schedule.end_date.as_deref().map(parse_date).transpose()?;
+            let next_run =
+                parse_date(schedule.next_run.as_deref().unwrap_or(&schedule.start_date))?;
+            Ok((schedule, start, end, next_run))
+        });
+        let (schedule, start, end, mut next_run) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                conn.execute(
+                    "UPDATE recurring_invoices SET last_error = ?1 WHERE id = ?2",
+                    params![e, id],
+                )
+                .map_err(|e| e.to_string())?;
+                continue;
+            }
+        };
+
+        while next_run <= today && end.map_or(true, |end| next_run <= end) {
+            let run_date = next_run.format("%Y-%m-%d").to_string();
+            let following = next_run_after(start, next_run, &schedule.frequency);
+
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(|e| e.to_string())?;
+            // Claim the run before generating it: a generation started
+            // meanwhile (on startup or on demand) may already have taken it
+            let claimed = tx
+                .execute(
+                    r#"
+                    UPDATE recurring_invoices SET next_run = ?1, last_error = NULL
+                    WHERE id = ?2 AND substr(next_run, 1, 10) = ?3
+                    "#,
+                    params![following.format("%Y-%m-%d").to_string(), id, run_date],
+                )
+                .map_err(|e| e.to_string())?;
+            if claimed == 0 {
+                break;
+            }
+            match generate_run(&tx, &schedule, &run_date) {
+                Ok(run) => {
+                    tx.commit().map_err(|e| e.to_string())?;
+                    runs.push(run);
+                    next_run = following;
+                }
+                Err(e) => {
+                    drop(tx);
+                    conn.execute(
+                        "UPDATE recurring_invoices SET last_error = ?1 WHERE id = ?2",
+                        params![format!("{}: {}", run_date, String::from(e)), id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    #[test]
+    fn monthly_comes_back_to_month_end() {
+        let start = date("2026-01-31");
+        let feb = next_run_after(start, start, "monthly");
+        assert_eq!(feb, date("2026-02-28"));
+        let mar = next_run_after(start, feb, "monthly");
+        assert_eq!(mar, date("2026-03-31"));
+        assert_eq!(next_run_after(start, mar, "monthly"), date("2026-04-30"));
+        assert_eq!(
+            next_run_after(start, date("2026-12-31"), "monthly"),
+            date("2027-01-31")
+        );
+    }
+
+    #[test]
+    fn quarterly_and_yearly() {
+        let start = date("2025-11-30");
+        let feb = next_run_after(start, start, "quarterly");
+        assert_eq!(feb, date("2026-02-28"));
+        assert_eq!(next_run_after(start, feb, "quarterly"), date("2026-05-30"));
+
+        let leap = date("2024-02-29");
+        let next = next_run_after(leap, leap, "yearly");
+        assert_eq!(next, date("2025-02-28"));
+        assert_eq!(
+            next_run_after(leap, date("2027-02-28"), "yearly"),
+            date("2028-02-29")
+        );
+    }
+
+    #[test]
+    fn weekly_adds_seven_days() {
+        let start = date("2026-12-28");
+        assert_eq!(next_run_after(start, start, "weekly"), date("2027-01-04"));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::commands::audit::{record_audit, snapshot, snapshot_customer, snapshot_invoice};
+use crate::commands::lifecycle::sync_invoice_payments;
+use crate::db::get_db;
+use crate::models::DeletedItem;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+fn table_for(entity: &str) -> Result<&'static str, String> {
+    match entity {
+        "customer" => Ok("customers"),
+        "invoice" => Ok("invoices"),
+        "payment" => Ok("payments"),
+        _ => Err(format!("نوع غير معروف: {}", entity)),
+    }
+}
+
+/// Returns `deleted_at` of a record, or an error if it is not in the recycle bin.
+fn deleted_at(conn: &Connection, table: &str, id: i64) -> Result<String, String> {
+    let sql = format!("SELECT deleted_at FROM {} WHERE id = ?1", table);
+    let value: Option<Option<String>> = conn
+        .query_row(&sql, [id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    match value {
+        Some(Some(ts)) => Ok(ts),
+        Some(None) => Err("السجل غير موجود في سلة المحذوفات".into()),
+        None => Err("السجل غير موجود".into()),
+    }
+}
+
+fn owner_is_deleted(conn: &Connection, table: &str, id: i64) -> Result<bool, String> {
+    let sql = format!(
+        "SELECT c.deleted_at IS NOT NULL FROM {} t JOIN customers c ON c.id = t.customer_id WHERE t.id = ?1",
+        table
+    );
+    conn.query_row(&sql, [id], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn entity_snapshot(
+    conn: &Connection,
+    entity: &str,
+    id: i64,
+) -> Result<Option<serde_json::Value>, String> {
+    match entity {
+        "customer" => snapshot_customer(conn, id),
+        "invoice" => snapshot_invoice(conn, id),
+        _ => snapshot(conn, table_for(entity)?, id),
+    }
+}
+
+#[tauri::command]
+pub fn get_deleted_items(entity: Option<String>) -> Result<Vec<DeletedItem>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    // Invoices and payments of a deleted customer are restored with it, so
+    // only the customer is listed
+    let sql = r#"
+        SELECT * FROM (
+            SELECT 'customer' AS entity, c.id, c.name AS label, NULL AS customer_name, NULL AS amount, c.deleted_at
+            FROM customers c
+            WHERE c.deleted_at IS NOT NULL
+
+            UNION ALL
+
+            SELECT 'invoice', i.id, i.invoice_number, c.name, i.total, i.deleted_at
+            FROM invoices i
+            JOIN customers c ON i.customer_id = c.id
+            WHERE i.deleted_at IS NOT NULL AND c.deleted_at IS NULL
+
+            UNION ALL
+
+            SELECT 'payment', p.id, p.payment_number, c.name, p.amount, p.deleted_at
+            FROM payments p
+            JOIN customers c ON p.customer_id = c.id
+            WHERE p.deleted_at IS NOT NULL AND c.deleted_at IS NULL
+        )
+        WHERE ?1 IS NULL OR entity = ?1
+        ORDER BY deleted_at DESC
+    "#;
+
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![entity], |row| {
+            Ok(DeletedItem {
+                entity: row.get(0)?,
+                id: row.get(1)?,
+                label: row.get(2)?,
+                customer_name: row.get(3)?,
+                amount: row.get(4)?,
+                deleted_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+#[tauri::command]
+pub fn restore_deleted_item(entity: String, id: i64) -> Result<(), String> {
+    let table = table_for(&entity)?;
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let ts = deleted_at(&tx, table, id)?;
+
+    if entity == "customer" {
+        tx.execute(
+            "UPDATE invoices SET deleted_at = NULL WHERE customer_id = ?1 AND deleted_at = ?2",
+            params![id, ts],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE payments SET deleted_at = NULL WHERE customer_id = ?1 AND deleted_at = ?2",
+            params![id, ts],
+        )
+        .map_err(|e| e.to_string())?;
+    } else if owner_is_deleted(&tx, table, id)? {
+        return Err("يجب استعادة العميل أولاً".into());
+    }
+
+    let before = entity_snapshot(&tx, &entity, id)?;
+
+    tx.execute(
+        &format!("UPDATE {} SET deleted_at = NULL WHERE id = ?1", table),
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    if entity == "payment" {
+        if let Some(invoice_id) = before.as_ref().and_then(|b| b["invoice_id"].as_i64()) {
+            sync_invoice_payments(&tx, invoice_id as i32)?;
+        }
+    }
+
+    let after = entity_snapshot(&tx, &entity, id)?;
+    record_audit(&tx, &entity, id, "restore", before.as_ref(), after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn purge_deleted_item(entity: String, id: i64) -> Result<(), String> {
+    let table = table_for(&entity)?;
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    // Only records already in the recycle bin can be purged
+    deleted_at(&tx, table, id)?;
+
+    let before = entity_snapshot(&tx, &entity, id)?;
+
+    // Items, and for customers their invoices and payments, go with the row
+    // through ON DELETE CASCADE
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", table), [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(&tx, &entity, id, "purge", before.as_ref(), None)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
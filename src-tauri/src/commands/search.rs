@@ -0,0 +1,202 @@
+use crate::db::get_db;
+use crate::models::SearchHit;
+use crate::normalize::normalize_text;
+use rusqlite::{params, Result};
+
+/// Turns user input into an FTS5 query: every word must match, the last
+/// characters typed are treated as a prefix.
+fn build_match_query(query: &str) -> String {
+    normalize_text(query)
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Characters that belong to a word: letters, digits and the marks
+/// `normalize_text` drops (tashkeel, tatweel), so "مُحَمَّد" stays one word.
+fn is_word_char(c: char) -> bool {
+    let mut buf = [0u8; 4];
+    c.is_alphanumeric() || normalize_text(c.encode_utf8(&mut buf)).is_empty()
+}
+
+/// Byte ranges of the words in `text`.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (is_word_char(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, text.len()));
+    }
+    spans
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Builds the snippet from the stored (not normalized) text: words whose
+/// normalized form starts with a query term are wrapped in `<mark>`, and
+/// long texts are cut to `max_words` words around the first hit.
+fn highlight(text: &str, query: &str, max_words: usize) -> String {
+    let normalized_query = normalize_text(query);
+    let terms: Vec<&str> = word_spans(&normalized_query)
+        .into_iter()
+        .map(|(s, e)| &normalized_query[s..e])
+        .collect();
+    let spans = word_spans(text);
+    let hits: Vec<bool> = spans
+        .iter()
+        .map(|&(s, e)| {
+            let word = normalize_text(&text[s..e]);
+            terms.iter().any(|t| word.starts_with(t))
+        })
+        .collect();
+
+    let first_hit = hits.iter().position(|&h| h).unwrap_or(0);
+    let start = first_hit
+        .saturating_sub(2)
+        .min(spans.len().saturating_sub(max_words));
+    let end = (start + max_words).min(spans.len());
+    if start >= end {
+        return escape_html(text);
+    }
+
+    let mut out = String::new();
+    let mut pos = 0;
+    if start > 0 {
+        out.push('…');
+        pos = spans[start].0;
+    }
+    for (&(s, e), &hit) in spans[start..end].iter().zip(&hits[start..end]) {
+        out.push_str(&escape_html(&text[pos..s]));
+        if hit {
+            out.push_str("<mark>");
+            out.push_str(&escape_html(&text[s..e]));
+            out.push_str("</mark>");
+        } else {
+            out.push_str(&escape_html(&text[s..e]));
+        }
+        pos = e;
+    }
+    if end < spans.len() {
+        out.push('…');
+    } else {
+        out.push_str(&escape_html(&text[pos..]));
+    }
+    out
+}
+
+#[tauri::command]
+pub fn global_search(query: String, limit: Option<i64>) -> Result<Vec<SearchHit>, String> {
+    let match_query = build_match_query(&query);
+    if match_query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    // Hits are joined back to their records so soft-deleted rows (and rows of
+    // deleted customers) are skipped. Titles weigh ten times more than bodies.
+    // The index only holds normalized text, so the snippet is built from the
+    // stored fields the entry was made of.
+    let sql = r#"
+        SELECT
+            s.entity,
+            s.record_id,
+            COALESCE(iv.id, pa.invoice_id) AS invoice_id,
+            c.id AS customer_id,
+            c.name AS customer_name,
+            s.label,
+            CASE s.entity
+                WHEN 'customer' THEN c.name || ' ' || IFNULL(c.phone, '') || ' ' ||
+                    IFNULL(c.address, '') || ' ' || IFNULL(c.notes, '') || ' ' ||
+                    IFNULL((SELECT group_concat(value, ' ') FROM customer_field_values
+                            WHERE customer_id = c.id), '')
+                WHEN 'invoice' THEN iv.invoice_number || ' ' || iv.date
+                WHEN 'payment' THEN pa.payment_number || ' ' || pa.date || ' ' || IFNULL(pa.notes, '')
+                ELSE s.label
+            END AS original,
+            bm25(search_index, 0, 0, 0, 0, 10.0, 1.0) AS score
+        FROM search_index s
+        LEFT JOIN invoices iv
+          ON (s.entity = 'invoice' AND iv.id = s.record_id)
+          OR (s.entity = 'item' AND iv.id = s.parent_id)
+        LEFT JOIN payments pa ON s.entity = 'payment' AND pa.id = s.record_id
+        JOIN customers c ON c.id = CASE s.entity
+            WHEN 'customer' THEN s.record_id
+            WHEN 'payment' THEN pa.customer_id
+            ELSE iv.customer_id
+        END
+        WHERE search_index MATCH ?1
+          AND c.deleted_at IS NULL
+          AND (iv.id IS NULL OR iv.deleted_at IS NULL)
+          AND (pa.id IS NULL OR pa.deleted_at IS NULL)
+        ORDER BY score
+        LIMIT ?2
+    "#;
+
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![match_query, limit.unwrap_or(50)], |row| {
+            Ok(SearchHit {
+                entity: row.get(0)?,
+                record_id: row.get(1)?,
+                invoice_id: row.get(2)?,
+                customer_id: row.get(3)?,
+                customer_name: row.get(4)?,
+                label: row.get(5)?,
+                snippet: highlight(&row.get::<_, String>(6)?, &query, 12),
+                score: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_original_text_through_normalization() {
+        assert_eq!(
+            highlight("أحمد مُحَمَّد", "احمد محمد", 12),
+            "<mark>أحمد</mark> <mark>مُحَمَّد</mark>"
+        );
+    }
+
+    #[test]
+    fn marks_prefixes_and_keeps_punctuation() {
+        assert_eq!(
+            highlight("INV-0012", "inv-00", 12),
+            "<mark>INV</mark>-<mark>0012</mark>"
+        );
+    }
+
+    #[test]
+    fn cuts_long_text_around_first_hit() {
+        let text = "a b c d e f g h target i j k";
+        assert_eq!(highlight(text, "target", 4), "…g h <mark>target</mark> i…");
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            highlight("<b> & x", "x", 12),
+            "&lt;b&gt; &amp; <mark>x</mark>"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::currency::{is_known_currency, DEFAULT_BASE_CURRENCY};
+use crate::db::get_db;
+use crate::phone::{is_known_country, DEFAULT_COUNTRY};
+use crate::terms::{is_known_terms, DEFAULT_PAYMENT_TERMS};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+
+pub(crate) fn read_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+        row.get(0)
+    })
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// Country used for phone numbers typed without a country code.
+pub(crate) fn default_country(conn: &Connection) -> Result<String, String> {
+    Ok(read_setting(conn, "default_country")?.unwrap_or_else(|| DEFAULT_COUNTRY.to_string()))
+}
+
+/// Payment terms (and days for "custom" terms) of customers without their own.
+pub(crate) fn default_payment_terms(conn: &Connection) -> Result<(String, Option<i32>), String> {
+    let terms = read_setting(conn, "default_payment_terms")?
+        .unwrap_or_else(|| DEFAULT_PAYMENT_TERMS.to_string());
+    let days = read_setting(conn, "default_payment_terms_days")?.and_then(|d| d.parse().ok());
+    Ok((terms, days))
+}
+
+/// Currency the books are kept in; balances and reports are converted to it.
+pub(crate) fn base_currency(conn: &Connection) -> Result<String, String> {
+    Ok(read_setting(conn, "base_currency")?.unwrap_or_else(|| DEFAULT_BASE_CURRENCY.to_string()))
+}
+
+/// Renames the base currency on the documents kept in it. Refused once
+/// exchange rates or documents in other currencies exist, as their rates are
+/// against the old one.
+fn change_base_currency(conn: &mut Connection, currency: &str) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let current = base_currency(&tx)?;
+    if current != currency {
+        let foreign: bool = tx
+            .query_row(
+                r#"
+                SELECT EXISTS (SELECT 1 FROM invoices WHERE currency <> ?1)
+                    OR EXISTS (SELECT 1 FROM payments WHERE currency <> ?1)
+                    OR EXISTS (SELECT 1 FROM quotes WHERE currency <> ?1)
+                    OR EXISTS (SELECT 1 FROM recurring_invoices WHERE currency <> ?1)
+                    OR EXISTS (SELECT 1 FROM exchange_rates)
+                "#,
+                [&current],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if foreign {
+            return Err(
+                "لا يمكن تغيير العملة الأساسية بعد تسجيل أسعار صرف أو مستندات بعملات أخرى".to_string(),
+            );
+        }
+        for table in [
+            "invoices",
+            "payments",
+            "credit_notes",
+            "quotes",
+            "recurring_invoices",
+        ] {
+            tx.execute(&format!("UPDATE {} SET currency = ?1", table), [currency])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.execute(
+        "INSERT INTO settings (key, value) VALUES ('base_currency', ?1) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [currency],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_settings() -> Result<HashMap<String, String>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+#[tauri::command]
+pub fn set_setting(key: String, value: String) -> Result<(), String> {
+    if key == "default_country" && !is_known_country(&value) {
+        return Err(format!("الدولة غير معروفة: {}", value));
+    }
+    if key == "credit_limit_mode" && !matches!(value.as_str(), "block" | "warn") {
+        return Err(format!("وضع الحد الائتماني غير صالح: {}", value));
+    }
+    if key == "default_payment_terms" && !is_known_terms(&value) {
+        return Err(format!("شروط الدفع غير معروفة: {}", value));
+    }
+    if key == "default_payment_terms_days" && !value.parse::<i32>().is_ok_and(|d| d >= 0) {
+        return Err(format!("عدد أيام الأجل غير صالح: {}", value));
+    }
+    // Custom terms only work once their number of days is set
+    if key == "default_payment_terms" && value == "custom" {
+        let conn = get_db().map_err(|e| e.to_string())?;
+        if default_payment_terms(&conn)?.1.is_none() {
+            return Err("حدد عدد أيام الأجل الافتراضي قبل اختيار أجل مخصص".into());
+        }
+    }
+    if key == "base_currency" {
+        if !is_known_currency(&value) {
+            return Err(format!("العملة غير معروفة: {}", value));
+        }
+        let mut conn = get_db().map_err(|e| e.to_string())?;
+        return change_base_currency(&mut conn, &value);
+    }
+
+    let conn = get_db().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,357 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::products::get_products;
+use crate::commands::units::{check_quantity, convert};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{Product, StockMovement, StockValuation, StockValuationRow};
+use crate::terms::parse_date;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+//
+// ==================== Helpers ====================
+//
+
+/// Name and unit of a product whose stock is tracked.
+fn tracked_product(conn: &Connection, product_id: i32) -> Result<(String, Option<i32>), String> {
+    let (name, unit_id, tracked): (String, Option<i32>, bool) = conn
+        .query_row(
+            "SELECT name, unit_id, track_stock FROM products WHERE id = ?1",
+            [product_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("المنتج غير موجود")?;
+    if !tracked {
+        return Err(format!("المنتج {} لا يخضع لتتبع المخزون", name));
+    }
+    Ok((name, unit_id))
+}
+
+fn movement_date(date: Option<String>) -> Result<String, String> {
+    match date {
+        Some(d) => Ok(parse_date(&d)?.format("%Y-%m-%d").to_string()),
+        None => Ok(chrono::Local::now().format("%Y-%m-%d").to_string()),
+    }
+}
+
+/// Takes the lines of an issued invoice out of stock. Lines are matched to
+/// tracked products by name and converted to the product's unit.
+pub(crate) fn record_invoice_stock(conn: &Connection, invoice_id: i32) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT it.id, it.product_name, it.quantity, it.unit_id, p.id, p.unit_id, i.date
+            FROM invoice_items it
+            JOIN invoices i ON i.id = it.invoice_id
+            JOIN products p ON p.name_normalized = normalize_text(it.product_name) AND p.track_stock = 1
+            WHERE it.invoice_id = ?1
+            ORDER BY it.id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let lines = stmt
+        .query_map([invoice_id], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, Option<i32>>(3)?,
+                row.get::<_, i32>(4)?,
+                row.get::<_, Option<i32>>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    for (item_id, product_name, quantity, line_unit, product_id, product_unit, date) in lines {
+        let quantity = match (line_unit, product_unit) {
+            (Some(from), Some(to)) => {
+                convert(conn, quantity, from, to).map_err(|e| format!("{}: {}", product_name, e))?
+            }
+            _ => quantity,
+        };
+        conn.execute(
+            "INSERT INTO stock_movements (product_id, date, quantity, kind, invoice_id, invoice_item_id) VALUES (?1, ?2, ?3, 'sale', ?4, ?5)",
+            params![product_id, date, -quantity, invoice_id, item_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Puts the returned lines of a credit note back in stock, in proportion to
+/// what their sale took out.
+pub(crate) fn record_credit_note_stock(
+    conn: &Connection,
+    credit_note_id: i32,
+) -> Result<(), String> {
+    conn.execute(
+        r#"
+        INSERT INTO stock_movements (product_id, date, quantity, kind, invoice_id, invoice_item_id, credit_note_id)
+        SELECT m.product_id, cn.date, -m.quantity * ci.quantity / it.quantity, 'return', cn.invoice_id, ci.invoice_item_id, cn.id
+        FROM credit_note_items ci
+        JOIN credit_notes cn ON cn.id = ci.credit_note_id
+        JOIN invoice_items it ON it.id = ci.invoice_item_id
+        JOIN stock_movements m ON m.invoice_item_id = ci.invoice_item_id AND m.kind = 'sale'
+        WHERE ci.credit_note_id = ?1 AND it.quantity > 0
+        "#,
+        [credit_note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Puts back in stock what a voided invoice still has out.
+pub(crate) fn reverse_invoice_stock(conn: &Connection, invoice_id: i32) -> Result<(), String> {
+    conn.execute(
+        r#"
+        INSERT INTO stock_movements (product_id, date, quantity, kind, invoice_id, invoice_item_id)
+        SELECT product_id, date('now', 'localtime'), -SUM(quantity), 'void', invoice_id, invoice_item_id
+        FROM stock_movements
+        WHERE invoice_id = ?1
+        GROUP BY product_id, invoice_item_id
+        HAVING ABS(SUM(quantity)) > 1e-9
+        "#,
+        [invoice_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+//
+// ==================== Commands ====================
+//
+
+/// Stock movements, newest first, optionally for one product.
+#[tauri::command]
+pub fn get_stock_movements(product_id: Option<i32>) -> Result<Vec<StockMovement>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT m.id, m.product_id, p.name, m.date, m.quantity, m.kind,
+                   m.invoice_id, i.invoice_number, m.credit_note_id, m.reason, m.created_at
+            FROM stock_movements m
+            LEFT JOIN products p ON p.id = m.product_id
+            LEFT JOIN invoices i ON i.id = m.invoice_id
+            WHERE ?1 IS NULL OR m.product_id = ?1
+            ORDER BY m.date DESC, m.id DESC
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([product_id], |row| {
+            Ok(StockMovement {
+                id: row.get(0)?,
+                product_id: row.get(1)?,
+                product_name: row.get(2)?,
+                date: row.get(3)?,
+                quantity: row.get(4)?,
+                kind: row.get(5)?,
+                invoice_id: row.get(6)?,
+                invoice_number: row.get(7)?,
+                credit_note_id: row.get(8)?,
+                reason: row.get(9)?,
+                created_at: row.get(10)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+/// Sets the stock a product starts from and starts tracking it. Entering it
+/// again replaces the previous opening stock.
+#[tauri::command]
+pub fn set_opening_stock(
+    product_id: i32,
+    quantity: f64,
+    date: Option<String>,
+) -> Result<i32, CommandError> {
+    let date = movement_date(date)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let unit_id: Option<i32> = tx
+        .query_row(
+            "SELECT unit_id FROM products WHERE id = ?1",
+            [product_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("المنتج غير موجود")?;
+    let mut errors = FieldErrors::default();
+    if quantity < 0.0 {
+        errors.add("quantity", "لا يمكن أن يكون المخزون الافتتاحي سالباً");
+    } else if let Err(e) = check_quantity(&tx, unit_id, quantity) {
+        errors.add("quantity", e);
+    }
+    errors.into_result()?;
+
+    tx.execute(
+        "UPDATE products SET track_stock = 1 WHERE id = ?1",
+        [product_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let existing: Option<i32> = tx
+        .query_row(
+            "SELECT id FROM stock_movements WHERE product_id = ?1 AND kind = 'opening'",
+            [product_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let id = match existing {
+        Some(id) => {
+            let before = snapshot(&tx, "stock_movements", id as i64)?;
+            tx.execute(
+                "UPDATE stock_movements SET quantity = ?1, date = ?2 WHERE id = ?3",
+                params![quantity, date, id],
+            )
+            .map_err(|e| e.to_string())?;
+            let after = snapshot(&tx, "stock_movements", id as i64)?;
+            record_audit(
+                &tx,
+                "stock_movement",
+                id as i64,
+                "update",
+                before.as_ref(),
+                after.as_ref(),
+            )?;
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO stock_movements (product_id, date, quantity, kind) VALUES (?1, ?2, ?3, 'opening')",
+                params![product_id, date, quantity],
+            )
+            .map_err(|e| e.to_string())?;
+            let id = tx.last_insert_rowid() as i32;
+            let after = snapshot(&tx, "stock_movements", id as i64)?;
+            record_audit(
+                &tx,
+                "stock_movement",
+                id as i64,
+                "create",
+                None,
+                after.as_ref(),
+            )?;
+            id
+        }
+    };
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Adds `quantity` to the stock of a product, or takes it out when
+/// negative, e.g. after a count or for damaged goods.
+#[tauri::command]
+pub fn adjust_stock(
+    product_id: i32,
+    quantity: f64,
+    reason: String,
+    date: Option<String>,
+) -> Result<i32, CommandError> {
+    let date = movement_date(date)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (_, unit_id) = tracked_product(&tx, product_id)?;
+    let mut errors = FieldErrors::default();
+    if reason.trim().is_empty() {
+        errors.add("reason", "سبب التعديل مطلوب");
+    }
+    if quantity == 0.0 {
+        errors.add("quantity", "يجب أن تكون الكمية مختلفة عن صفر");
+    } else if let Err(e) = check_quantity(&tx, unit_id, quantity) {
+        errors.add("quantity", e);
+    }
+    errors.into_result()?;
+
+    tx.execute(
+        "INSERT INTO stock_movements (product_id, date, quantity, kind, reason) VALUES (?1, ?2, ?3, 'adjustment', ?4)",
+        params![product_id, date, quantity, reason.trim()],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid() as i32;
+
+    let after = snapshot(&tx, "stock_movements", id as i64)?;
+    record_audit(
+        &tx,
+        "stock_movement",
+        id as i64,
+        "create",
+        None,
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Tracked products at or below their low-stock threshold.
+#[tauri::command]
+pub fn get_low_stock_products() -> Result<Vec<Product>, String> {
+    Ok(get_products(None)?
+        .into_iter()
+        .filter(|p| match (p.stock, p.low_stock_threshold) {
+            (Some(stock), Some(threshold)) => stock <= threshold,
+            _ => false,
+        })
+        .collect())
+}
+
+/// Stock of every tracked product on `as_of_date` (today by default),
+/// valued at its cost and at its selling price.
+#[tauri::command]
+pub fn get_stock_valuation(as_of_date: Option<String>) -> Result<StockValuation, String> {
+    let as_of_date = movement_date(as_of_date)?;
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT p.id, p.name, u.name, p.cost_price, p.unit_price,
+                   (SELECT IFNULL(SUM(m.quantity), 0) FROM stock_movements m WHERE m.product_id = p.id AND m.date <= ?1)
+            FROM products p
+            LEFT JOIN units u ON u.id = p.unit_id
+            WHERE p.track_stock = 1
+            ORDER BY p.name
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([&as_of_date], |row| {
+            let cost_price: Option<f64> = row.get(3)?;
+            let unit_price: Option<f64> = row.get(4)?;
+            let quantity: f64 = row.get(5)?;
+            Ok(StockValuationRow {
+                product_id: row.get(0)?,
+                product_name: row.get(1)?,
+                unit_name: row.get(2)?,
+                quantity,
+                cost_price,
+                unit_price,
+                cost_value: quantity * cost_price.unwrap_or(0.0),
+                sale_value: quantity * unit_price.unwrap_or(0.0),
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(StockValuation {
+        as_of_date,
+        total_cost_value: rows.iter().map(|r| r.cost_value).sum(),
+        total_sale_value: rows.iter().map(|r| r.sale_value).sum(),
+        rows,
+    })
+}
@@ -0,0 +1,314 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{Tag, TagDebtTotal};
+use rusqlite::{params, Connection, Result};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+//
+// ==================== Helpers ====================
+//
+
+/// SQL condition keeping the rows whose `customer_column` refers to a
+/// customer carrying every tag in `tag_ids`. Ids are integers, so they are
+/// inlined and callers don't have to renumber their parameters.
+pub(crate) fn tag_filter_sql(customer_column: &str, tag_ids: Option<&[i32]>) -> Option<String> {
+    let tag_ids = tag_ids.filter(|ids| !ids.is_empty())?;
+    let mut ids: Vec<i32> = tag_ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+
+    Some(format!(
+        "{} IN (SELECT customer_id FROM customer_tags WHERE tag_id IN ({}) GROUP BY customer_id HAVING COUNT(*) = {})",
+        customer_column,
+        ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "),
+        ids.len()
+    ))
+}
+
+fn tag_from_row(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        category: row.get(2)?,
+        color: row.get(3)?,
+        customer_count: row.get(4)?,
+    })
+}
+
+const TAG_SELECT: &str = r#"
+    SELECT
+      t.id,
+      t.name,
+      NULLIF(t.category, '') AS category,
+      t.color,
+      (SELECT COUNT(*)
+         FROM customer_tags ct
+         JOIN customers c ON c.id = ct.customer_id
+        WHERE ct.tag_id = t.id AND c.deleted_at IS NULL) AS customer_count
+    FROM tags t
+"#;
+
+fn customer_tag_ids(conn: &Connection, customer_id: i32) -> Result<Vec<i32>, String> {
+    let mut stmt = conn
+        .prepare("SELECT tag_id FROM customer_tags WHERE customer_id = ?1 ORDER BY tag_id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([customer_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+fn validate_tag(tag: &Tag) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if tag.name.trim().is_empty() {
+        errors.add("name", "اسم الوسم مطلوب");
+    }
+    errors.into_result()
+}
+
+fn map_unique_error(e: rusqlite::Error) -> CommandError {
+    if e.to_string().contains("UNIQUE") {
+        CommandError::Validation {
+            fields: BTreeMap::from([("name".to_string(), "هذا الوسم موجود بالفعل".to_string())]),
+        }
+    } else {
+        e.to_string().into()
+    }
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_tags(category: Option<String>) -> Result<Vec<Tag>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let rows = if let Some(category) = category {
+        let mut stmt = conn
+            .prepare(&format!(
+                "{} WHERE t.category = ?1 ORDER BY t.name",
+                TAG_SELECT
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([category], tag_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|x| x.ok()).collect()
+    } else {
+        let mut stmt = conn
+            .prepare(&format!("{} ORDER BY t.category, t.name", TAG_SELECT))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], tag_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|x| x.ok()).collect()
+    };
+
+    Ok(rows)
+}
+
+#[tauri::command]
+pub fn create_tag(tag: Tag) -> Result<i32, CommandError> {
+    validate_tag(&tag)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO tags (name, category, color) VALUES (?1, ?2, ?3)",
+        params![
+            tag.name.trim(),
+            tag.category.as_deref().unwrap_or("").trim(),
+            tag.color
+        ],
+    )
+    .map_err(map_unique_error)?;
+
+    let id = tx.last_insert_rowid() as i32;
+
+    let after = snapshot(&tx, "tags", id as i64)?;
+    record_audit(&tx, "tag", id as i64, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn update_tag(id: i32, tag: Tag) -> Result<(), CommandError> {
+    validate_tag(&tag)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "tags", id as i64)?;
+    tx.execute(
+        "UPDATE tags SET name = ?1, category = ?2, color = ?3 WHERE id = ?4",
+        params![
+            tag.name.trim(),
+            tag.category.as_deref().unwrap_or("").trim(),
+            tag.color,
+            id
+        ],
+    )
+    .map_err(map_unique_error)?;
+
+    let after = snapshot(&tx, "tags", id as i64)?;
+    record_audit(
+        &tx,
+        "tag",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Deletes a tag; it is removed from every customer that carries it.
+#[tauri::command]
+pub fn delete_tag(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "tags", id as i64)?;
+    tx.execute("DELETE FROM tags WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(&tx, "tag", id as i64, "delete", before.as_ref(), None)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_customer_tags(customer_id: i32) -> Result<Vec<Tag>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE t.id IN (SELECT tag_id FROM customer_tags WHERE customer_id = ?1) ORDER BY t.category, t.name",
+            TAG_SELECT
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([customer_id], tag_from_row)
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+/// Replaces the tags of a customer with `tag_ids`.
+#[tauri::command]
+pub fn set_customer_tags(customer_id: i32, tag_ids: Vec<i32>) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = customer_tag_ids(&tx, customer_id)?;
+
+    tx.execute(
+        "DELETE FROM customer_tags WHERE customer_id = ?1",
+        [customer_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for tag_id in &tag_ids {
+        tx.execute(
+            "INSERT OR IGNORE INTO customer_tags (customer_id, tag_id) VALUES (?1, ?2)",
+            params![customer_id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let after = customer_tag_ids(&tx, customer_id)?;
+    if before != after {
+        record_audit(
+            &tx,
+            "customer",
+            customer_id as i64,
+            "update_tags",
+            Some(&json!({ "tag_ids": before })),
+            Some(&json!({ "tag_ids": after })),
+        )?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Debt totals of the live customers carrying each tag. A customer with
+/// several tags is counted under each of them.
+#[tauri::command]
+pub fn get_debts_by_tag(category: Option<String>) -> Result<Vec<TagDebtTotal>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let sql = format!(
+        r#"
+        SELECT
+            t.id,
+            t.name,
+            NULLIF(t.category, '') AS category,
+            COUNT(c.id) AS customer_count,
+            IFNULL(SUM(inv.total_invoices), 0) AS total_invoices,
+            IFNULL(SUM(pay.total_payments), 0) AS total_payments,
+            IFNULL(SUM(inv.total_invoices), 0) - IFNULL(SUM(pay.total_payments), 0)
+              - IFNULL(SUM(cn.total_credits), 0) AS total_debt
+        FROM tags t
+        LEFT JOIN customer_tags ct ON ct.tag_id = t.id
+        LEFT JOIN customers c ON c.id = ct.customer_id AND c.deleted_at IS NULL
+        LEFT JOIN (
+            SELECT customer_id, SUM(total * exchange_rate) AS total_invoices
+            FROM invoices
+            WHERE deleted_at IS NULL AND state <> 'cancelled'
+            GROUP BY customer_id
+        ) AS inv ON inv.customer_id = c.id
+        LEFT JOIN (
+            SELECT customer_id, SUM(amount * exchange_rate) AS total_payments
+            FROM payments
+            WHERE deleted_at IS NULL
+            GROUP BY customer_id
+        ) AS pay ON pay.customer_id = c.id
+        LEFT JOIN (
+            SELECT customer_id, SUM(total * exchange_rate) AS total_credits
+            FROM live_credit_notes
+            GROUP BY customer_id
+        ) AS cn ON cn.customer_id = c.id
+        {}
+        GROUP BY t.id
+        ORDER BY total_debt DESC
+        "#,
+        if category.is_some() {
+            "WHERE t.category = ?1"
+        } else {
+            ""
+        }
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let map_row = |row: &rusqlite::Row| {
+        Ok(TagDebtTotal {
+            tag_id: row.get(0)?,
+            tag_name: row.get(1)?,
+            category: row.get(2)?,
+            customer_count: row.get(3)?,
+            total_invoices: row.get(4)?,
+            total_payments: row.get(5)?,
+            total_debt: row.get(6)?,
+        })
+    };
+
+    let totals = if let Some(category) = category {
+        let rows = stmt
+            .query_map([category], map_row)
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|x| x.ok()).collect()
+    } else {
+        let rows = stmt.query_map([], map_row).map_err(|e| e.to_string())?;
+        rows.filter_map(|x| x.ok()).collect()
+    };
+
+    Ok(totals)
+}
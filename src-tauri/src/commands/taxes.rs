@@ -0,0 +1,421 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::commands::discounts::discount_amount;
+use crate::commands::invoices::report_conditions;
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::{TaxRate, TaxSummary};
+use rusqlite::{params, Connection, OptionalExtension, Result, ToSql};
+use std::collections::BTreeMap;
+
+//
+// ==================== Helpers ====================
+//
+
+pub(crate) fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub(crate) struct LineAmounts {
+    pub discount: f64,
+    pub subtotal: f64,
+    pub tax: f64,
+    pub total: f64,
+}
+
+/// Amounts of an invoice line. The discount comes off the price first; with
+/// `price_includes_tax` the tax is then taken out of what is left instead of
+/// added on top of it.
+pub(crate) fn line_amounts(
+    unit_price: f64,
+    quantity: f64,
+    discount_type: Option<&str>,
+    discount_value: f64,
+    tax_rate: f64,
+    price_includes_tax: bool,
+) -> LineAmounts {
+    let gross = round2(unit_price * quantity);
+    let discount = discount_amount(gross, discount_type, discount_value);
+    let amount = round2(gross - discount);
+    if price_includes_tax {
+        let subtotal = round2(amount / (1.0 + tax_rate / 100.0));
+        LineAmounts {
+            discount,
+            subtotal,
+            tax: round2(amount - subtotal),
+            total: amount,
+        }
+    } else {
+        let tax = round2(amount * tax_rate / 100.0);
+        LineAmounts {
+            discount,
+            subtotal: amount,
+            tax,
+            total: round2(amount + tax),
+        }
+    }
+}
+
+/// Totals of a document from the sums of its lines. The document discount
+/// comes off the lines before tax and reduces the tax of every rate in
+/// proportion.
+pub(crate) fn document_totals(
+    lines_subtotal: f64,
+    lines_tax: f64,
+    discount_type: Option<&str>,
+    discount_value: f64,
+) -> LineAmounts {
+    let discount = discount_amount(lines_subtotal, discount_type, discount_value);
+    let factor = if lines_subtotal > 0.0 {
+        (lines_subtotal - discount) / lines_subtotal
+    } else {
+        1.0
+    };
+    let subtotal = round2(lines_subtotal - discount);
+    let tax = round2(lines_tax * factor);
+    LineAmounts {
+        discount,
+        subtotal,
+        tax,
+        total: round2(subtotal + tax),
+    }
+}
+
+/// Sets the discount, subtotal, tax and total of an invoice from its lines.
+/// Invoices without lines keep the amounts they were given.
+pub(crate) fn refresh_invoice_totals(conn: &Connection, invoice_id: i32) -> Result<(), String> {
+    let lines: Option<(f64, f64)> = conn
+        .query_row(
+            "SELECT SUM(subtotal), SUM(tax_amount) FROM invoice_items WHERE invoice_id = ?1 HAVING COUNT(*) > 0",
+            [invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some((lines_subtotal, lines_tax)) = lines else {
+        return Ok(());
+    };
+
+    let (discount_type, discount_value): (Option<String>, f64) = conn
+        .query_row(
+            "SELECT discount_type, discount_value FROM invoices WHERE id = ?1",
+            [invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let totals = document_totals(
+        lines_subtotal,
+        lines_tax,
+        discount_type.as_deref(),
+        discount_value,
+    );
+    conn.execute(
+        "UPDATE invoices SET discount_amount = ?1, subtotal = ?2, tax_total = ?3, total = ?4 WHERE id = ?5",
+        params![totals.discount, totals.subtotal, totals.tax, totals.total, invoice_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Line amounts per tax rate for the invoices matching `where_sql`, which
+/// may refer to the invoice as `i`. Invoice discounts are spread over the
+/// lines the same way as in the invoice totals. Amounts are in the base
+/// currency.
+pub(crate) fn tax_summary(
+    conn: &Connection,
+    where_sql: &str,
+    params: &[&dyn ToSql],
+) -> Result<Vec<TaxSummary>, String> {
+    let sql = format!(
+        r#"
+        SELECT
+          it.tax_rate,
+          ROUND(SUM(it.subtotal * d.factor * i.exchange_rate), 2),
+          ROUND(SUM(it.tax_amount * d.factor * i.exchange_rate), 2),
+          ROUND(SUM((it.subtotal + it.tax_amount) * d.factor * i.exchange_rate), 2),
+          COUNT(DISTINCT i.id)
+        FROM invoice_items it
+        JOIN invoices i ON i.id = it.invoice_id
+        JOIN (
+          SELECT
+            x.invoice_id,
+            CASE WHEN SUM(x.subtotal) > 0 THEN 1 - MAX(v.discount_amount) / SUM(x.subtotal) ELSE 1 END AS factor
+          FROM invoice_items x
+          JOIN invoices v ON v.id = x.invoice_id
+          GROUP BY x.invoice_id
+        ) d ON d.invoice_id = it.invoice_id
+        WHERE {}
+        GROUP BY it.tax_rate
+        ORDER BY it.tax_rate
+        "#,
+        where_sql
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params, |row| {
+            Ok(TaxSummary {
+                tax_rate: row.get(0)?,
+                subtotal: row.get(1)?,
+                tax_amount: row.get(2)?,
+                total: row.get(3)?,
+                invoice_count: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+pub(crate) fn invoice_tax_breakdown(
+    conn: &Connection,
+    invoice_id: i32,
+) -> Result<Vec<TaxSummary>, String> {
+    tax_summary(conn, "i.id = ?1", &[&invoice_id])
+}
+
+/// Rate of a new invoice line: the default rate when none is given,
+/// otherwise one of the configured rates.
+pub(crate) fn line_tax_rate(conn: &Connection, tax_rate: Option<f64>) -> Result<f64, String> {
+    let Some(rate) = tax_rate else {
+        let default: Option<f64> = conn
+            .query_row(
+                "SELECT rate FROM tax_rates WHERE is_default = 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        return Ok(default.unwrap_or(0.0));
+    };
+    if !(0.0..=100.0).contains(&rate) {
+        return Err("يجب أن تكون نسبة الضريبة بين 0 و 100".into());
+    }
+    let known: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM tax_rates WHERE rate = ?1)",
+            [rate],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !known {
+        return Err(format!("نسبة الضريبة {}% غير معرّفة", rate));
+    }
+    Ok(rate)
+}
+
+fn validate_tax_rate(tax_rate: &TaxRate) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if tax_rate.name.trim().is_empty() {
+        errors.add("name", "اسم النسبة مطلوب");
+    }
+    if !(0.0..=100.0).contains(&tax_rate.rate) {
+        errors.add("rate", "يجب أن تكون النسبة بين 0 و 100");
+    }
+    errors.into_result()
+}
+
+fn map_unique_error(e: rusqlite::Error) -> CommandError {
+    if e.to_string().contains("UNIQUE") {
+        CommandError::Validation {
+            fields: BTreeMap::from([("rate".to_string(), "هذه النسبة موجودة بالفعل".to_string())]),
+        }
+    } else {
+        e.to_string().into()
+    }
+}
+
+// Only one rate can be the default
+fn clear_default(conn: &Connection, except_id: i32) -> Result<(), String> {
+    conn.execute(
+        "UPDATE tax_rates SET is_default = 0 WHERE id <> ?1 AND is_default = 1",
+        [except_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_tax_rates() -> Result<Vec<TaxRate>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, rate, is_default FROM tax_rates ORDER BY rate")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(TaxRate {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                rate: row.get(2)?,
+                is_default: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|x| x.ok()).collect())
+}
+
+#[tauri::command]
+pub fn create_tax_rate(tax_rate: TaxRate) -> Result<i32, CommandError> {
+    validate_tax_rate(&tax_rate)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO tax_rates (name, rate, is_default) VALUES (?1, ?2, ?3)",
+        params![tax_rate.name.trim(), tax_rate.rate, tax_rate.is_default],
+    )
+    .map_err(map_unique_error)?;
+
+    let id = tx.last_insert_rowid() as i32;
+    if tax_rate.is_default {
+        clear_default(&tx, id)?;
+    }
+
+    let after = snapshot(&tx, "tax_rates", id as i64)?;
+    record_audit(&tx, "tax_rate", id as i64, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Updates a rate. Invoice lines keep the rate they were issued with.
+#[tauri::command]
+pub fn update_tax_rate(id: i32, tax_rate: TaxRate) -> Result<(), CommandError> {
+    validate_tax_rate(&tax_rate)?;
+
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "tax_rates", id as i64)?;
+    if before.is_none() {
+        return Err("النسبة غير موجودة".into());
+    }
+    tx.execute(
+        "UPDATE tax_rates SET name = ?1, rate = ?2, is_default = ?3 WHERE id = ?4",
+        params![tax_rate.name.trim(), tax_rate.rate, tax_rate.is_default, id],
+    )
+    .map_err(map_unique_error)?;
+    if tax_rate.is_default {
+        clear_default(&tx, id)?;
+    }
+
+    let after = snapshot(&tx, "tax_rates", id as i64)?;
+    record_audit(
+        &tx,
+        "tax_rate",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_tax_rate(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let before = snapshot(&tx, "tax_rates", id as i64)?;
+    tx.execute("DELETE FROM tax_rates WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(&tx, "tax_rate", id as i64, "delete", before.as_ref(), None)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Taxable amount and tax per rate for the invoices of the period, with the
+/// same filters as the invoices report.
+#[tauri::command]
+pub fn get_tax_summary(
+    from_date: String,
+    to_date: Option<String>,
+    customer_id: Option<i32>,
+    tag_ids: Option<Vec<i32>>,
+) -> Result<Vec<TaxSummary>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let (where_sql, params) = report_conditions(from_date, to_date, customer_id, tag_ids);
+    let params_refs: Vec<&dyn ToSql> = params.iter().map(|b| &**b as &dyn ToSql).collect();
+
+    tax_summary(
+        &conn,
+        &format!("{} AND i.state <> 'cancelled'", where_sql),
+        params_refs.as_slice(),
+    )
+}
+
+#[tauri::command]
+pub fn get_invoice_tax_breakdown(invoice_id: i32) -> Result<Vec<TaxSummary>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    invoice_tax_breakdown(&conn, invoice_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_discount_then_tax() {
+        let line = line_amounts(50.0, 3.0, Some("percent"), 10.0, 19.0, false);
+        assert_eq!(line.discount, 15.0);
+        assert_eq!(line.subtotal, 135.0);
+        assert_eq!(line.tax, 25.65);
+        assert_eq!(line.total, 160.65);
+    }
+
+    #[test]
+    fn price_including_tax() {
+        let line = line_amounts(119.0, 1.0, None, 0.0, 19.0, true);
+        assert_eq!(line.subtotal, 100.0);
+        assert_eq!(line.tax, 19.0);
+        assert_eq!(line.total, 119.0);
+    }
+
+    #[test]
+    fn fixed_line_discount_above_amount() {
+        let line = line_amounts(20.0, 2.0, Some("fixed"), 100.0, 19.0, false);
+        assert_eq!(line.discount, 40.0);
+        assert_eq!(line.total, 0.0);
+    }
+
+    #[test]
+    fn document_discount_spreads_over_tax() {
+        // 100 at 19% + 100 at 9%: half the subtotal off halves each tax
+        let totals = document_totals(200.0, 28.0, Some("percent"), 50.0);
+        assert_eq!(totals.discount, 100.0);
+        assert_eq!(totals.subtotal, 100.0);
+        assert_eq!(totals.tax, 14.0);
+        assert_eq!(totals.total, 114.0);
+
+        let fixed = document_totals(200.0, 28.0, Some("fixed"), 50.0);
+        assert_eq!(fixed.subtotal, 150.0);
+        assert_eq!(fixed.tax, 21.0);
+    }
+
+    #[test]
+    fn fixed_document_discount_above_subtotal() {
+        let totals = document_totals(80.0, 15.2, Some("fixed"), 100.0);
+        assert_eq!(totals.discount, 80.0);
+        assert_eq!(totals.subtotal, 0.0);
+        assert_eq!(totals.tax, 0.0);
+        assert_eq!(totals.total, 0.0);
+    }
+
+    #[test]
+    fn empty_document() {
+        let totals = document_totals(0.0, 0.0, Some("fixed"), 10.0);
+        assert_eq!(totals.discount, 0.0);
+        assert_eq!(totals.total, 0.0);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::commands::audit::{record_audit, snapshot};
+use crate::db::get_db;
+use crate::error::{CommandError, FieldErrors};
+use crate::models::Unit;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::BTreeMap;
+
+//
+// ==================== Helpers ====================
+//
+
+const UNIT_SELECT: &str = r#"
+    SELECT u.id, u.name, u.decimals, u.base_unit_id, b.name, u.factor, u.created_at
+    FROM units u
+    LEFT JOIN units b ON b.id = u.base_unit_id
+"#;
+
+fn unit_from_row(row: &rusqlite::Row) -> rusqlite::Result<Unit> {
+    Ok(Unit {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        decimals: row.get(2)?,
+        base_unit_id: row.get(3)?,
+        base_unit_name: row.get(4)?,
+        factor: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn unit(conn: &Connection, id: i32) -> Result<Unit, String> {
+    conn.query_row(
+        &format!("{} WHERE u.id = ?1", UNIT_SELECT),
+        [id],
+        unit_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "الوحدة غير موجودة".to_string())
+}
+
+/// `(root unit, factor)` of a unit: the unit it is counted in and how many
+/// of it one unit holds.
+fn root_unit(unit: &Unit) -> (i32, f64) {
+    match unit.base_unit_id {
+        Some(base) => (base, unit.factor),
+        None => (unit.id.unwrap_or_default(), 1.0),
+    }
+}
+
+/// Converts `quantity` between two units sharing the same base unit.
+pub(crate) fn convert(
+    conn: &Connection,
+    quantity: f64,
+    from_unit_id: i32,
+    to_unit_id: i32,
+) -> Result<f64, String> {
+    if from_unit_id == to_unit_id {
+        return Ok(quantity);
+    }
+    let (from, to) = (unit(conn, from_unit_id)?, unit(conn, to_unit_id)?);
+    let ((from_root, from_factor), (to_root, to_factor)) = (root_unit(&from), root_unit(&to));
+    if from_root != to_root {
+        return Err(format!("لا يمكن التحويل من {} إلى {}", from.name, to.name));
+    }
+    Ok(quantity * from_factor / to_factor)
+}
+
+/// Checks that `quantity` has no more decimals than its unit allows.
+pub(crate) fn check_quantity(
+    conn: &Connection,
+    unit_id: Option<i32>,
+    quantity: f64,
+) -> Result<(), String> {
+    let Some(unit_id) = unit_id else {
+        return Ok(());
+    };
+    let unit = unit(conn, unit_id)?;
+    let scale = 10f64.powi(unit.decimals);
+    if ((quantity * scale).round() - quantity * scale).abs() > 1e-6 {
+        return Err(match unit.decimals {
+            0 => format!("الكمية بوحدة {} يجب أن تكون عدداً صحيحاً", unit.name),
+            n => format!(
+                "الكمية بوحدة {} تقبل {} منازل عشرية على الأكثر",
+                unit.name, n
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// `quantity` followed by its unit, e.g. "2.5 كغ".
+pub(crate) fn quantity_label(quantity: f64, unit_name: Option<&str>) -> String {
+    // Drops the noise left by summing quantities
+    let quantity = (quantity * 1e6).round() / 1e6;
+    match unit_name {
+        Some(unit) => format!("{} {}", quantity, unit),
+        None => quantity.to_string(),
+    }
+}
+
+fn validate_unit(conn: &Connection, id: Option<i32>, unit: &Unit) -> Result<(), CommandError> {
+    let mut errors = FieldErrors::default();
+    if unit.name.trim().is_empty() {
+        errors.add("name", "اسم الوحدة مطلوب");
+    }
+    if !(0..=6).contains(&unit.decimals) {
+        errors.add("decimals", "عدد المنازل العشرية يجب أن يكون بين 0 و 6");
+    }
+    if let Some(base_unit_id) = unit.base_unit_id {
+        // Conversions only go one level deep
+        let base: Option<Option<i32>> = conn
+            .query_row(
+                "SELECT base_unit_id FROM units WHERE id = ?1",
+                [base_unit_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let derived: bool = match id {
+            Some(id) => conn
+                .query_row(
+                    "SELECT EXISTS (SELECT 1 FROM units WHERE base_unit_id = ?1)",
+                    [id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?,
+            None => false,
+        };
+        match base {
+            None => errors.add("base_unit_id", "الوحدة الأساسية غير موجودة"),
+            Some(_) if Some(base_unit_id) == id => {
+                errors.add("base_unit_id", "لا يمكن أن تكون الوحدة أساساً لنفسها")
+            }
+            Some(Some(_)) => errors.add(
+                "base_unit_id",
+                "يجب أن تكون الوحدة الأساسية غير مشتقة من وحدة أخرى",
+            ),
+            Some(None) if derived => errors.add("base_unit_id", "هذه الوحدة أساس لوحدات أخرى"),
+            Some(None) => {}
+        }
+        if unit.factor <= 0.0 {
+            errors.add("factor", "يجب أن يكون معامل التحويل أكبر من صفر");
+        }
+    }
+    errors.into_result()
+}
+
+fn map_unique_error(e: rusqlite::Error) -> CommandError {
+    if e.to_string().contains("UNIQUE") {
+        CommandError::Validation {
+            fields: BTreeMap::from([("name".to_string(), "هذه الوحدة موجودة بالفعل".to_string())]),
+        }
+    } else {
+        e.to_string().into()
+    }
+}
+
+//
+// ==================== Commands ====================
+//
+
+#[tauri::command]
+pub fn get_units() -> Result<Vec<Unit>, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY u.name", UNIT_SELECT))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], unit_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_unit(unit: Unit) -> Result<i32, CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    validate_unit(&conn, None, &unit)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO units (name, decimals, base_unit_id, factor) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            unit.name.trim(),
+            unit.decimals,
+            unit.base_unit_id,
+            if unit.base_unit_id.is_some() {
+                unit.factor
+            } else {
+                1.0
+            }
+        ],
+    )
+    .map_err(map_unique_error)?;
+    let id = tx.last_insert_rowid() as i32;
+
+    let after = snapshot(&tx, "units", id as i64)?;
+    record_audit(&tx, "unit", id as i64, "create", None, after.as_ref())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// Updates a unit. Lines keep their quantities as entered, so its conversion
+/// and decimals are locked once documents or stock use it.
+#[tauri::command]
+pub fn update_unit(id: i32, unit: Unit) -> Result<(), CommandError> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    validate_unit(&conn, Some(id), &unit)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let factor = if unit.base_unit_id.is_some() {
+        unit.factor
+    } else {
+        1.0
+    };
+    let (base_unit_id, current_factor, current_decimals, in_use): (Option<i32>, f64, i32, bool) =
+        tx.query_row(
+            r#"
+            SELECT base_unit_id, factor, decimals,
+                   EXISTS (SELECT 1 FROM invoice_items WHERE unit_id = ?1)
+                OR EXISTS (SELECT 1 FROM quote_items WHERE unit_id = ?1)
+                OR EXISTS (SELECT 1 FROM recurring_invoice_items WHERE unit_id = ?1)
+                -- credit and delivery note lines are in the unit of their invoice line
+                OR EXISTS (
+                     SELECT 1 FROM credit_note_items ci
+                     JOIN invoice_items ii ON ii.id = ci.invoice_item_id
+                     WHERE ii.unit_id = ?1
+                   )
+                OR EXISTS (
+                     SELECT 1 FROM delivery_note_items di
+                     JOIN invoice_items ii ON ii.id = di.invoice_item_id
+                     WHERE ii.unit_id = ?1
+                   )
+                OR EXISTS (
+                     SELECT 1 FROM products p
+                     JOIN stock_movements m ON m.product_id = p.id
+                     WHERE p.unit_id = ?1
+                   )
+            FROM units WHERE id = ?1
+            "#,
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or("الوحدة غير موجودة")?;
+    if in_use {
+        let mut errors = FieldErrors::default();
+        if base_unit_id != unit.base_unit_id {
+            errors.add(
+                "base_unit_id",
+                "لا يمكن تغيير أساس وحدة مستخدمة في مستندات أو مخزون",
+            );
+        } else if (current_factor - factor).abs() > 1e-9 {
+            errors.add(
+                "factor",
+                "لا يمكن تغيير معامل وحدة مستخدمة في مستندات أو مخزون",
+            );
+        }
+        // Quantities already entered were checked against the old decimals
+        if current_decimals != unit.decimals {
+            errors.add(
+                "decimals",
+                "لا يمكن تغيير عدد الخانات العشرية لوحدة مستخدمة في مستندات أو مخزون",
+            );
+        }
+        errors.into_result()?;
+    }
+
+    let before = snapshot(&tx, "units", id as i64)?;
+    tx.execute(
+        "UPDATE units SET name = ?1, decimals = ?2, base_unit_id = ?3, factor = ?4 WHERE id = ?5",
+        params![
+            unit.name.trim(),
+            unit.decimals,
+            unit.base_unit_id,
+            factor,
+            id
+        ],
+    )
+    .map_err(map_unique_error)?;
+
+    let after = snapshot(&tx, "units", id as i64)?;
+    record_audit(
+        &tx,
+        "unit",
+        id as i64,
+        "update",
+        before.as_ref(),
+        after.as_ref(),
+    )?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Deletes a unit no line or other unit uses. Products in it are left
+/// without a unit.
+#[tauri::command]
+pub fn delete_unit(id: i32) -> Result<(), String> {
+    let mut conn = get_db().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let in_use: bool = tx
+        .query_row(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM invoice_items WHERE unit_id = ?1)
+                OR EXISTS (SELECT 1 FROM quote_items WHERE unit_id = ?1)
+                OR EXISTS (SELECT 1 FROM recurring_invoice_items WHERE unit_id = ?1)
+                OR EXISTS (SELECT 1 FROM units WHERE base_unit_id = ?1)
+            "#,
+            [id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if in_use {
+        return Err("لا يمكن حذف وحدة مستخدمة في مستندات أو وحدات أخرى".into());
+    }
+
+    let before = snapshot(&tx, "units", id as i64)?;
+    tx.execute("DELETE FROM units WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    record_audit(&tx, "unit", id as i64, "delete", before.as_ref(), None)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// `quantity` of `from_unit_id` expressed in `to_unit_id`, e.g. 2 boxes in
+/// pieces.
+#[tauri::command]
+pub fn convert_quantity(quantity: f64, from_unit_id: i32, to_unit_id: i32) -> Result<f64, String> {
+    let conn = get_db().map_err(|e| e.to_string())?;
+    convert(&conn, quantity, from_unit_id, to_unit_id)
+}
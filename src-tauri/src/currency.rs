@@ -0,0 +1,53 @@
+/// (ISO code, symbol, decimal places)
+const CURRENCIES: &[(&str, &str, usize)] = &[
+    ("DZD", "د.ج", 2),
+    ("MAD", "د.م", 2),
+    ("TND", "د.ت", 3),
+    ("LYD", "د.ل", 3),
+    ("EGP", "ج.م", 2),
+    ("SAR", "ر.س", 2),
+    ("AED", "د.إ", 2),
+    ("QAR", "ر.ق", 2),
+    ("KWD", "د.ك", 3),
+    ("JOD", "د.أ", 3),
+    ("EUR", "€", 2),
+    ("USD", "$", 2),
+    ("GBP", "£", 2),
+    ("TRY", "₺", 2),
+    ("CAD", "C$", 2),
+];
+
+/// Currency of the books when none is set, matching the default country.
+pub const DEFAULT_BASE_CURRENCY: &str = "DZD";
+
+pub fn is_known_currency(code: &str) -> bool {
+    CURRENCIES.iter().any(|(c, _, _)| *c == code)
+}
+
+/// `(code, symbol, decimals)` of every supported currency.
+pub fn currencies() -> impl Iterator<Item = (&'static str, &'static str, usize)> {
+    CURRENCIES.iter().copied()
+}
+
+pub fn currency_decimals(code: &str) -> usize {
+    CURRENCIES
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map(|(_, _, d)| *d)
+        .unwrap_or(2)
+}
+
+/// `amount` with the decimals of `currency`, e.g. "1234.500" for TND.
+pub fn format_number(amount: f64, currency: &str) -> String {
+    format!("{:.*}", currency_decimals(currency), amount)
+}
+
+/// `amount` followed by its currency symbol, e.g. "1234.50 €".
+pub fn format_amount(amount: f64, currency: &str) -> String {
+    let symbol = CURRENCIES
+        .iter()
+        .find(|(c, _, _)| *c == currency)
+        .map(|(_, s, _)| *s)
+        .unwrap_or(currency);
+    format!("{} {}", format_number(amount, currency), symbol)
+}
@@ -1,5 +1,8 @@
-use rusqlite::{Connection, Result};
+use crate::normalize::register_functions;
+use rusqlite::{params, Connection, Result, Transaction, TransactionBehavior};
+use sha2::{Digest, Sha256};
 use std::path::PathBuf;
+use std::time::Duration;
 
 fn get_db_path() -> PathBuf {
     // Use a data directory outside src-tauri to avoid triggering rebuilds
@@ -19,12 +22,189 @@ fn get_db_path() -> PathBuf {
     data_dir.join("app.db")
 }
 
+// Schema changes that cannot be expressed with `IF NOT EXISTS` (e.g. new
+// columns). Entry N is applied once, after which `user_version` is set to N + 1.
+const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "001_soft_delete",
+        include_str!("migrations/001_soft_delete.sql"),
+    ),
+    (
+        "002_search_index",
+        include_str!("migrations/002_search_index.sql"),
+    ),
+    (
+        "003_normalized_names",
+        include_str!("migrations/003_normalized_names.sql"),
+    ),
+    (
+        "004_customer_phone_e164",
+        include_str!("migrations/004_customer_phone_e164.sql"),
+    ),
+    (
+        "005_customer_contacts_addresses",
+        include_str!("migrations/005_customer_contacts_addresses.sql"),
+    ),
+    (
+        "006_credit_limits",
+        include_str!("migrations/006_credit_limits.sql"),
+    ),
+    (
+        "007_customer_tags",
+        include_str!("migrations/007_customer_tags.sql"),
+    ),
+    (
+        "008_custom_fields",
+        include_str!("migrations/008_custom_fields.sql"),
+    ),
+    (
+        "009_payment_terms",
+        include_str!("migrations/009_payment_terms.sql"),
+    ),
+    ("010_tax", include_str!("migrations/010_tax.sql")),
+    (
+        "011_discounts",
+        include_str!("migrations/011_discounts.sql"),
+    ),
+    (
+        "012_credit_notes",
+        include_str!("migrations/012_credit_notes.sql"),
+    ),
+    (
+        "013_invoice_state",
+        include_str!("migrations/013_invoice_state.sql"),
+    ),
+    (
+        "014_void_invoices",
+        include_str!("migrations/014_void_invoices.sql"),
+    ),
+    ("015_quotes", include_str!("migrations/015_quotes.sql")),
+    (
+        "016_delivery_notes",
+        include_str!("migrations/016_delivery_notes.sql"),
+    ),
+    (
+        "017_recurring_invoices",
+        include_str!("migrations/017_recurring_invoices.sql"),
+    ),
+    ("018_products", include_str!("migrations/018_products.sql")),
+    (
+        "019_currencies",
+        include_str!("migrations/019_currencies.sql"),
+    ),
+    ("020_units", include_str!("migrations/020_units.sql")),
+    ("021_stock", include_str!("migrations/021_stock.sql")),
+    (
+        "022_live_credit_notes_voided",
+        include_str!("migrations/022_live_credit_notes_voided.sql"),
+    ),
+    (
+        "023_recurring_run_once",
+        include_str!("migrations/023_recurring_run_once.sql"),
+    ),
+];
+
+/// Hashes the invoices migration 013 marked as issued. This is a frozen copy
+/// of `lifecycle::invoice_content_hash` as of 013 so the migration keeps
+/// producing the same hashes (and only reads columns that exist by then)
+/// whatever later happens to the live version.
+fn hash_invoices_013(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, invoice_number, date, due_date, discount_type, discount_value,
+               discount_amount, IFNULL(subtotal, total), IFNULL(tax_total, 0), total
+        FROM invoices WHERE state <> 'draft' AND content_hash IS NULL
+        "#,
+    )?;
+    let invoices = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                serde_json::json!({
+                    "invoice_number": row.get::<_, String>(1)?,
+                    "date": row.get::<_, String>(2)?,
+                    "due_date": row.get::<_, Option<String>>(3)?,
+                    "discount_type": row.get::<_, Option<String>>(4)?,
+                    "discount_value": row.get::<_, f64>(5)?,
+                    "discount_amount": row.get::<_, f64>(6)?,
+                    "subtotal": row.get::<_, f64>(7)?,
+                    "tax_total": row.get::<_, f64>(8)?,
+                    "total": row.get::<_, f64>(9)?,
+                }),
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut items_stmt = conn.prepare(
+        r#"
+        SELECT product_name, unit_price, quantity, discount_type, discount_value,
+               tax_rate, price_includes_tax, subtotal, tax_amount, total
+        FROM invoice_items WHERE invoice_id = ?1 ORDER BY id
+        "#,
+    )?;
+    for (id, invoice) in invoices {
+        let items = items_stmt
+            .query_map([id], |row| {
+                Ok(serde_json::json!([
+                    row.get::<_, String>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, f64>(4)?,
+                    row.get::<_, f64>(5)?,
+                    row.get::<_, bool>(6)?,
+                    row.get::<_, Option<f64>>(7)?,
+                    row.get::<_, f64>(8)?,
+                    row.get::<_, f64>(9)?,
+                ]))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let content = serde_json::json!({ "invoice": invoice, "items": items }).to_string();
+        conn.execute(
+            "UPDATE invoices SET content_hash = ?1 WHERE id = ?2",
+            params![format!("{:x}", Sha256::digest(content.as_bytes())), id],
+        )?;
+    }
+    Ok(())
+}
+
+fn run_migrations(conn: &Connection) -> Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (i, (name, sql)) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        // Take the write lock before checking again: another connection may
+        // have applied this migration while we were waiting for it
+        let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+        let current: i64 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if current > i as i64 {
+            continue;
+        }
+        tx.execute_batch(sql)?;
+        // 013 marks existing invoices as issued; their hash needs Rust
+        if *name == "013_invoice_state" {
+            hash_invoices_013(&tx)?;
+        }
+        tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
 pub fn get_db() -> Result<Connection> {
     let db_path = get_db_path();
     let conn = Connection::open(db_path)?;
+    // Connections opened from other threads wait for each other's writes
+    // instead of failing with SQLITE_BUSY
+    conn.busy_timeout(Duration::from_secs(5))?;
+
+    // Custom SQL functions must exist before triggers that call them run
+    register_functions(&conn)?;
 
     // Run migrations
     conn.execute_batch(include_str!("migrations/init.sql"))?;
+    run_migrations(&conn)?;
 
     Ok(conn)
 }
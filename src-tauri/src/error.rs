@@ -0,0 +1,76 @@
+use crate::models::CreditStatus;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Error returned by commands that validate user input. Serialized as
+/// `{ "kind": "message", "message": ... }` or
+/// `{ "kind": "validation", "fields": { "phone": ... } }` so forms can show
+/// each message next to its field.
+///
+/// `{ "kind": "credit_limit", ... }` is returned when an invoice would push a
+/// customer past their credit limit; the form can resend it as an override.
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    Message {
+        message: String,
+    },
+    Validation {
+        fields: BTreeMap<String, String>,
+    },
+    CreditLimit {
+        message: String,
+        mode: String, // "block" | "warn"
+        new_balance: f64,
+        status: Box<CreditStatus>,
+    },
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Message { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Message {
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Flattens the error into one message, for commands that only report text.
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> Self {
+        match error {
+            CommandError::Message { message } | CommandError::CreditLimit { message, .. } => {
+                message
+            }
+            CommandError::Validation { fields } => {
+                fields.into_values().collect::<Vec<_>>().join("، ")
+            }
+        }
+    }
+}
+
+/// Collects per-field messages while validating a form.
+#[derive(Default)]
+pub struct FieldErrors(BTreeMap<String, String>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0
+            .entry(field.to_string())
+            .or_insert_with(|| message.into());
+    }
+
+    /// `Ok(())` when no field failed, otherwise a `CommandError::Validation`.
+    pub fn into_result(self) -> Result<(), CommandError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(CommandError::Validation { fields: self.0 })
+        }
+    }
+}
@@ -1,6 +1,11 @@
 pub mod commands;
+pub mod currency;
 pub mod db;
+pub mod error;
 pub mod models;
+pub mod normalize;
+pub mod phone;
+pub mod terms;
 pub use commands::*;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -13,6 +18,17 @@ fn greet(name: &str) -> String {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .setup(|_app| {
+            // Migrations run once here, before the window and the catch-up
+            // thread open connections of their own
+            db::get_db()?;
+            // Catch up on recurring invoices that fell due while the app was
+            // closed, without holding up the window. Failed runs are kept on
+            // their schedule; the UI asks for anything else with
+            // get_recurring_startup_error.
+            std::thread::spawn(run_startup_generation);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_all_customers,
@@ -20,6 +36,46 @@ pub fn run() {
             create_customer,
             update_customer,
             delete_customer,
+            find_duplicate_customers,
+            merge_customers,
+            normalize_customer_phones,
+            // contacts & addresses
+            get_customer_contacts,
+            create_customer_contact,
+            update_customer_contact,
+            delete_customer_contact,
+            get_customer_addresses,
+            create_customer_address,
+            update_customer_address,
+            delete_customer_address,
+            // credit limits
+            set_customer_credit_limit,
+            get_customer_credit_status,
+            get_customers_over_limit,
+            // payment terms
+            set_customer_payment_terms,
+            // custom fields
+            get_custom_fields,
+            create_custom_field,
+            update_custom_field,
+            delete_custom_field,
+            // tags
+            get_tags,
+            create_tag,
+            update_tag,
+            delete_tag,
+            get_customer_tags,
+            set_customer_tags,
+            get_debts_by_tag,
+            // taxes
+            get_tax_rates,
+            create_tax_rate,
+            update_tax_rate,
+            delete_tax_rate,
+            get_tax_summary,
+            get_invoice_tax_breakdown,
+            // discounts
+            get_discounts_report,
             // invoices
             get_all_invoices,
             get_invoice_by_id,
@@ -32,6 +88,64 @@ pub fn run() {
             update_invoice,
             delete_invoice,
             get_invoices,
+            generate_invoice_pdf,
+            finalize_invoice,
+            verify_invoice,
+            void_invoice,
+            duplicate_invoice,
+            // credit notes
+            create_credit_note,
+            get_credit_notes,
+            get_credit_note,
+            generate_credit_note_pdf,
+            // products
+            get_products,
+            create_product,
+            update_product,
+            delete_product,
+            // units
+            get_units,
+            create_unit,
+            update_unit,
+            delete_unit,
+            convert_quantity,
+            // stock
+            get_stock_movements,
+            set_opening_stock,
+            adjust_stock,
+            get_low_stock_products,
+            get_stock_valuation,
+            // delivery notes
+            create_delivery_note,
+            get_delivery_notes,
+            get_delivery_note,
+            get_invoice_delivery_status,
+            delete_delivery_note,
+            generate_delivery_note_pdf,
+            // recurring invoices
+            create_recurring_invoice,
+            update_recurring_invoice,
+            set_recurring_invoice_active,
+            delete_recurring_invoice,
+            get_recurring_invoices,
+            get_recurring_invoice,
+            get_recurring_invoice_runs,
+            generate_recurring_invoices,
+            get_recurring_startup_error,
+            // quotes
+            create_quote,
+            update_quote,
+            set_quote_status,
+            get_quotes,
+            get_quote,
+            delete_quote,
+            convert_quote_to_invoice,
+            generate_quote_pdf,
+            // currencies
+            get_currencies,
+            get_exchange_rates,
+            set_exchange_rate,
+            delete_exchange_rate,
             // payments
             get_all_payments,
             create_payment,
@@ -40,10 +154,29 @@ pub fn run() {
             // debts
             get_all_debts,
             get_customer_debt,
+            get_currency_balances,
             get_report_summary,
             get_transactions,
             generate_invoices_pdf,
-            generate_transactions_pdf
+            generate_transactions_pdf,
+            // aging
+            get_aging_report,
+            generate_aging_pdf,
+            export_aging_csv,
+            get_overdue_invoices,
+            // audit
+            get_audit_log,
+            get_invoice_history,
+            get_payment_history,
+            // recycle bin
+            get_deleted_items,
+            restore_deleted_item,
+            purge_deleted_item,
+            // search
+            global_search,
+            // settings
+            get_settings,
+            set_setting
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
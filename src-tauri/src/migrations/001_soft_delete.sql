@@ -0,0 +1,7 @@
+ALTER TABLE customers ADD COLUMN deleted_at TEXT;
+ALTER TABLE invoices ADD COLUMN deleted_at TEXT;
+ALTER TABLE payments ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_customers_deleted ON customers(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_invoices_deleted ON invoices(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_payments_deleted ON payments(deleted_at);
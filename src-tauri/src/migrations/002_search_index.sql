@@ -0,0 +1,98 @@
+-- Global search index. `title` and `body` hold normalize_text() output;
+-- `label` keeps the original text for display.
+CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+  entity UNINDEXED,
+  record_id UNINDEXED,
+  parent_id UNINDEXED,
+  label UNINDEXED,
+  title,
+  body,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Customers
+CREATE TRIGGER IF NOT EXISTS customers_search_ai AFTER INSERT ON customers BEGIN
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('customer', new.id, NULL, new.name, normalize_text(new.name),
+          normalize_text(new.phone || ' ' || new.address || ' ' || IFNULL(new.notes, '')));
+END;
+
+CREATE TRIGGER IF NOT EXISTS customers_search_au AFTER UPDATE OF name, phone, address, notes ON customers BEGIN
+  DELETE FROM search_index WHERE entity = 'customer' AND record_id = old.id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('customer', new.id, NULL, new.name, normalize_text(new.name),
+          normalize_text(new.phone || ' ' || new.address || ' ' || IFNULL(new.notes, '')));
+END;
+
+CREATE TRIGGER IF NOT EXISTS customers_search_ad AFTER DELETE ON customers BEGIN
+  DELETE FROM search_index WHERE entity = 'customer' AND record_id = old.id;
+END;
+
+-- Invoices
+CREATE TRIGGER IF NOT EXISTS invoices_search_ai AFTER INSERT ON invoices BEGIN
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('invoice', new.id, NULL, new.invoice_number, normalize_text(new.invoice_number), new.date);
+END;
+
+CREATE TRIGGER IF NOT EXISTS invoices_search_au AFTER UPDATE OF invoice_number, date ON invoices BEGIN
+  DELETE FROM search_index WHERE entity = 'invoice' AND record_id = old.id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('invoice', new.id, NULL, new.invoice_number, normalize_text(new.invoice_number), new.date);
+END;
+
+CREATE TRIGGER IF NOT EXISTS invoices_search_ad AFTER DELETE ON invoices BEGIN
+  DELETE FROM search_index WHERE entity = 'invoice' AND record_id = old.id;
+END;
+
+-- Invoice lines
+CREATE TRIGGER IF NOT EXISTS invoice_items_search_ai AFTER INSERT ON invoice_items BEGIN
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('item', new.id, new.invoice_id, new.product_name, normalize_text(new.product_name), '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS invoice_items_search_au AFTER UPDATE OF product_name, invoice_id ON invoice_items BEGIN
+  DELETE FROM search_index WHERE entity = 'item' AND record_id = old.id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('item', new.id, new.invoice_id, new.product_name, normalize_text(new.product_name), '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS invoice_items_search_ad AFTER DELETE ON invoice_items BEGIN
+  DELETE FROM search_index WHERE entity = 'item' AND record_id = old.id;
+END;
+
+-- Payments
+CREATE TRIGGER IF NOT EXISTS payments_search_ai AFTER INSERT ON payments BEGIN
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('payment', new.id, new.invoice_id, new.payment_number, normalize_text(new.payment_number),
+          normalize_text(new.date || ' ' || IFNULL(new.notes, '')));
+END;
+
+CREATE TRIGGER IF NOT EXISTS payments_search_au AFTER UPDATE OF payment_number, invoice_id, date, notes ON payments BEGIN
+  DELETE FROM search_index WHERE entity = 'payment' AND record_id = old.id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('payment', new.id, new.invoice_id, new.payment_number, normalize_text(new.payment_number),
+          normalize_text(new.date || ' ' || IFNULL(new.notes, '')));
+END;
+
+CREATE TRIGGER IF NOT EXISTS payments_search_ad AFTER DELETE ON payments BEGIN
+  DELETE FROM search_index WHERE entity = 'payment' AND record_id = old.id;
+END;
+
+-- Index existing rows
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'customer', id, NULL, name, normalize_text(name),
+       normalize_text(phone || ' ' || address || ' ' || IFNULL(notes, ''))
+FROM customers;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'invoice', id, NULL, invoice_number, normalize_text(invoice_number), date
+FROM invoices;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'item', id, invoice_id, product_name, normalize_text(product_name), ''
+FROM invoice_items;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'payment', id, invoice_id, payment_number, normalize_text(payment_number),
+       normalize_text(date || ' ' || IFNULL(notes, ''))
+FROM payments;
@@ -0,0 +1,34 @@
+ALTER TABLE customers ADD COLUMN name_normalized TEXT;
+
+UPDATE customers SET name_normalized = normalize_text(name);
+
+CREATE INDEX IF NOT EXISTS idx_customers_name_normalized ON customers(name_normalized);
+
+CREATE TRIGGER IF NOT EXISTS customers_name_normalized_ai AFTER INSERT ON customers BEGIN
+  UPDATE customers SET name_normalized = normalize_text(new.name) WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS customers_name_normalized_au AFTER UPDATE OF name ON customers BEGIN
+  UPDATE customers SET name_normalized = normalize_text(new.name) WHERE id = new.id;
+END;
+
+-- Re-index with the extended normalization (tatweel, ى, Arabic-Indic digits)
+DELETE FROM search_index;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'customer', id, NULL, name, normalize_text(name),
+       normalize_text(phone || ' ' || address || ' ' || IFNULL(notes, ''))
+FROM customers;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'invoice', id, NULL, invoice_number, normalize_text(invoice_number), date
+FROM invoices;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'item', id, invoice_id, product_name, normalize_text(product_name), ''
+FROM invoice_items;
+
+INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+SELECT 'payment', id, invoice_id, payment_number, normalize_text(payment_number),
+       normalize_text(date || ' ' || IFNULL(notes, ''))
+FROM payments;
@@ -0,0 +1,5 @@
+-- E.164 form of customers.phone; `phone` keeps the display form.
+-- Existing rows are filled by the normalize_customer_phones command.
+ALTER TABLE customers ADD COLUMN phone_e164 TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_customers_phone_e164 ON customers(phone_e164);
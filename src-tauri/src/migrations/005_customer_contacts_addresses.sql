@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS customer_contacts (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  customer_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  role TEXT,
+  phone TEXT,
+  phone_e164 TEXT,
+  email TEXT,
+  is_primary INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS customer_addresses (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  customer_id INTEGER NOT NULL,
+  kind TEXT NOT NULL DEFAULT 'billing', -- 'billing' | 'delivery'
+  label TEXT,
+  address TEXT NOT NULL,
+  is_primary INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_customer_contacts_customer ON customer_contacts(customer_id);
+CREATE INDEX IF NOT EXISTS idx_customer_addresses_customer ON customer_addresses(customer_id);
+
+ALTER TABLE invoices ADD COLUMN billing_address_id INTEGER REFERENCES customer_addresses(id) ON DELETE SET NULL;
+ALTER TABLE invoices ADD COLUMN delivery_address_id INTEGER REFERENCES customer_addresses(id) ON DELETE SET NULL;
+
@@ -0,0 +1,4 @@
+ALTER TABLE customers ADD COLUMN credit_limit REAL;
+ALTER TABLE customers ADD COLUMN max_days_overdue INTEGER;
+
+ALTER TABLE invoices ADD COLUMN credit_override_reason TEXT;
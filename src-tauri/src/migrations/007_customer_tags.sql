@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS tags (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL,
+  category TEXT NOT NULL DEFAULT '', -- e.g. 'segment' | 'region' | 'salesperson'
+  color TEXT,
+  created_at TEXT DEFAULT (datetime('now')),
+  UNIQUE (category, name)
+);
+
+CREATE TABLE IF NOT EXISTS customer_tags (
+  customer_id INTEGER NOT NULL,
+  tag_id INTEGER NOT NULL,
+  PRIMARY KEY (customer_id, tag_id),
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+  FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_customer_tags_tag ON customer_tags(tag_id);
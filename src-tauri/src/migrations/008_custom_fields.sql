@@ -0,0 +1,57 @@
+CREATE TABLE IF NOT EXISTS custom_fields (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  key TEXT NOT NULL UNIQUE, -- used in templates as {custom.key}
+  label TEXT NOT NULL,
+  field_type TEXT NOT NULL DEFAULT 'text', -- 'text' | 'number' | 'date' | 'select'
+  options TEXT, -- JSON array of choices for 'select'
+  required INTEGER NOT NULL DEFAULT 0,
+  position INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS customer_field_values (
+  customer_id INTEGER NOT NULL,
+  field_id INTEGER NOT NULL,
+  value TEXT NOT NULL,
+  PRIMARY KEY (customer_id, field_id),
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+  FOREIGN KEY (field_id) REFERENCES custom_fields(id) ON DELETE CASCADE
+);
+
+-- Custom field values are part of the customer's searchable text
+DROP TRIGGER IF EXISTS customers_search_au;
+
+CREATE TRIGGER IF NOT EXISTS customers_search_au AFTER UPDATE OF name, phone, address, notes ON customers BEGIN
+  DELETE FROM search_index WHERE entity = 'customer' AND record_id = old.id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  VALUES ('customer', new.id, NULL, new.name, normalize_text(new.name),
+          normalize_text(new.phone || ' ' || new.address || ' ' || IFNULL(new.notes, '') || ' ' ||
+            IFNULL((SELECT group_concat(value, ' ') FROM customer_field_values WHERE customer_id = new.id), '')));
+END;
+
+CREATE TRIGGER IF NOT EXISTS customer_field_values_search_ai AFTER INSERT ON customer_field_values BEGIN
+  DELETE FROM search_index WHERE entity = 'customer' AND record_id = new.customer_id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  SELECT 'customer', c.id, NULL, c.name, normalize_text(c.name),
+         normalize_text(c.phone || ' ' || c.address || ' ' || IFNULL(c.notes, '') || ' ' ||
+           IFNULL((SELECT group_concat(value, ' ') FROM customer_field_values WHERE customer_id = c.id), ''))
+  FROM customers c WHERE c.id = new.customer_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS customer_field_values_search_au AFTER UPDATE ON customer_field_values BEGIN
+  DELETE FROM search_index WHERE entity = 'customer' AND record_id = new.customer_id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  SELECT 'customer', c.id, NULL, c.name, normalize_text(c.name),
+         normalize_text(c.phone || ' ' || c.address || ' ' || IFNULL(c.notes, '') || ' ' ||
+           IFNULL((SELECT group_concat(value, ' ') FROM customer_field_values WHERE customer_id = c.id), ''))
+  FROM customers c WHERE c.id = new.customer_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS customer_field_values_search_ad AFTER DELETE ON customer_field_values BEGIN
+  DELETE FROM search_index WHERE entity = 'customer' AND record_id = old.customer_id;
+  INSERT INTO search_index (entity, record_id, parent_id, label, title, body)
+  SELECT 'customer', c.id, NULL, c.name, normalize_text(c.name),
+         normalize_text(c.phone || ' ' || c.address || ' ' || IFNULL(c.notes, '') || ' ' ||
+           IFNULL((SELECT group_concat(value, ' ') FROM customer_field_values WHERE customer_id = c.id), ''))
+  FROM customers c WHERE c.id = old.customer_id;
+END;
@@ -0,0 +1,10 @@
+-- NULL terms mean the default from settings applies
+ALTER TABLE customers ADD COLUMN payment_terms TEXT;
+ALTER TABLE customers ADD COLUMN payment_terms_days INTEGER;
+
+ALTER TABLE invoices ADD COLUMN due_date TEXT;
+
+-- Existing invoices were due on issue
+UPDATE invoices SET due_date = substr(date, 1, 10) WHERE due_date IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_invoices_due_date ON invoices(due_date);
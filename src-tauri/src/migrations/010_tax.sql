@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS tax_rates (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL,
+  rate REAL NOT NULL UNIQUE, -- percent, e.g. 19
+  is_default INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT DEFAULT (datetime('now'))
+);
+
+INSERT OR IGNORE INTO tax_rates (name, rate, is_default) VALUES
+  ('معفى', 0, 1),
+  ('TVA 9%', 9, 0),
+  ('TVA 19%', 19, 0);
+
+-- Lines keep the rate they were issued with, so editing a rate never
+-- changes past invoices
+ALTER TABLE invoice_items ADD COLUMN tax_rate REAL NOT NULL DEFAULT 0;
+ALTER TABLE invoice_items ADD COLUMN price_includes_tax INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invoice_items ADD COLUMN subtotal REAL;
+ALTER TABLE invoice_items ADD COLUMN tax_amount REAL NOT NULL DEFAULT 0;
+UPDATE invoice_items SET subtotal = total;
+
+ALTER TABLE invoices ADD COLUMN subtotal REAL;
+ALTER TABLE invoices ADD COLUMN tax_total REAL NOT NULL DEFAULT 0;
+UPDATE invoices SET subtotal = total;
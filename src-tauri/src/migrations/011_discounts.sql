@@ -0,0 +1,12 @@
+-- discount_type is 'percent' or 'fixed'; discount_amount is what it came to
+ALTER TABLE invoice_items ADD COLUMN discount_type TEXT;
+ALTER TABLE invoice_items ADD COLUMN discount_value REAL NOT NULL DEFAULT 0;
+ALTER TABLE invoice_items ADD COLUMN discount_amount REAL NOT NULL DEFAULT 0;
+
+-- The invoice discount applies to the lines before tax
+ALTER TABLE invoices ADD COLUMN discount_type TEXT;
+ALTER TABLE invoices ADD COLUMN discount_value REAL NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN discount_amount REAL NOT NULL DEFAULT 0;
+ALTER TABLE invoices ADD COLUMN salesperson TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_invoices_salesperson ON invoices(salesperson);
@@ -0,0 +1,42 @@
+CREATE TABLE IF NOT EXISTS credit_notes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  credit_note_number TEXT NOT NULL UNIQUE,
+  invoice_id INTEGER NOT NULL,
+  customer_id INTEGER NOT NULL,
+  date TEXT NOT NULL,
+  reason TEXT,
+  subtotal REAL NOT NULL,
+  tax_total REAL NOT NULL DEFAULT 0,
+  total REAL NOT NULL,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE,
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+-- Lines returned from the original invoice
+CREATE TABLE IF NOT EXISTS credit_note_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  credit_note_id INTEGER NOT NULL,
+  invoice_item_id INTEGER,
+  product_name TEXT NOT NULL,
+  unit_price REAL NOT NULL,
+  quantity REAL NOT NULL,
+  tax_rate REAL NOT NULL DEFAULT 0,
+  subtotal REAL NOT NULL,
+  tax_amount REAL NOT NULL DEFAULT 0,
+  total REAL NOT NULL,
+  FOREIGN KEY (credit_note_id) REFERENCES credit_notes(id) ON DELETE CASCADE,
+  FOREIGN KEY (invoice_item_id) REFERENCES invoice_items(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_notes_invoice ON credit_notes(invoice_id);
+CREATE INDEX IF NOT EXISTS idx_credit_notes_customer ON credit_notes(customer_id);
+CREATE INDEX IF NOT EXISTS idx_credit_note_items_note ON credit_note_items(credit_note_id);
+CREATE INDEX IF NOT EXISTS idx_credit_note_items_item ON credit_note_items(invoice_item_id);
+
+-- Credit notes count towards balances only while their invoice is live
+CREATE VIEW IF NOT EXISTS live_credit_notes AS
+  SELECT cn.*
+  FROM credit_notes cn
+  JOIN invoices i ON i.id = cn.invoice_id
+  WHERE i.deleted_at IS NULL;
@@ -0,0 +1,14 @@
+-- Lifecycle of an invoice, separate from its payment status:
+-- draft -> issued -> paid | cancelled. Only drafts can be edited.
+ALTER TABLE invoices ADD COLUMN state TEXT NOT NULL DEFAULT 'draft';
+ALTER TABLE invoices ADD COLUMN issued_at TEXT;
+-- SHA-256 of the invoice content when it was issued
+ALTER TABLE invoices ADD COLUMN content_hash TEXT;
+
+-- Invoices from before this migration were already handed out, so they are
+-- issued; their hash is computed in Rust right after (see db.rs)
+UPDATE invoices
+SET state = CASE WHEN status = 'paid' THEN 'paid' ELSE 'issued' END,
+    issued_at = created_at;
+
+CREATE INDEX IF NOT EXISTS idx_invoices_state ON invoices(state);
@@ -0,0 +1,11 @@
+-- Voided invoices keep their number with state 'cancelled'
+ALTER TABLE invoices ADD COLUMN void_reason TEXT;
+ALTER TABLE invoices ADD COLUMN voided_at TEXT;
+
+-- Credit notes of a voided invoice no longer count either
+DROP VIEW IF EXISTS live_credit_notes;
+CREATE VIEW live_credit_notes AS
+  SELECT cn.*
+  FROM credit_notes cn
+  JOIN invoices i ON i.id = cn.invoice_id
+  WHERE i.deleted_at IS NULL AND i.state <> 'cancelled';
@@ -0,0 +1,42 @@
+CREATE TABLE IF NOT EXISTS quotes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  quote_number TEXT NOT NULL UNIQUE,
+  customer_id INTEGER NOT NULL,
+  date TEXT NOT NULL,
+  valid_until TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'draft', -- 'draft' | 'sent' | 'accepted' | 'rejected' | 'expired'
+  notes TEXT,
+  discount_type TEXT,
+  discount_value REAL NOT NULL DEFAULT 0,
+  discount_amount REAL NOT NULL DEFAULT 0,
+  subtotal REAL NOT NULL DEFAULT 0,
+  tax_total REAL NOT NULL DEFAULT 0,
+  total REAL NOT NULL DEFAULT 0,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+-- Same line structure as invoice_items
+CREATE TABLE IF NOT EXISTS quote_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  quote_id INTEGER NOT NULL,
+  product_name TEXT NOT NULL,
+  unit_price REAL NOT NULL,
+  quantity REAL NOT NULL,
+  discount_type TEXT,
+  discount_value REAL NOT NULL DEFAULT 0,
+  discount_amount REAL NOT NULL DEFAULT 0,
+  tax_rate REAL NOT NULL DEFAULT 0,
+  price_includes_tax INTEGER NOT NULL DEFAULT 0,
+  subtotal REAL NOT NULL,
+  tax_amount REAL NOT NULL DEFAULT 0,
+  total REAL NOT NULL,
+  FOREIGN KEY (quote_id) REFERENCES quotes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_quotes_customer ON quotes(customer_id);
+CREATE INDEX IF NOT EXISTS idx_quote_items_quote ON quote_items(quote_id);
+
+-- Invoice created from a quote
+ALTER TABLE invoices ADD COLUMN quote_id INTEGER REFERENCES quotes(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_invoices_quote ON invoices(quote_id);
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS delivery_notes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  delivery_note_number TEXT NOT NULL UNIQUE,
+  invoice_id INTEGER NOT NULL,
+  customer_id INTEGER NOT NULL,
+  date TEXT NOT NULL,
+  notes TEXT,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE,
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+-- Quantities delivered per invoice line, printed without prices
+CREATE TABLE IF NOT EXISTS delivery_note_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  delivery_note_id INTEGER NOT NULL,
+  invoice_item_id INTEGER,
+  product_name TEXT NOT NULL,
+  quantity REAL NOT NULL,
+  FOREIGN KEY (delivery_note_id) REFERENCES delivery_notes(id) ON DELETE CASCADE,
+  FOREIGN KEY (invoice_item_id) REFERENCES invoice_items(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_delivery_notes_invoice ON delivery_notes(invoice_id);
+CREATE INDEX IF NOT EXISTS idx_delivery_notes_customer ON delivery_notes(customer_id);
+CREATE INDEX IF NOT EXISTS idx_delivery_note_items_note ON delivery_note_items(delivery_note_id);
+CREATE INDEX IF NOT EXISTS idx_delivery_note_items_item ON delivery_note_items(invoice_item_id);
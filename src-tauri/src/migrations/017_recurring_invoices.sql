@@ -0,0 +1,46 @@
+-- Schedules that bill a customer the same lines every period
+CREATE TABLE IF NOT EXISTS recurring_invoices (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  customer_id INTEGER NOT NULL,
+  frequency TEXT NOT NULL,
+  start_date TEXT NOT NULL,
+  end_date TEXT,
+  next_run TEXT NOT NULL,
+  active INTEGER NOT NULL DEFAULT 1,
+  notes TEXT,
+  discount_type TEXT,
+  discount_value REAL NOT NULL DEFAULT 0,
+  total REAL NOT NULL DEFAULT 0,
+  last_error TEXT,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS recurring_invoice_items (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  recurring_invoice_id INTEGER NOT NULL,
+  product_name TEXT NOT NULL,
+  unit_price REAL NOT NULL,
+  quantity REAL NOT NULL,
+  tax_rate REAL NOT NULL DEFAULT 0,
+  price_includes_tax INTEGER NOT NULL DEFAULT 0,
+  discount_type TEXT,
+  discount_value REAL NOT NULL DEFAULT 0,
+  FOREIGN KEY (recurring_invoice_id) REFERENCES recurring_invoices(id) ON DELETE CASCADE
+);
+
+-- One row per invoice generated from a schedule
+CREATE TABLE IF NOT EXISTS recurring_invoice_runs (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  recurring_invoice_id INTEGER NOT NULL,
+  invoice_id INTEGER,
+  run_date TEXT NOT NULL,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (recurring_invoice_id) REFERENCES recurring_invoices(id) ON DELETE CASCADE,
+  FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_invoices_customer ON recurring_invoices(customer_id);
+CREATE INDEX IF NOT EXISTS idx_recurring_invoices_next_run ON recurring_invoices(next_run);
+CREATE INDEX IF NOT EXISTS idx_recurring_invoice_items_schedule ON recurring_invoice_items(recurring_invoice_id);
+CREATE INDEX IF NOT EXISTS idx_recurring_invoice_runs_schedule ON recurring_invoice_runs(recurring_invoice_id);
@@ -0,0 +1,28 @@
+-- Product catalog with the current selling price
+CREATE TABLE IF NOT EXISTS products (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL,
+  name_normalized TEXT,
+  unit_price REAL,
+  created_at TEXT DEFAULT (datetime('now'))
+);
+
+-- Started from the products already sold, at their latest price
+INSERT INTO products (name, name_normalized, unit_price)
+SELECT product_name, name_key, unit_price
+FROM (
+  SELECT product_name, normalize_text(product_name) AS name_key, unit_price,
+         ROW_NUMBER() OVER (PARTITION BY normalize_text(product_name) ORDER BY id DESC) AS rn
+  FROM invoice_items
+)
+WHERE rn = 1 AND name_key <> '';
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_name_normalized ON products(name_normalized);
+
+CREATE TRIGGER IF NOT EXISTS products_name_normalized_ai AFTER INSERT ON products BEGIN
+  UPDATE products SET name_normalized = normalize_text(new.name) WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_name_normalized_au AFTER UPDATE OF name ON products BEGIN
+  UPDATE products SET name_normalized = normalize_text(new.name) WHERE id = new.id;
+END;
@@ -0,0 +1,31 @@
+-- Manually entered rates: how many units of the base currency one unit of
+-- `currency` is worth from `date` on
+CREATE TABLE IF NOT EXISTS exchange_rates (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  currency TEXT NOT NULL,
+  date TEXT NOT NULL,
+  rate REAL NOT NULL,
+  created_at TEXT DEFAULT (datetime('now')),
+  UNIQUE (currency, date)
+);
+
+-- Documents keep the rate they were issued at; existing ones are in the
+-- base currency
+ALTER TABLE invoices ADD COLUMN currency TEXT NOT NULL DEFAULT 'DZD';
+ALTER TABLE invoices ADD COLUMN exchange_rate REAL NOT NULL DEFAULT 1;
+ALTER TABLE payments ADD COLUMN currency TEXT NOT NULL DEFAULT 'DZD';
+ALTER TABLE payments ADD COLUMN exchange_rate REAL NOT NULL DEFAULT 1;
+ALTER TABLE credit_notes ADD COLUMN currency TEXT NOT NULL DEFAULT 'DZD';
+ALTER TABLE credit_notes ADD COLUMN exchange_rate REAL NOT NULL DEFAULT 1;
+
+-- Converted at the rate of the day the invoice is issued
+ALTER TABLE quotes ADD COLUMN currency TEXT NOT NULL DEFAULT 'DZD';
+ALTER TABLE recurring_invoices ADD COLUMN currency TEXT NOT NULL DEFAULT 'DZD';
+
+-- The view was expanded with the old columns when it was created
+DROP VIEW IF EXISTS live_credit_notes;
+CREATE VIEW live_credit_notes AS
+  SELECT cn.*
+  FROM credit_notes cn
+  JOIN invoices i ON i.id = cn.invoice_id
+  WHERE i.deleted_at IS NULL;
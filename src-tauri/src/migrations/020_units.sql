@@ -0,0 +1,22 @@
+-- Units of measure. A unit may be a multiple of a base unit, e.g. a box of
+-- 12 pieces has base_unit_id = piece and factor = 12
+CREATE TABLE IF NOT EXISTS units (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL UNIQUE,
+  decimals INTEGER NOT NULL DEFAULT 0, -- decimal places allowed in quantities
+  base_unit_id INTEGER REFERENCES units(id),
+  factor REAL NOT NULL DEFAULT 1,
+  created_at TEXT DEFAULT (datetime('now'))
+);
+
+INSERT OR IGNORE INTO units (name, decimals) VALUES ('قطعة', 0), ('كغ', 3), ('م', 2), ('ل', 2);
+INSERT OR IGNORE INTO units (name, decimals, base_unit_id, factor)
+SELECT 'علبة', 0, id, 12 FROM units WHERE name = 'قطعة';
+INSERT OR IGNORE INTO units (name, decimals, base_unit_id, factor)
+SELECT 'غ', 0, id, 0.001 FROM units WHERE name = 'كغ';
+
+-- Existing products and lines have no unit
+ALTER TABLE products ADD COLUMN unit_id INTEGER REFERENCES units(id) ON DELETE SET NULL;
+ALTER TABLE invoice_items ADD COLUMN unit_id INTEGER REFERENCES units(id);
+ALTER TABLE quote_items ADD COLUMN unit_id INTEGER REFERENCES units(id);
+ALTER TABLE recurring_invoice_items ADD COLUMN unit_id INTEGER REFERENCES units(id);
@@ -0,0 +1,27 @@
+-- Stock is tracked per catalog product once enabled, in the product's unit
+ALTER TABLE products ADD COLUMN track_stock INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE products ADD COLUMN low_stock_threshold REAL;
+ALTER TABLE products ADD COLUMN cost_price REAL;
+
+-- Every change of stock; the current stock is their sum. Sales and returns
+-- keep the invoice line they come from
+CREATE TABLE IF NOT EXISTS stock_movements (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  product_id INTEGER NOT NULL,
+  date TEXT NOT NULL,
+  quantity REAL NOT NULL, -- negative when stock goes out
+  kind TEXT NOT NULL, -- 'opening' | 'sale' | 'return' | 'void' | 'adjustment'
+  invoice_id INTEGER,
+  invoice_item_id INTEGER,
+  credit_note_id INTEGER,
+  reason TEXT,
+  created_at TEXT DEFAULT (datetime('now')),
+  FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+  FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE SET NULL,
+  FOREIGN KEY (invoice_item_id) REFERENCES invoice_items(id) ON DELETE SET NULL,
+  FOREIGN KEY (credit_note_id) REFERENCES credit_notes(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_product ON stock_movements(product_id, date);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_invoice ON stock_movements(invoice_id);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_item ON stock_movements(invoice_item_id);
@@ -0,0 +1,7 @@
+-- Credit notes of voided invoices no longer reduce what the customer owes
+DROP VIEW IF EXISTS live_credit_notes;
+CREATE VIEW live_credit_notes AS
+  SELECT cn.*
+  FROM credit_notes cn
+  JOIN invoices i ON i.id = cn.invoice_id
+  WHERE i.deleted_at IS NULL AND i.state <> 'cancelled';
@@ -0,0 +1,8 @@
+-- A schedule runs at most once per date, even if two generations overlap
+DELETE FROM recurring_invoice_runs
+WHERE id NOT IN (
+  SELECT MIN(id) FROM recurring_invoice_runs GROUP BY recurring_invoice_id, run_date
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_recurring_invoice_runs_date
+  ON recurring_invoice_runs(recurring_invoice_id, run_date);
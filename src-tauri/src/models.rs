@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 //
 // ==================== Customer ====================
@@ -11,23 +12,46 @@ pub struct Customer {
     pub address: String,
     pub notes: Option<String>,
     pub created_at: Option<String>,
+    pub phone_e164: Option<String>, // normalized form of `phone`, e.g. "+213555123456"
+    pub credit_limit: Option<f64>,  // no limit when None
+    pub max_days_overdue: Option<i32>, // no limit when None
+    pub payment_terms: Option<String>, // "immediate" | "net_15" | "net_30" | "net_60" | "end_of_month" | "custom"; default terms when None
+    pub payment_terms_days: Option<i32>, // for "custom" terms
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, String>, // custom field key → value
 }
 
 //
 // ==================== Invoice ====================
 //
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Invoice {
     pub id: Option<i32>,
     pub invoice_number: String,
     pub customer_id: i32,
     pub customer_name: Option<String>,
     pub date: String,
+    pub due_date: Option<String>, // computed from the customer's payment terms when None
+    pub subtotal: Option<f64>,    // before tax, after discounts; equals `total` when None
+    pub tax_total: Option<f64>,
+    pub discount_type: Option<String>, // "percent" | "fixed"
+    #[serde(default)]
+    pub discount_value: f64,
+    pub discount_amount: Option<f64>, // computed from the lines
+    pub salesperson: Option<String>,
     pub total: f64,
     pub status: Option<String>, // "paid" | "unpaid" | "partial"
+    pub state: Option<String>,  // "draft" | "issued" | "paid" | "cancelled"
+    pub issued_at: Option<String>,
+    pub void_reason: Option<String>,
+    pub quote_id: Option<i32>, // the quote this invoice was converted from
+    pub currency: Option<String>, // the base currency when None
+    pub exchange_rate: Option<f64>, // rate of the invoice date when None
     pub paid_amount: Option<f64>,
     pub remaining_amount: Option<f64>,
     pub created_at: Option<String>,
+    pub billing_address_id: Option<i32>,
+    pub delivery_address_id: Option<i32>,
 }
 
 //
@@ -40,10 +64,28 @@ pub struct InvoiceWithCustomer {
     pub customer_id: i32,
     pub customer_name: Option<String>,
     pub customer_phone: Option<String>,
-    pub customer_address: Option<String>,
+    pub customer_address: Option<String>, // billing address, or the customer's address
+    pub delivery_address: Option<String>,
+    pub billing_address_id: Option<i32>,
+    pub delivery_address_id: Option<i32>,
     pub date: String,
+    pub due_date: Option<String>,
+    pub subtotal: Option<f64>,
+    pub tax_total: Option<f64>,
+    pub discount_type: Option<String>,
+    pub discount_value: f64,
+    pub discount_amount: f64,
+    pub salesperson: Option<String>,
     pub total: f64,
     pub status: Option<String>,
+    pub state: Option<String>,
+    pub issued_at: Option<String>,
+    pub void_reason: Option<String>,
+    pub quote_id: Option<i32>,
+    pub currency: String,
+    pub exchange_rate: f64,
+    pub overdue: bool,         // not paid and past its due date
+    pub fully_delivered: bool, // every line delivered through delivery notes
     pub paid_amount: Option<f64>,
     pub remaining_amount: Option<f64>,
     pub created_at: Option<String>,
@@ -55,7 +97,9 @@ pub struct InvoiceWithCustomer {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct InvoicesResponse {
     pub data: Vec<InvoiceWithCustomer>,
-    pub total: f64,
+    pub total: f64, // totals in the base currency
+    pub subtotal: f64,
+    pub tax_total: f64,
 }
 
 //
@@ -66,11 +110,249 @@ pub struct InvoiceItem {
     pub id: Option<i32>,
     pub invoice_id: i32,
     pub product_name: String,
+    pub unit_price: f64, // per `unit_id`
+    pub quantity: f64,
+    pub unit_id: Option<i32>, // the product's unit when None
+    pub unit_name: Option<String>,
+    pub tax_rate: Option<f64>, // percent, the default rate when None
+    #[serde(default)]
+    pub price_includes_tax: bool, // `unit_price` already includes the tax
+    pub discount_type: Option<String>, // "percent" | "fixed"
+    #[serde(default)]
+    pub discount_value: f64,
+    pub discount_amount: Option<f64>, // computed when the line is saved
+    pub subtotal: Option<f64>,        // computed when the line is saved
+    pub tax_amount: Option<f64>,      // computed when the line is saved
+    pub total: f64,
+}
+
+//
+// ==================== Quote ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Quote {
+    pub id: Option<i32>,
+    pub quote_number: Option<String>, // "DEV-2026-0001", assigned on creation
+    pub customer_id: i32,
+    pub customer_name: Option<String>,
+    pub date: String,
+    pub valid_until: Option<String>, // 30 days after `date` when None
+    pub status: Option<String>,      // "draft" | "sent" | "accepted" | "rejected" | "expired"
+    pub notes: Option<String>,
+    pub discount_type: Option<String>,
+    #[serde(default)]
+    pub discount_value: f64,
+    pub discount_amount: Option<f64>, // computed from the lines
+    pub subtotal: Option<f64>,
+    pub tax_total: Option<f64>,
+    pub total: Option<f64>,
+    pub currency: Option<String>, // the base currency when None
+    pub invoice_id: Option<i32>,  // set once converted to an invoice
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub items: Vec<QuoteItem>, // only filled for a single quote
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuoteItem {
+    pub id: Option<i32>,
+    pub product_name: String,
     pub unit_price: f64,
     pub quantity: f64,
+    pub unit_id: Option<i32>,
+    pub unit_name: Option<String>,
+    #[serde(default)]
+    pub tax_rate: f64,
+    #[serde(default)]
+    pub price_includes_tax: bool,
+    pub discount_type: Option<String>,
+    #[serde(default)]
+    pub discount_value: f64,
+    pub discount_amount: Option<f64>, // computed when the quote is saved
+    pub subtotal: Option<f64>,
+    pub tax_amount: Option<f64>,
+    pub total: Option<f64>,
+}
+
+//
+// ==================== Recurring Invoice ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecurringInvoice {
+    pub id: Option<i32>,
+    pub customer_id: i32,
+    pub customer_name: Option<String>,
+    pub frequency: String, // "weekly" | "monthly" | "quarterly" | "yearly"
+    pub start_date: String,
+    pub end_date: Option<String>, // no end when None
+    pub next_run: Option<String>, // date of the next invoice; `start_date` when None
+    pub active: Option<bool>,
+    pub notes: Option<String>,
+    pub discount_type: Option<String>,
+    #[serde(default)]
+    pub discount_value: f64,
+    pub total: Option<f64>,         // computed from the lines
+    pub currency: Option<String>,   // the base currency when None
+    pub last_error: Option<String>, // why the last due invoice could not be generated
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub items: Vec<RecurringInvoiceItem>, // only filled for a single schedule
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecurringInvoiceItem {
+    pub id: Option<i32>,
+    pub product_name: String,
+    pub unit_price: f64,
+    pub quantity: f64,
+    pub unit_id: Option<i32>,
+    pub unit_name: Option<String>,
+    #[serde(default)]
+    pub tax_rate: f64,
+    #[serde(default)]
+    pub price_includes_tax: bool,
+    pub discount_type: Option<String>,
+    #[serde(default)]
+    pub discount_value: f64,
+}
+
+// An invoice generated from a schedule
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecurringInvoiceRun {
+    pub id: i32,
+    pub recurring_invoice_id: i32,
+    pub invoice_id: Option<i32>, // None once the invoice is purged
+    pub invoice_number: Option<String>,
+    pub run_date: String,
+    pub created_at: Option<String>,
+}
+
+//
+// ==================== Credit Note ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreditNote {
+    pub id: Option<i32>,
+    pub credit_note_number: String, // "AV-2026-0001"
+    pub invoice_id: i32,
+    pub invoice_number: Option<String>,
+    pub customer_id: i32,
+    pub customer_name: Option<String>,
+    pub date: String,
+    pub reason: Option<String>,
+    pub subtotal: f64,
+    pub tax_total: f64,
+    pub total: f64,
+    pub currency: Option<String>, // the invoice's
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub items: Vec<CreditNoteItem>, // only filled for a single credit note
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreditNoteItem {
+    pub id: Option<i32>,
+    pub invoice_item_id: Option<i32>,
+    pub product_name: String,
+    pub unit_price: f64,
+    pub quantity: f64,
+    pub unit_name: Option<String>, // the invoice line's
+    pub tax_rate: f64,
+    pub subtotal: f64,
+    pub tax_amount: f64,
     pub total: f64,
 }
 
+// A line to return: `quantity` of the invoice line `invoice_item_id`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreditNoteLine {
+    pub invoice_item_id: i32,
+    pub quantity: f64,
+}
+
+//
+// ==================== Delivery Note ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryNote {
+    pub id: Option<i32>,
+    pub delivery_note_number: String, // "BL-2026-0001"
+    pub invoice_id: i32,
+    pub invoice_number: Option<String>,
+    pub customer_id: i32,
+    pub customer_name: Option<String>,
+    pub date: String,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub items: Vec<DeliveryNoteItem>, // only filled for a single delivery note
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryNoteItem {
+    pub id: Option<i32>,
+    pub invoice_item_id: Option<i32>,
+    pub product_name: String,
+    pub quantity: f64,
+    pub unit_name: Option<String>, // the invoice line's
+}
+
+// A line to deliver: `quantity` of the invoice line `invoice_item_id`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryNoteLine {
+    pub invoice_item_id: i32,
+    pub quantity: f64,
+}
+
+// Ordered vs delivered quantity of an invoice line
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeliveryStatus {
+    pub invoice_item_id: i32,
+    pub product_name: String,
+    pub unit_id: Option<i32>,
+    pub unit_name: Option<String>,
+    pub ordered_quantity: f64,
+    pub delivered_quantity: f64,
+    pub remaining_quantity: f64,
+}
+
+//
+// ==================== Tax Rate ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaxRate {
+    pub id: Option<i32>,
+    pub name: String,
+    pub rate: f64, // percent
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+// Amounts of the invoice lines taxed at one rate
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaxSummary {
+    pub tax_rate: f64,
+    pub subtotal: f64,
+    pub tax_amount: f64,
+    pub total: f64,
+    pub invoice_count: i64,
+}
+
+//
+// ==================== Discounts Report ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DiscountTotal {
+    pub customer_id: Option<i32>,    // set when grouped by customer
+    pub salesperson: Option<String>, // set when grouped by salesperson
+    pub name: String,
+    pub invoice_count: i64,
+    pub gross_amount: f64, // lines before any discount
+    pub line_discounts: f64,
+    pub invoice_discounts: f64,
+    pub total_discount: f64,
+}
+
 //
 // ==================== Product ====================
 //
@@ -78,10 +360,103 @@ pub struct InvoiceItem {
 pub struct Product {
     pub id: Option<i32>,
     pub name: String,
+    pub unit_price: Option<f64>, // per `unit_id`
+    pub unit_id: Option<i32>,
+    pub unit_name: Option<String>,
+    #[serde(default)]
+    pub track_stock: bool,
+    pub low_stock_threshold: Option<f64>,
+    pub cost_price: Option<f64>, // used to value the stock
+    pub stock: Option<f64>,      // current stock, None when not tracked
+    pub created_at: Option<String>,
+}
+
+//
+// ==================== Stock ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StockMovement {
+    pub id: Option<i32>,
+    pub product_id: i32,
+    pub product_name: Option<String>,
+    pub date: String,
+    pub quantity: f64, // in the product's unit, negative when stock goes out
+    pub kind: String,  // "opening" | "sale" | "return" | "void" | "adjustment"
+    pub invoice_id: Option<i32>,
+    pub invoice_number: Option<String>,
+    pub credit_note_id: Option<i32>,
+    pub reason: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StockValuationRow {
+    pub product_id: i32,
+    pub product_name: String,
+    pub unit_name: Option<String>,
+    pub quantity: f64,
+    pub cost_price: Option<f64>,
     pub unit_price: Option<f64>,
+    pub cost_value: f64, // 0 without a cost price
+    pub sale_value: f64, // 0 without a selling price
+}
+
+// Stock of the tracked products on a date, in the base currency
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StockValuation {
+    pub as_of_date: String,
+    pub rows: Vec<StockValuationRow>,
+    pub total_cost_value: f64,
+    pub total_sale_value: f64,
+}
+
+//
+// ==================== Unit ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Unit {
+    pub id: Option<i32>,
+    pub name: String, // "كغ"
+    #[serde(default)]
+    pub decimals: i32, // decimal places allowed in quantities
+    pub base_unit_id: Option<i32>,
+    pub base_unit_name: Option<String>,
+    #[serde(default)]
+    pub factor: f64, // `base_unit_id` units in one of this unit, 1 without one
     pub created_at: Option<String>,
 }
 
+//
+// ==================== Currency ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Currency {
+    pub code: String, // "EUR"
+    pub symbol: String,
+    pub decimals: usize,
+    pub is_base: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExchangeRate {
+    pub id: Option<i32>,
+    pub currency: String,
+    pub date: String, // applies from this date until the next rate
+    pub rate: f64,    // units of the base currency per unit of `currency`
+    pub created_at: Option<String>,
+}
+
+// Balance of the documents issued in one currency
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrencyBalance {
+    pub currency: String,
+    pub total_invoices: f64,
+    pub total_payments: f64,
+    pub total_credits: f64,
+    pub balance: f64,
+    pub base_balance: f64, // converted at the rates of the documents
+}
+
 //
 // ==================== Payment ====================
 //
@@ -94,6 +469,8 @@ pub struct Payment {
     pub invoice_id: Option<i32>,
     pub invoice_number: Option<String>,
     pub amount: f64,
+    pub currency: Option<String>, // the invoice's, or the base currency, when not given
+    pub exchange_rate: Option<f64>, // rate of the day when not given
     pub date: String,
     pub notes: Option<String>,
     pub created_at: Option<String>,
@@ -121,6 +498,7 @@ pub struct CustomerDebt {
     pub total_debt: f64,
     pub total_payments: f64,
     pub total_invoices: f64,
+    pub total_credits: f64, // credit notes
 }
 
 //
@@ -130,6 +508,7 @@ pub struct CustomerDebt {
 pub struct ReportSummary {
     pub total_invoices: i32,
     pub total_payments: f64,
+    pub total_credits: f64,
     pub total_debts: f64,
     pub customer_count: i32,
 }
@@ -140,14 +519,17 @@ pub struct ReportSummary {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Transaction {
     pub record_id: i32,
-    pub transaction_type: String,  // "invoice" | "payment"
-    pub reference: Option<String>, // invoice_number for invoices, None for payments
+    pub transaction_type: String, // "invoice" | "payment" | "credit_note"
+    pub reference: Option<String>, // invoice, payment or credit note number
     pub customer_id: i32,
     pub customer_name: String,
     pub customer_phone: String,
     pub date: String,
-    pub amount: f64,
+    pub amount: f64, // in the base currency
     pub created_at: String,
+    pub currency: String,
+    pub currency_amount: f64, // in the document's own currency
+    pub running_balance: f64, // customer balance after this transaction
 }
 
 //
@@ -156,7 +538,214 @@ pub struct Transaction {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TransactionsResponse {
     pub data: Vec<Transaction>,
+    pub total_invoices: f64, // totals in the base currency
+    pub total_payments: f64,
+    pub total_credits: f64,
+    pub remaining_total: f64, // net of the period (invoices - payments - credit notes)
+    pub opening_balance: f64, // balance carried from before `from_date`
+    pub closing_balance: f64, // opening_balance + remaining_total
+}
+
+//
+// ==================== Audit Entry ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity: String, // "customer" | "invoice" | "payment" | "customer_contact" | "customer_address" | "tag" | "custom_field" | "tax_rate" | "credit_note" | "quote" | "delivery_note" | "recurring_invoice" | "product" | "exchange_rate" | "unit" | "stock_movement"
+    pub entity_id: i64,
+    pub action: String, // "create" | "update" | "delete" | "restore" | "purge" | "merge" | "add_item" | "delete_items" | "credit_override" | "update_tags" | "finalize" | "void" | "convert"
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub user_name: Option<String>,
+    pub created_at: Option<String>,
+}
+
+//
+// ==================== Deleted Item ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeletedItem {
+    pub entity: String, // "customer" | "invoice" | "payment"
+    pub id: i64,
+    pub label: String, // customer name, invoice number or payment number
+    pub customer_name: Option<String>,
+    pub amount: Option<f64>,
+    pub deleted_at: String,
+}
+
+//
+// ==================== Search Hit ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchHit {
+    pub entity: String, // "customer" | "invoice" | "item" | "payment"
+    pub record_id: i64,
+    pub invoice_id: Option<i64>, // owning invoice for items, linked invoice for payments
+    pub customer_id: i64,
+    pub customer_name: String,
+    pub label: String,   // name, invoice/payment number or product name
+    pub snippet: String, // matched text with <mark>…</mark> around hits
+    pub score: f64,      // bm25, lower is better
+}
+
+//
+// ==================== Duplicate Group ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DuplicateGroup {
+    pub customers: Vec<Customer>,
+    pub score: f64, // 1.0 for the same phone number, otherwise name similarity
+}
+
+//
+// ==================== Phone Normalization Report ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InvalidPhone {
+    pub customer_id: i32,
+    pub customer_name: String,
+    pub phone: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PhoneNormalizationReport {
+    pub updated: i32,
+    pub unchanged: i32,
+    pub invalid: Vec<InvalidPhone>,
+}
+
+//
+// ==================== Customer Contact ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomerContact {
+    pub id: Option<i32>,
+    pub customer_id: i32,
+    pub name: String,
+    pub role: Option<String>,
+    pub phone: Option<String>,
+    pub phone_e164: Option<String>,
+    pub email: Option<String>,
+    pub is_primary: bool,
+    pub created_at: Option<String>,
+}
+
+//
+// ==================== Customer Address ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomerAddress {
+    pub id: Option<i32>,
+    pub customer_id: i32,
+    pub kind: String, // "billing" | "delivery"
+    pub label: Option<String>,
+    pub address: String,
+    pub is_primary: bool,
+    pub created_at: Option<String>,
+}
+
+//
+// ==================== Credit Status ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreditStatus {
+    pub customer_id: i32,
+    pub customer_name: String,
+    pub balance: f64,
+    pub credit_limit: Option<f64>,
+    pub max_days_overdue: Option<i32>,
+    pub days_overdue: Option<i64>, // days past the due date of the oldest unpaid invoice
+    pub over_limit: bool,
+    pub over_days: bool,
+}
+
+//
+// ==================== Tag ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Tag {
+    pub id: Option<i32>,
+    pub name: String,
+    pub category: Option<String>, // e.g. "segment" | "region" | "salesperson"
+    pub color: Option<String>,
+    pub customer_count: Option<i64>,
+}
+
+//
+// ==================== Tag Debt Total ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TagDebtTotal {
+    pub tag_id: i32,
+    pub tag_name: String,
+    pub category: Option<String>,
+    pub customer_count: i64,
     pub total_invoices: f64,
     pub total_payments: f64,
-    pub remaining_total: f64,
+    pub total_debt: f64,
+}
+
+//
+// ==================== Custom Field ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomField {
+    pub id: Option<i32>,
+    pub key: String, // used in templates as {custom.key}
+    pub label: String,
+    pub field_type: String, // "text" | "number" | "date" | "select"
+    #[serde(default)]
+    pub options: Vec<String>, // choices for "select"
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub position: i32,
+}
+
+//
+// ==================== Aging Report ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgingBucket {
+    pub label: String, // "current", "1-30", ..., "90+"
+    pub from_days: i64,
+    pub to_days: Option<i64>, // None for the last bucket
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgingRow {
+    pub customer_id: i32,
+    pub customer_name: String,
+    pub customer_phone: String,
+    pub amounts: Vec<f64>, // one amount per bucket
+    pub total: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgingReport {
+    pub as_of_date: String,
+    pub basis: String, // "invoice_date" | "due_date"
+    pub buckets: Vec<AgingBucket>,
+    pub rows: Vec<AgingRow>,
+    pub totals: Vec<f64>, // one total per bucket
+    pub total: f64,
+}
+
+//
+// ==================== Overdue Invoice ====================
+//
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OverdueInvoice {
+    pub id: i32,
+    pub invoice_number: String,
+    pub customer_id: i32,
+    pub customer_name: String,
+    pub customer_phone: String,
+    pub date: String,
+    pub due_date: String,
+    pub total: f64,       // in the base currency
+    pub outstanding: f64, // after allocating the customer's payments
+    pub days_overdue: i64,
 }
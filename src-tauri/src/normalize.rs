@@ -0,0 +1,79 @@
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{Connection, Result};
+
+/// Folds text for searching and comparison: lowercases, drops Arabic
+/// diacritics (tashkeel) and tatweel, maps letter variants to a single form
+/// (أ إ آ ٱ → ا, ؤ → و, ئ ى → ي, ة → ه) and Arabic-Indic digits to 0-9.
+pub fn normalize_text(input: &str) -> String {
+    input
+        .chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                '\u{0610}'..='\u{061A}' // Quranic marks
+                    | '\u{064B}'..='\u{065F}' // tashkeel
+                    | '\u{0670}' // superscript alef
+                    | '\u{0640}' // tatweel
+                    | '\u{06D6}'..='\u{06ED}' // Quranic annotation signs
+            )
+        })
+        .map(|c| match c {
+            'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+            'ؤ' => 'و',
+            'ئ' | 'ى' => 'ي',
+            'ة' => 'ه',
+            '\u{0660}'..='\u{0669}' => char::from(b'0' + (c as u32 - 0x0660) as u8),
+            '\u{06F0}'..='\u{06F9}' => char::from(b'0' + (c as u32 - 0x06F0) as u8),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Builds a `LIKE` pattern matching `query` anywhere in a normalized column.
+pub fn like_pattern(query: &str) -> String {
+    format!("%{}%", normalize_text(query))
+}
+
+/// Registers `normalize_text(x)` on the connection so SQL (triggers, search
+/// queries) folds text the same way as Rust code.
+pub fn register_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "normalize_text",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let value: Option<String> = ctx.get(0)?;
+            Ok(value.map(|s| normalize_text(&s)))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_letter_variants() {
+        assert_eq!(normalize_text("أإآٱ"), "اااا");
+        assert_eq!(normalize_text("مؤسسة"), "موسسه");
+        assert_eq!(normalize_text("شاطئ مستشفى"), "شاطي مستشفي");
+    }
+
+    #[test]
+    fn drops_diacritics_and_tatweel() {
+        assert_eq!(normalize_text("مُحَمَّد"), "محمد");
+        assert_eq!(normalize_text("محـــمد"), "محمد");
+    }
+
+    #[test]
+    fn maps_digits_and_lowercases() {
+        assert_eq!(normalize_text("INV-٠١٢"), "inv-012");
+        assert_eq!(normalize_text("۰۹"), "09");
+    }
+
+    #[test]
+    fn like_pattern_wraps_normalized_query() {
+        assert_eq!(like_pattern("أحمد"), "%احمد%");
+    }
+}
@@ -0,0 +1,219 @@
+use crate::normalize::normalize_text;
+
+/// Numbering plans: (ISO countries, calling code, national number length
+/// range without the trunk prefix, whether a leading 0 is a trunk prefix).
+/// Countries sharing a calling code (NANP) form a single entry.
+type Plan = (&'static [&'static str], &'static str, usize, usize, bool);
+
+const COUNTRIES: &[Plan] = &[
+    (&["DZ"], "213", 8, 9, true),
+    (&["MA"], "212", 9, 9, true),
+    (&["TN"], "216", 8, 8, false),
+    (&["LY"], "218", 8, 9, true),
+    (&["EG"], "20", 8, 10, true),
+    (&["SA"], "966", 8, 9, true),
+    (&["AE"], "971", 8, 9, true),
+    (&["QA"], "974", 8, 8, false),
+    (&["KW"], "965", 8, 8, false),
+    (&["JO"], "962", 8, 9, true),
+    (&["LB"], "961", 7, 8, true),
+    (&["FR"], "33", 9, 9, true),
+    (&["BE"], "32", 8, 9, true),
+    (&["ES"], "34", 9, 9, false),
+    // Italian landlines keep their leading 0 after +39
+    (&["IT"], "39", 6, 11, false),
+    (&["DE"], "49", 6, 13, true),
+    (&["GB"], "44", 9, 10, true),
+    (&["TR"], "90", 10, 10, true),
+    (&["US", "CA"], "1", 10, 10, false),
+];
+
+fn plan_for(country: &str) -> Option<&'static Plan> {
+    COUNTRIES
+        .iter()
+        .find(|(isos, _, _, _, _)| isos.iter().any(|iso| iso.eq_ignore_ascii_case(country)))
+}
+
+pub const DEFAULT_COUNTRY: &str = "DZ";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedPhone {
+    pub e164: String,    // "+213555123456"
+    pub display: String, // "0555 12 34 56" for local numbers, "+33 612 34 56 78" otherwise
+}
+
+pub fn is_known_country(country: &str) -> bool {
+    plan_for(country).is_some()
+}
+
+fn group_digits(digits: &str, first: usize) -> String {
+    let mut parts = vec![&digits[..first.min(digits.len())]];
+    let mut rest = &digits[first.min(digits.len())..];
+    while rest.len() > 3 {
+        parts.push(&rest[..2]);
+        rest = &rest[2..];
+    }
+    if !rest.is_empty() {
+        parts.push(rest);
+    }
+    parts.join(" ")
+}
+
+/// Parses a phone number typed in any common format (spaces, dashes,
+/// Arabic-Indic digits, `00` or `+` prefix, with or without the trunk 0)
+/// into E.164. Numbers without a country code use `default_country`.
+pub fn parse_phone(input: &str, default_country: &str) -> Result<ParsedPhone, String> {
+    let cleaned: String = normalize_text(input)
+        .chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                ' ' | '-' | '.' | '(' | ')' | '/' | '\u{200F}' | '\u{200E}'
+            )
+        })
+        .collect();
+
+    if cleaned.is_empty() {
+        return Err("رقم الهاتف مطلوب".into());
+    }
+
+    let (international, digits) = if let Some(rest) = cleaned.strip_prefix('+') {
+        (true, rest.to_string())
+    } else if let Some(rest) = cleaned.strip_prefix("00") {
+        (true, rest.to_string())
+    } else {
+        (false, cleaned.clone())
+    };
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err("رقم الهاتف يجب أن يحتوي على أرقام فقط".into());
+    }
+
+    let default_plan = plan_for(default_country);
+
+    let (plan, nsn) = if international {
+        // Longest calling code that prefixes the number
+        let plan = COUNTRIES
+            .iter()
+            .filter(|(_, code, _, _, _)| digits.starts_with(code))
+            .max_by_key(|(_, code, _, _, _)| code.len())
+            .ok_or_else(|| "رمز الدولة غير معروف".to_string())?;
+        let rest = &digits[plan.1.len()..];
+        // "+33 (0)6 ..." is a common way of writing the trunk prefix
+        let nsn = if plan.4 {
+            rest.strip_prefix('0').unwrap_or(rest)
+        } else {
+            rest
+        };
+        (plan, nsn.to_string())
+    } else {
+        let plan = default_plan
+            .ok_or_else(|| format!("الدولة الافتراضية غير معروفة: {}", default_country))?;
+        let (_, code, _, max, trunk) = plan;
+        let nsn = if *code == "1" {
+            digits
+                .strip_prefix('1')
+                .filter(|d| d.len() == *max)
+                .unwrap_or(&digits)
+        } else if *trunk {
+            digits.strip_prefix('0').unwrap_or(&digits)
+        } else {
+            &digits
+        };
+        (plan, nsn.to_string())
+    };
+
+    let (_, code, min, max, trunk) = plan;
+    if nsn.len() < *min || nsn.len() > *max {
+        return Err("رقم الهاتف غير صالح".into());
+    }
+
+    let local = default_plan.is_some_and(|d| std::ptr::eq(d, plan));
+    let display = if local && *code != "1" {
+        if *trunk {
+            group_digits(&format!("0{}", nsn), 4)
+        } else {
+            group_digits(&nsn, 4)
+        }
+    } else {
+        format!("+{} {}", code, group_digits(&nsn, 3))
+    };
+
+    Ok(ParsedPhone {
+        e164: format!("+{}{}", code, nsn),
+        display,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_number_with_trunk_prefix() {
+        let p = parse_phone("0555 12-34-56", "DZ").unwrap();
+        assert_eq!(p.e164, "+213555123456");
+        assert_eq!(p.display, "0555 12 34 56");
+        assert_eq!(
+            parse_phone("٠٥٥٥١٢٣٤٥٦", "DZ").unwrap().e164,
+            "+213555123456"
+        );
+    }
+
+    #[test]
+    fn international_prefixes() {
+        assert_eq!(
+            parse_phone("00213555123456", "FR").unwrap().e164,
+            "+213555123456"
+        );
+        let p = parse_phone("+33 (0)6 12 34 56 78", "DZ").unwrap();
+        assert_eq!(p.e164, "+33612345678");
+        assert_eq!(p.display, "+33 612 34 56 78");
+    }
+
+    #[test]
+    fn variable_length_plans() {
+        assert!(parse_phone("+49 30 1234567", "DZ").is_ok());
+        assert!(parse_phone("+49 1512 3456789", "DZ").is_ok());
+        assert!(parse_phone("+44 1632 960961", "DZ").is_ok());
+        assert!(parse_phone("+44 20 7946 0958", "DZ").is_ok());
+        assert!(parse_phone("+213 555", "DZ").is_err());
+    }
+
+    #[test]
+    fn italian_landline_keeps_leading_zero() {
+        assert_eq!(
+            parse_phone("+39 06 1234 5678", "DZ").unwrap().e164,
+            "+390612345678"
+        );
+        assert_eq!(
+            parse_phone("06 1234 5678", "IT").unwrap().e164,
+            "+390612345678"
+        );
+        assert_eq!(
+            parse_phone("+39 312 345 6789", "DZ").unwrap().e164,
+            "+393123456789"
+        );
+    }
+
+    #[test]
+    fn nanp_is_one_plan() {
+        let us = parse_phone("+1 415 555 0100", "US").unwrap();
+        let ca = parse_phone("+1 415 555 0100", "CA").unwrap();
+        assert_eq!(us, ca);
+        assert_eq!(us.display, "+1 415 55 50 100");
+        assert_eq!(
+            parse_phone("1 415 555 0100", "CA").unwrap().e164,
+            "+14155550100"
+        );
+        assert!(is_known_country("ca"));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse_phone("", "DZ").is_err());
+        assert!(parse_phone("05x5", "DZ").is_err());
+        assert!(parse_phone("+999 123456", "DZ").is_err());
+        assert!(parse_phone("0555123456", "XX").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Payment terms a customer can be given.
+pub const PAYMENT_TERMS: &[&str] = &[
+    "immediate",
+    "net_15",
+    "net_30",
+    "net_60",
+    "end_of_month",
+    "custom", // `days` after the invoice date
+];
+
+pub const DEFAULT_PAYMENT_TERMS: &str = "immediate";
+
+pub fn is_known_terms(terms: &str) -> bool {
+    PAYMENT_TERMS.contains(&terms)
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    date.get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or_else(|| format!("التاريخ غير صالح: {}", date))
+}
+
+/// Due date ("YYYY-MM-DD") of an invoice dated `date` under `terms`.
+/// `days` is only used by "custom" terms.
+pub fn due_date(date: &str, terms: &str, days: Option<i32>) -> Result<String, String> {
+    let date = parse_date(date)?;
+
+    let due = match terms {
+        "immediate" => date,
+        "net_15" => date + Duration::days(15),
+        "net_30" => date + Duration::days(30),
+        "net_60" => date + Duration::days(60),
+        "end_of_month" => {
+            let (year, month) = if date.month() == 12 {
+                (date.year() + 1, 1)
+            } else {
+                (date.year(), date.month() + 1)
+            };
+            NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(date) - Duration::days(1)
+        }
+        "custom" => match days {
+            Some(d) if d >= 0 => date + Duration::days(d as i64),
+            _ => return Err("عدد أيام الأجل مطلوب".into()),
+        },
+        _ => return Err(format!("شروط الدفع غير معروفة: {}", terms)),
+    };
+
+    Ok(due.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn net_terms_add_days() {
+        assert_eq!(
+            due_date("2026-01-20", "immediate", None).unwrap(),
+            "2026-01-20"
+        );
+        assert_eq!(
+            due_date("2026-01-20", "net_15", None).unwrap(),
+            "2026-02-04"
+        );
+        assert_eq!(
+            due_date("2026-01-20 10:30:00", "net_30", None).unwrap(),
+            "2026-02-19"
+        );
+        assert_eq!(
+            due_date("2026-12-15", "net_60", None).unwrap(),
+            "2027-02-13"
+        );
+    }
+
+    #[test]
+    fn end_of_month() {
+        assert_eq!(
+            due_date("2026-02-03", "end_of_month", None).unwrap(),
+            "2026-02-28"
+        );
+        assert_eq!(
+            due_date("2028-02-29", "end_of_month", None).unwrap(),
+            "2028-02-29"
+        );
+        assert_eq!(
+            due_date("2026-12-31", "end_of_month", None).unwrap(),
+            "2026-12-31"
+        );
+    }
+
+    #[test]
+    fn custom_terms_need_days() {
+        assert_eq!(
+            due_date("2026-03-01", "custom", Some(45)).unwrap(),
+            "2026-04-15"
+        );
+        assert!(due_date("2026-03-01", "custom", None).is_err());
+        assert!(due_date("2026-03-01", "custom", Some(-1)).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_terms_and_bad_dates() {
+        assert!(due_date("2026-03-01", "net_90", None).is_err());
+        assert!(due_date("01/03/2026", "net_30", None).is_err());
+    }
+}
@@ -1,20 +1,59 @@
 import { invoke } from "@tauri-apps/api/core";
 import {
+  AgingBasis,
+  AgingReport,
+  AuditEntry,
+  CreditNote,
+  CreditNoteLine,
+  CreditStatus,
+  Currency,
+  CurrencyBalance,
+  CustomField,
   Customer,
+  CustomerAddress,
+  CustomerContact,
+  DeletedItem,
+  DeliveryNote,
+  DeliveryNoteLine,
+  DeliveryStatus,
+  DiscountTotal,
+  DuplicateGroup,
+  ExchangeRate,
   Invoice,
   InvoiceItem,
+  OverdueInvoice,
   Payment,
+  PaymentTerms,
+  PhoneNormalizationReport,
   Product,
+  Quote,
+  QuoteStatus,
+  RecurringInvoice,
+  RecurringInvoiceRun,
   ReportSummary,
+  SearchHit,
+  StockMovement,
+  StockValuation,
+  Tag,
+  TagDebtTotal,
+  TaxRate,
+  TaxSummary,
+  Unit,
 } from "./types";
 
 // ================== CUSTOMERS ==================
 export const getAllCustomers = async (
   searchQuery?: string,
   page?: number,
-  pageSize?: number
+  pageSize?: number,
+  tagIds?: number[]
 ): Promise<Customer[]> => {
-  return await invoke("get_all_customers", { searchQuery, page, pageSize });
+  return await invoke("get_all_customers", {
+    searchQuery,
+    page,
+    pageSize,
+    tagIds,
+  });
 };
 
 export const getCustomerById = async (id: number): Promise<Customer> => {
@@ -22,24 +61,229 @@ export const getCustomerById = async (id: number): Promise<Customer> => {
 };
 
 export const createCustomer = async (customer: any): Promise<Customer> => {
-  return await invoke("create_customer", customer);
+  return await invoke("create_customer", {
+    ...customer,
+    customFields: customer.custom_fields,
+  });
 };
 
 export const updateCustomer = async (id: number, customer: any) => {
-  return await invoke("update_customer", { id, ...customer });
+  return await invoke("update_customer", {
+    id,
+    ...customer,
+    customFields: customer.custom_fields,
+  });
+};
+
+export const deleteCustomer = async (id: number, force?: boolean) => {
+  return await invoke("delete_customer", { id, force });
+};
+
+// ================== CONTACTS & ADDRESSES ==================
+export const getCustomerContacts = async (
+  customerId: number
+): Promise<CustomerContact[]> => {
+  return await invoke("get_customer_contacts", { customerId });
+};
+
+export const createCustomerContact = async (
+  contact: CustomerContact
+): Promise<number> => {
+  return await invoke("create_customer_contact", { contact });
+};
+
+export const updateCustomerContact = async (
+  id: number,
+  contact: CustomerContact
+) => {
+  return await invoke("update_customer_contact", { id, contact });
+};
+
+export const deleteCustomerContact = async (id: number) => {
+  return await invoke("delete_customer_contact", { id });
+};
+
+export const getCustomerAddresses = async (
+  customerId: number,
+  kind?: CustomerAddress["kind"]
+): Promise<CustomerAddress[]> => {
+  return await invoke("get_customer_addresses", { customerId, kind });
+};
+
+export const createCustomerAddress = async (
+  address: CustomerAddress
+): Promise<number> => {
+  return await invoke("create_customer_address", { address });
+};
+
+export const updateCustomerAddress = async (
+  id: number,
+  address: CustomerAddress
+) => {
+  return await invoke("update_customer_address", { id, address });
+};
+
+export const deleteCustomerAddress = async (id: number) => {
+  return await invoke("delete_customer_address", { id });
+};
+
+export const normalizeCustomerPhones =
+  async (): Promise<PhoneNormalizationReport> => {
+    return await invoke("normalize_customer_phones");
+  };
+
+export const findDuplicateCustomers = async (
+  minScore?: number
+): Promise<DuplicateGroup[]> => {
+  return await invoke("find_duplicate_customers", { minScore });
+};
+
+export const mergeCustomers = async (keepId: number, mergeIds: number[]) => {
+  return await invoke("merge_customers", { keepId, mergeIds });
+};
+
+// ================== CREDIT LIMITS ==================
+export const setCustomerCreditLimit = async (
+  customerId: number,
+  creditLimit?: number,
+  maxDaysOverdue?: number
+) => {
+  return await invoke("set_customer_credit_limit", {
+    customerId,
+    creditLimit,
+    maxDaysOverdue,
+  });
+};
+
+export const getCustomerCreditStatus = async (
+  customerId: number
+): Promise<CreditStatus | null> => {
+  return await invoke("get_customer_credit_status", { customerId });
+};
+
+export const getCustomersOverLimit = async (): Promise<CreditStatus[]> => {
+  return await invoke("get_customers_over_limit");
+};
+
+// ================== PAYMENT TERMS ==================
+export const setCustomerPaymentTerms = async (
+  customerId: number,
+  paymentTerms?: PaymentTerms,
+  paymentTermsDays?: number
+) => {
+  return await invoke("set_customer_payment_terms", {
+    customerId,
+    paymentTerms,
+    paymentTermsDays,
+  });
+};
+
+// ================== CUSTOM FIELDS ==================
+export const getCustomFields = async (): Promise<CustomField[]> => {
+  return await invoke("get_custom_fields");
+};
+
+export const createCustomField = async (field: CustomField): Promise<number> => {
+  return await invoke("create_custom_field", { field });
+};
+
+export const updateCustomField = async (id: number, field: CustomField) => {
+  return await invoke("update_custom_field", { id, field });
+};
+
+export const deleteCustomField = async (id: number) => {
+  return await invoke("delete_custom_field", { id });
+};
+
+// ================== TAGS ==================
+export const getTags = async (category?: string): Promise<Tag[]> => {
+  return await invoke("get_tags", { category });
+};
+
+export const createTag = async (tag: Tag): Promise<number> => {
+  return await invoke("create_tag", { tag });
 };
 
-export const deleteCustomer = async (id: number) => {
-  return await invoke("delete_customer", { id });
+export const updateTag = async (id: number, tag: Tag) => {
+  return await invoke("update_tag", { id, tag });
+};
+
+export const deleteTag = async (id: number) => {
+  return await invoke("delete_tag", { id });
+};
+
+export const getCustomerTags = async (customerId: number): Promise<Tag[]> => {
+  return await invoke("get_customer_tags", { customerId });
+};
+
+export const setCustomerTags = async (customerId: number, tagIds: number[]) => {
+  return await invoke("set_customer_tags", { customerId, tagIds });
+};
+
+// ================== TAXES ==================
+export const getTaxRates = async (): Promise<TaxRate[]> => {
+  return await invoke("get_tax_rates");
+};
+
+export const createTaxRate = async (taxRate: TaxRate): Promise<number> => {
+  return await invoke("create_tax_rate", { taxRate });
+};
+
+export const updateTaxRate = async (id: number, taxRate: TaxRate) => {
+  return await invoke("update_tax_rate", { id, taxRate });
+};
+
+export const deleteTaxRate = async (id: number) => {
+  return await invoke("delete_tax_rate", { id });
+};
+
+export const getTaxSummary = async (
+  fromDate: string,
+  toDate?: string,
+  customerId?: number,
+  tagIds?: number[]
+): Promise<TaxSummary[]> => {
+  return await invoke("get_tax_summary", {
+    fromDate,
+    toDate,
+    customerId,
+    tagIds,
+  });
+};
+
+export const getInvoiceTaxBreakdown = async (
+  invoiceId: number
+): Promise<TaxSummary[]> => {
+  return await invoke("get_invoice_tax_breakdown", { invoiceId });
+};
+
+// ================== DISCOUNTS ==================
+export const getDiscountsReport = async (
+  fromDate: string,
+  toDate: string | undefined,
+  groupBy: "salesperson" | "customer"
+): Promise<DiscountTotal[]> => {
+  return await invoke("get_discounts_report", { fromDate, toDate, groupBy });
 };
 
 // ================== INVOICES ==================
 export const getAllInvoices = async (
   searchQuery?: string,
   page?: number,
-  pageSize?: number
-): Promise<{ data: Invoice[]; total: number }> => {
-  return await invoke("get_all_invoices", { searchQuery, page, pageSize });
+  pageSize?: number,
+  tagIds?: number[]
+): Promise<{
+  data: Invoice[];
+  total: number;
+  subtotal: number;
+  tax_total: number;
+}> => {
+  return await invoke("get_all_invoices", {
+    searchQuery,
+    page,
+    pageSize,
+    tagIds,
+  });
 };
 
 export const getInvoiceById = async (id: number): Promise<Invoice> => {
@@ -52,12 +296,31 @@ export const getInvoicesByCustomerId = async (
   return await invoke("get_invoices_by_customer_id", { customerId });
 };
 
-export const createInvoice = async (invoice: any): Promise<number> => {
-  return await invoke("create_invoice", { invoice });
+export const createInvoice = async (
+  invoice: any,
+  overrideCreditLimit?: boolean,
+  overrideReason?: string
+): Promise<number> => {
+  return await invoke("create_invoice", {
+    invoice,
+    overrideCreditLimit,
+    overrideReason,
+  });
 };
 
-export const updateInvoice = async (id: number, invoice: any) => {
-  return await invoke("update_invoice", { id, ...invoice });
+// المسودات فقط؛ المجموع يُحسب من البنود، والمدفوع من الدفعات
+export const updateInvoice = async (
+  id: number,
+  invoice: Omit<Partial<Invoice>, "total" | "status" | "paid_amount">,
+  overrideCreditLimit?: boolean,
+  overrideReason?: string
+) => {
+  return await invoke("update_invoice", {
+    id,
+    ...invoice,
+    overrideCreditLimit,
+    overrideReason,
+  });
 };
 
 export const deleteInvoice = async (id: number) => {
@@ -66,9 +329,55 @@ export const deleteInvoice = async (id: number) => {
 
 export const getInvoices = async (
   fromDate: string,
-  toDate?: string
+  toDate?: string,
+  tagIds?: number[]
+): Promise<string> => {
+  return await invoke("generate_invoices_pdf", { fromDate, toDate, tagIds });
+};
+
+export const generateInvoicePdf = async (invoiceId: number): Promise<string> => {
+  return await invoke("generate_invoice_pdf", { invoiceId });
+};
+
+// إصدار الفاتورة بعد إعادة فحص الحد الائتماني على مجموعها النهائي، ولا تُعدل بعدها إلا بإشعار دائن
+export const finalizeInvoice = async (
+  id: number,
+  overrideCreditLimit?: boolean,
+  overrideReason?: string
 ): Promise<string> => {
-  return await invoke("generate_invoices_pdf", { fromDate, toDate });
+  return await invoke("finalize_invoice", {
+    id,
+    overrideCreditLimit,
+    overrideReason,
+  });
+};
+
+export const verifyInvoice = async (id: number): Promise<boolean> => {
+  return await invoke("verify_invoice", { id });
+};
+
+// إلغاء الفاتورة مع الاحتفاظ برقمها بدلاً من حذفها
+export const voidInvoice = async (id: number, reason: string) => {
+  return await invoke("void_invoice", { id, reason });
+};
+
+// نسخة مسودة من الفاتورة برقم جديد، اختيارياً لعميل آخر وبأسعار الكتالوج
+export const duplicateInvoice = async (
+  id: number,
+  newDate: string,
+  customerId?: number,
+  refreshPrices?: boolean,
+  overrideCreditLimit?: boolean,
+  overrideReason?: string
+): Promise<number> => {
+  return await invoke("duplicate_invoice", {
+    id,
+    newDate,
+    customerId,
+    refreshPrices,
+    overrideCreditLimit,
+    overrideReason,
+  });
 };
 
 // ================== INVOICE ITEMS ==================
@@ -87,6 +396,177 @@ export const deleteByInvoiceId = async (invoice_id: number) => {
   return await invoke("delete_invoice_items", { invoiceId: invoice_id });
 };
 
+// ================== CREDIT NOTES ==================
+export const createCreditNote = async (
+  invoiceId: number,
+  date?: string,
+  reason?: string,
+  lines?: CreditNoteLine[] // بدون بنود = إرجاع كل ما تبقى من الفاتورة
+): Promise<CreditNote> => {
+  return await invoke("create_credit_note", { invoiceId, date, reason, lines });
+};
+
+export const getCreditNotes = async (
+  customerId?: number,
+  invoiceId?: number
+): Promise<CreditNote[]> => {
+  return await invoke("get_credit_notes", { customerId, invoiceId });
+};
+
+export const getCreditNote = async (id: number): Promise<CreditNote> => {
+  return await invoke("get_credit_note", { id });
+};
+
+export const generateCreditNotePdf = async (
+  creditNoteId: number
+): Promise<string> => {
+  return await invoke("generate_credit_note_pdf", { creditNoteId });
+};
+
+// ================== DELIVERY NOTES ==================
+export const createDeliveryNote = async (
+  invoiceId: number,
+  date?: string,
+  notes?: string,
+  lines?: DeliveryNoteLine[] // بدون بنود = تسليم كل ما تبقى من الفاتورة
+): Promise<DeliveryNote> => {
+  return await invoke("create_delivery_note", { invoiceId, date, notes, lines });
+};
+
+export const getDeliveryNotes = async (
+  customerId?: number,
+  invoiceId?: number
+): Promise<DeliveryNote[]> => {
+  return await invoke("get_delivery_notes", { customerId, invoiceId });
+};
+
+export const getDeliveryNote = async (id: number): Promise<DeliveryNote> => {
+  return await invoke("get_delivery_note", { id });
+};
+
+export const getInvoiceDeliveryStatus = async (
+  invoiceId: number
+): Promise<DeliveryStatus[]> => {
+  return await invoke("get_invoice_delivery_status", { invoiceId });
+};
+
+export const deleteDeliveryNote = async (id: number): Promise<void> => {
+  return await invoke("delete_delivery_note", { id });
+};
+
+export const generateDeliveryNotePdf = async (
+  deliveryNoteId: number
+): Promise<string> => {
+  return await invoke("generate_delivery_note_pdf", { deliveryNoteId });
+};
+
+// ================== RECURRING INVOICES ==================
+// خطأ الإنشاء التلقائي للفواتير الدورية عند بدء التشغيل، ينتظر انتهاءه
+export const getRecurringStartupError = async (): Promise<string | null> => {
+  return await invoke("get_recurring_startup_error");
+};
+
+export const getRecurringInvoices = async (
+  customerId?: number
+): Promise<RecurringInvoice[]> => {
+  return await invoke("get_recurring_invoices", { customerId });
+};
+
+export const getRecurringInvoice = async (
+  id: number
+): Promise<RecurringInvoice> => {
+  return await invoke("get_recurring_invoice", { id });
+};
+
+export const createRecurringInvoice = async (
+  schedule: RecurringInvoice
+): Promise<RecurringInvoice> => {
+  return await invoke("create_recurring_invoice", { schedule });
+};
+
+export const updateRecurringInvoice = async (
+  id: number,
+  schedule: RecurringInvoice
+): Promise<RecurringInvoice> => {
+  return await invoke("update_recurring_invoice", { id, schedule });
+};
+
+export const setRecurringInvoiceActive = async (
+  id: number,
+  active: boolean
+): Promise<void> => {
+  return await invoke("set_recurring_invoice_active", { id, active });
+};
+
+export const deleteRecurringInvoice = async (id: number): Promise<void> => {
+  return await invoke("delete_recurring_invoice", { id });
+};
+
+export const getRecurringInvoiceRuns = async (
+  recurringInvoiceId: number
+): Promise<RecurringInvoiceRun[]> => {
+  return await invoke("get_recurring_invoice_runs", { recurringInvoiceId });
+};
+
+// يُنشئ كل الفواتير المستحقة حتى اليوم (يُنفذ أيضاً عند تشغيل التطبيق)
+export const generateRecurringInvoices = async (): Promise<
+  RecurringInvoiceRun[]
+> => {
+  return await invoke("generate_recurring_invoices");
+};
+
+// ================== QUOTES ==================
+export const getQuotes = async (
+  status?: QuoteStatus,
+  customerId?: number
+): Promise<Quote[]> => {
+  return await invoke("get_quotes", { status, customerId });
+};
+
+export const getQuote = async (id: number): Promise<Quote> => {
+  return await invoke("get_quote", { id });
+};
+
+export const createQuote = async (quote: Quote): Promise<Quote> => {
+  return await invoke("create_quote", { quote });
+};
+
+export const updateQuote = async (id: number, quote: Quote): Promise<Quote> => {
+  return await invoke("update_quote", { id, quote });
+};
+
+export const setQuoteStatus = async (
+  id: number,
+  status: QuoteStatus
+): Promise<void> => {
+  return await invoke("set_quote_status", { id, status });
+};
+
+export const deleteQuote = async (id: number): Promise<void> => {
+  return await invoke("delete_quote", { id });
+};
+
+// يُنشئ فاتورة مسودة من عرض السعر ويعيد رقمها التعريفي
+export const convertQuoteToInvoice = async (
+  id: number,
+  invoiceNumber?: string,
+  date?: string,
+  overrideCreditLimit?: boolean,
+  overrideReason?: string
+): Promise<number> => {
+  return await invoke("convert_quote_to_invoice", {
+    id,
+    invoiceNumber,
+    date,
+    overrideCreditLimit,
+    overrideReason,
+  });
+};
+
+export const generateQuotePdf = async (quoteId: number): Promise<string> => {
+  return await invoke("generate_quote_pdf", { quoteId });
+};
+
 // ================== PRODUCTS ==================
 
 export const getProductsGrouped = async (
@@ -95,6 +575,112 @@ export const getProductsGrouped = async (
   return await invoke("get_products_grouped", { searchQuery });
 };
 
+export const getProducts = async (searchQuery?: string): Promise<Product[]> => {
+  return await invoke("get_products", { searchQuery });
+};
+
+export const createProduct = async (product: Product): Promise<number> => {
+  return await invoke("create_product", { product });
+};
+
+export const updateProduct = async (
+  id: number,
+  product: Product
+): Promise<void> => {
+  return await invoke("update_product", { id, product });
+};
+
+export const deleteProduct = async (id: number): Promise<void> => {
+  return await invoke("delete_product", { id });
+};
+
+// ================== UNITS ==================
+export const getUnits = async (): Promise<Unit[]> => {
+  return await invoke("get_units");
+};
+
+export const createUnit = async (unit: Unit): Promise<number> => {
+  return await invoke("create_unit", { unit });
+};
+
+export const updateUnit = async (id: number, unit: Unit): Promise<void> => {
+  return await invoke("update_unit", { id, unit });
+};
+
+// تُرفض إذا كانت الوحدة مستخدمة في مستندات أو وحدات أخرى
+export const deleteUnit = async (id: number): Promise<void> => {
+  return await invoke("delete_unit", { id });
+};
+
+// تحويل كمية بين وحدتين لهما نفس الوحدة الأساسية، مثل العلب إلى قطع
+export const convertQuantity = async (
+  quantity: number,
+  fromUnitId: number,
+  toUnitId: number
+): Promise<number> => {
+  return await invoke("convert_quantity", { quantity, fromUnitId, toUnitId });
+};
+
+// ================== STOCK ==================
+export const getStockMovements = async (
+  productId?: number
+): Promise<StockMovement[]> => {
+  return await invoke("get_stock_movements", { productId });
+};
+
+// يحدد المخزون الافتتاحي ويفعّل تتبع المخزون للمنتج
+export const setOpeningStock = async (
+  productId: number,
+  quantity: number,
+  date?: string
+): Promise<number> => {
+  return await invoke("set_opening_stock", { productId, quantity, date });
+};
+
+// كمية موجبة للإضافة وسالبة للخصم، مع ذكر السبب
+export const adjustStock = async (
+  productId: number,
+  quantity: number,
+  reason: string,
+  date?: string
+): Promise<number> => {
+  return await invoke("adjust_stock", { productId, quantity, reason, date });
+};
+
+export const getLowStockProducts = async (): Promise<Product[]> => {
+  return await invoke("get_low_stock_products");
+};
+
+export const getStockValuation = async (
+  asOfDate?: string
+): Promise<StockValuation> => {
+  return await invoke("get_stock_valuation", { asOfDate });
+};
+
+// ================== CURRENCIES ==================
+// العملات المدعومة مع تحديد العملة الأساسية
+export const getCurrencies = async (): Promise<Currency[]> => {
+  return await invoke("get_currencies");
+};
+
+// أسعار الصرف المسجلة، الأحدث أولاً
+export const getExchangeRates = async (
+  currency?: string
+): Promise<ExchangeRate[]> => {
+  return await invoke("get_exchange_rates", { currency });
+};
+
+// يستبدل سعر اليوم نفسه إن وُجد
+export const setExchangeRate = async (
+  exchangeRate: ExchangeRate
+): Promise<number> => {
+  return await invoke("set_exchange_rate", { exchangeRate });
+};
+
+export const deleteExchangeRate = async (id: number): Promise<void> => {
+  return await invoke("delete_exchange_rate", { id });
+};
+
 // ================== PAYMENTS ==================
 export const getAllPayments = async (
   searchQuery?: string,
@@ -131,15 +717,29 @@ export const deletePayment = async (id: number) => {
 // ================== DEBTS ==================
 export const getAllDebts = async (
   searchQuery?: string,
-  haveZero?: boolean
+  haveZero?: boolean,
+  tagIds?: number[]
 ): Promise<Customer[]> => {
-  return await invoke("get_all_debts", { searchQuery, haveZero });
+  return await invoke("get_all_debts", { searchQuery, haveZero, tagIds });
+};
+
+export const getDebtsByTag = async (
+  category?: string
+): Promise<TagDebtTotal[]> => {
+  return await invoke("get_debts_by_tag", { category });
 };
 
 export const getCustomerDebt = async (customerId: number) => {
   return await invoke("get_customer_debt", { customerId });
 };
 
+// الرصيد لكل عملة، مع ما يعادله بالعملة الأساسية
+export const getCurrencyBalances = async (
+  customerId?: number
+): Promise<CurrencyBalance[]> => {
+  return await invoke("get_currency_balances", { customerId });
+};
+
 export const getTransactions = async (
   customerId: number,
   customerName: string,
@@ -159,6 +759,103 @@ export const getTransactions = async (
 };
 
 // ================== REPORTS ==================
-export const getReportSummary = async (): Promise<ReportSummary> => {
-  return await invoke("get_report_summary");
+export const getReportSummary = async (
+  tagIds?: number[]
+): Promise<ReportSummary> => {
+  return await invoke("get_report_summary", { tagIds });
+};
+
+// ================== AGING ==================
+export const getAgingReport = async (
+  asOfDate?: string,
+  bucketEdges?: number[],
+  basis?: AgingBasis
+): Promise<AgingReport> => {
+  return await invoke("get_aging_report", { asOfDate, bucketEdges, basis });
+};
+
+export const generateAgingPdf = async (
+  asOfDate?: string,
+  bucketEdges?: number[],
+  basis?: AgingBasis
+): Promise<string> => {
+  return await invoke("generate_aging_pdf", { asOfDate, bucketEdges, basis });
+};
+
+export const exportAgingCsv = async (
+  asOfDate?: string,
+  bucketEdges?: number[],
+  basis?: AgingBasis
+): Promise<string> => {
+  return await invoke("export_aging_csv", { asOfDate, bucketEdges, basis });
+};
+
+export const getOverdueInvoices = async (
+  asOfDate?: string
+): Promise<OverdueInvoice[]> => {
+  return await invoke("get_overdue_invoices", { asOfDate });
+};
+
+// ================== AUDIT ==================
+export const getAuditLog = async (filters: {
+  entity?: string;
+  entityId?: number;
+  action?: string;
+  fromDate?: string;
+  toDate?: string;
+  page?: number;
+  pageSize?: number;
+}): Promise<AuditEntry[]> => {
+  return await invoke("get_audit_log", filters);
+};
+
+export const getInvoiceHistory = async (
+  invoiceId: number
+): Promise<AuditEntry[]> => {
+  return await invoke("get_invoice_history", { invoiceId });
+};
+
+export const getPaymentHistory = async (
+  paymentId: number
+): Promise<AuditEntry[]> => {
+  return await invoke("get_payment_history", { paymentId });
+};
+
+// ================== RECYCLE BIN ==================
+export const getDeletedItems = async (
+  entity?: DeletedItem["entity"]
+): Promise<DeletedItem[]> => {
+  return await invoke("get_deleted_items", { entity });
+};
+
+export const restoreDeletedItem = async (
+  entity: DeletedItem["entity"],
+  id: number
+) => {
+  return await invoke("restore_deleted_item", { entity, id });
+};
+
+export const purgeDeletedItem = async (
+  entity: DeletedItem["entity"],
+  id: number
+) => {
+  return await invoke("purge_deleted_item", { entity, id });
+};
+
+// ================== SEARCH ==================
+export const globalSearch = async (
+  query: string,
+  limit?: number
+): Promise<SearchHit[]> => {
+  return await invoke("global_search", { query, limit });
+};
+
+// ================== SETTINGS ==================
+export const getSettings = async (): Promise<Record<string, string>> => {
+  return await invoke("get_settings");
+};
+
+// الأجل المخصص "custom" يتطلب حفظ default_payment_terms_days أولاً
+export const setSetting = async (key: string, value: string) => {
+  return await invoke("set_setting", { key, value });
 };
@@ -6,11 +6,32 @@ export interface Customer {
   address: string;
   notes?: string;
   created_at?: string;
+  phone_e164?: string; // الصيغة الدولية للهاتف
+  credit_limit?: number; // الحد الائتماني، بدون حد إذا كان فارغاً
+  max_days_overdue?: number;
+  payment_terms?: PaymentTerms; // الشروط الافتراضية إذا كانت فارغة
+  payment_terms_days?: number; // عدد أيام الأجل للشروط المخصصة
+  custom_fields?: Record<string, string>; // قيم الحقول المخصصة حسب المعرف
   total_debt?: number;
   total_payments?: number;
   total_invoices?: number;
 }
 
+// نوع الخصم: نسبة مئوية أو مبلغ ثابت
+export type DiscountType = "percent" | "fixed";
+
+// شروط الدفع
+export type PaymentTerms =
+  | "immediate"
+  | "net_15"
+  | "net_30"
+  | "net_60"
+  | "end_of_month"
+  | "custom";
+
+// مراحل الفاتورة: مسودة ← مُصدرة ← (مدفوعة / ملغاة)
+export type InvoiceState = "draft" | "issued" | "paid" | "cancelled";
+
 // تعريفات الأنواع للفاتورة
 export interface Invoice {
   id?: number;
@@ -18,10 +39,28 @@ export interface Invoice {
   customer_id: number;
   customer_name?: string;
   customer_phone?: string;
-  customer_address?: string;
+  customer_address?: string; // عنوان الفوترة أو عنوان العميل
+  delivery_address?: string;
+  billing_address_id?: number;
+  delivery_address_id?: number;
   date: string;
+  due_date?: string; // تاريخ الاستحقاق، يُحسب من شروط الدفع إذا كان فارغاً
+  subtotal?: number; // المبلغ دون ضريبة بعد الخصم
+  tax_total?: number; // مجموع الضريبة
+  discount_type?: DiscountType; // خصم على الفاتورة كاملة
+  discount_value?: number;
+  discount_amount?: number; // يُحسب من البنود
+  salesperson?: string; // البائع
   total: number;
   status?: "paid" | "unpaid" | "partial";
+  state?: InvoiceState; // لا تُعدل إلا المسودات
+  issued_at?: string; // تاريخ إصدار الفاتورة
+  void_reason?: string; // سبب الإلغاء، الفاتورة الملغاة لا تدخل في الأرصدة
+  quote_id?: number; // عرض السعر الذي حُولت منه الفاتورة
+  currency?: string; // العملة الأساسية إذا كانت فارغة
+  exchange_rate?: number; // سعر صرف تاريخ الفاتورة إذا كان فارغاً
+  overdue?: boolean; // غير مسددة بعد تاريخ الاستحقاق
+  fully_delivered?: boolean; // سُلمت كل البنود بوصولات التسليم
   paid_amount?: number;
   remaining_amount?: number; // المتبقي
   created_at?: string;
@@ -34,13 +73,257 @@ export interface InvoiceItem {
   product_name: string;
   unit_price: number;
   quantity: number;
+  unit_id?: number; // وحدة المنتج إن لم تُحدد
+  unit_name?: string;
+  tax_rate?: number; // نسبة الضريبة %، النسبة الافتراضية إن لم تُحدد
+  price_includes_tax?: boolean; // سعر الوحدة يشمل الضريبة
+  discount_type?: DiscountType;
+  discount_value?: number;
+  discount_amount?: number; // يُحسب عند الحفظ
+  subtotal?: number; // يُحسب عند الحفظ
+  tax_amount?: number; // يُحسب عند الحفظ
   total: number;
 }
 
-export interface Product {
+// حالات عرض السعر، "expired" تُحسب تلقائياً بعد تاريخ الصلاحية
+export type QuoteStatus = "draft" | "sent" | "accepted" | "rejected" | "expired";
+
+// عروض الأسعار
+export interface Quote {
+  id?: number;
+  quote_number?: string; // "DEV-2026-0001"، يُحدد عند الإنشاء
+  customer_id: number;
+  customer_name?: string;
+  date: string;
+  valid_until?: string; // 30 يوماً بعد تاريخ العرض إذا كان فارغاً
+  status?: QuoteStatus;
+  notes?: string;
+  discount_type?: DiscountType;
+  discount_value?: number;
+  discount_amount?: number; // يُحسب من البنود
+  subtotal?: number;
+  tax_total?: number;
+  total?: number;
+  currency?: string; // العملة الأساسية إذا كانت فارغة
+  invoice_id?: number; // الفاتورة الناتجة عن التحويل
+  created_at?: string;
+  items: QuoteItem[]; // لا تُملأ في القوائم
+}
+
+export interface QuoteItem {
+  id?: number;
+  product_name: string;
+  unit_price: number;
+  quantity: number;
+  unit_id?: number;
+  unit_name?: string;
+  tax_rate?: number;
+  price_includes_tax?: boolean;
+  discount_type?: DiscountType;
+  discount_value?: number;
+  discount_amount?: number; // يُحسب عند الحفظ
+  subtotal?: number;
+  tax_amount?: number;
+  total?: number;
+}
+
+// دورية الفواتير المتكررة
+export type RecurringFrequency = "weekly" | "monthly" | "quarterly" | "yearly";
+
+// فاتورة دورية: تُنشأ منها فاتورة مسودة في كل فترة
+export interface RecurringInvoice {
+  id?: number;
+  customer_id: number;
+  customer_name?: string;
+  frequency: RecurringFrequency;
+  start_date: string;
+  end_date?: string; // بدون نهاية إذا كان فارغاً
+  next_run?: string; // تاريخ الفاتورة القادمة
+  active?: boolean;
+  notes?: string;
+  discount_type?: DiscountType;
+  discount_value?: number;
+  total?: number; // يُحسب من البنود
+  currency?: string; // تُحوّل بسعر صرف تاريخ كل فاتورة
+  last_error?: string; // سبب تعذر إنشاء آخر فاتورة مستحقة
+  created_at?: string;
+  items: RecurringInvoiceItem[]; // لا تُملأ في القوائم
+}
+
+export interface RecurringInvoiceItem {
+  id?: number;
+  product_name: string;
+  unit_price: number;
+  quantity: number;
+  unit_id?: number;
+  unit_name?: string;
+  tax_rate?: number;
+  price_includes_tax?: boolean;
+  discount_type?: DiscountType;
+  discount_value?: number;
+}
+
+// سجل الفواتير المنشأة من فاتورة دورية
+export interface RecurringInvoiceRun {
   id: number;
+  recurring_invoice_id: number;
+  invoice_id?: number;
+  invoice_number?: string;
+  run_date: string;
+  created_at?: string;
+}
+
+// الإشعارات الدائنة (مرتجعات على فاتورة)
+export interface CreditNote {
+  id?: number;
+  credit_note_number: string; // "AV-2026-0001"
+  invoice_id: number;
+  invoice_number?: string;
+  customer_id: number;
+  customer_name?: string;
+  date: string;
+  reason?: string;
+  subtotal: number;
+  tax_total: number;
+  total: number;
+  currency?: string; // عملة الفاتورة
+  created_at?: string;
+  items: CreditNoteItem[]; // لا تُملأ إلا عند جلب إشعار واحد
+}
+
+export interface CreditNoteItem {
+  id?: number;
+  invoice_item_id?: number;
+  product_name: string;
+  unit_price: number;
+  quantity: number;
+  unit_name?: string; // وحدة بند الفاتورة
+  tax_rate: number;
+  subtotal: number;
+  tax_amount: number;
+  total: number;
+}
+
+// كمية مرتجعة من بند في الفاتورة
+export interface CreditNoteLine {
+  invoice_item_id: number;
+  quantity: number;
+}
+
+// وصولات التسليم (كميات دون أسعار)
+export interface DeliveryNote {
+  id?: number;
+  delivery_note_number: string; // "BL-2026-0001"
+  invoice_id: number;
+  invoice_number?: string;
+  customer_id: number;
+  customer_name?: string;
+  date: string;
+  notes?: string;
+  created_at?: string;
+  items: DeliveryNoteItem[]; // لا تُملأ إلا عند جلب وصل واحد
+}
+
+export interface DeliveryNoteItem {
+  id?: number;
+  invoice_item_id?: number;
+  product_name: string;
+  quantity: number;
+  unit_name?: string; // وحدة بند الفاتورة
+}
+
+// كمية مسلمة من بند في الفاتورة
+export interface DeliveryNoteLine {
+  invoice_item_id: number;
+  quantity: number;
+}
+
+// الكمية المطلوبة مقابل المسلمة لكل بند
+export interface DeliveryStatus {
+  invoice_item_id: number;
+  product_name: string;
+  unit_id?: number;
+  unit_name?: string;
+  ordered_quantity: number;
+  delivered_quantity: number;
+  remaining_quantity: number;
+}
+
+// نسب الضريبة
+export interface TaxRate {
+  id?: number;
   name: string;
+  rate: number; // نسبة مئوية
+  is_default?: boolean;
+}
+
+// ملخص الضريبة حسب النسبة
+export interface TaxSummary {
+  tax_rate: number;
+  subtotal: number; // الوعاء الضريبي
+  tax_amount: number;
+  total: number;
+  invoice_count: number;
+}
+
+// منتجات الكتالوج
+export interface Product {
+  id?: number;
+  name: string;
+  unit_price?: number; // سعر البيع الحالي للوحدة
+  unit_id?: number;
+  unit_name?: string;
+  track_stock?: boolean; // تتبع المخزون
+  low_stock_threshold?: number; // حد التنبيه لانخفاض المخزون
+  cost_price?: number; // تكلفة الوحدة لتقييم المخزون
+  stock?: number; // المخزون الحالي، غير محدد إن لم يُتتبع
+  created_at?: string;
+}
+
+// حركات المخزون بوحدة المنتج؛ سالبة عند الخروج
+export type StockMovementKind = "opening" | "sale" | "return" | "void" | "adjustment";
+
+export interface StockMovement {
+  id?: number;
+  product_id: number;
+  product_name?: string;
+  date: string;
+  quantity: number;
+  kind: StockMovementKind;
+  invoice_id?: number;
+  invoice_number?: string;
+  credit_note_id?: number;
+  reason?: string;
+  created_at?: string;
+}
+
+export interface StockValuationRow {
+  product_id: number;
+  product_name: string;
+  unit_name?: string;
+  quantity: number;
+  cost_price?: number;
   unit_price?: number;
+  cost_value: number; // 0 بدون تكلفة
+  sale_value: number; // 0 بدون سعر بيع
+}
+
+// تقييم المخزون في تاريخ معين بالعملة الأساسية
+export interface StockValuation {
+  as_of_date: string;
+  rows: StockValuationRow[];
+  total_cost_value: number;
+  total_sale_value: number;
+}
+
+// وحدات القياس؛ الوحدة المشتقة مضاعف لوحدة أساسية (علبة = 12 قطعة)
+export interface Unit {
+  id?: number;
+  name: string;
+  decimals: number; // عدد المنازل العشرية المسموح بها في الكميات
+  base_unit_id?: number;
+  base_unit_name?: string;
+  factor: number; // عدد الوحدات الأساسية في وحدة واحدة
   created_at?: string;
 }
 
@@ -53,11 +336,39 @@ export interface Payment {
   invoice_id?: number;
   invoice_number?: string;
   amount: number;
+  currency?: string; // عملة الفاتورة، أو العملة الأساسية
+  exchange_rate?: number; // سعر صرف تاريخ الدفعة إذا كان فارغاً
   date: string;
   notes?: string;
   created_at?: string;
 }
 
+// تعريفات العملات وأسعار الصرف
+export interface Currency {
+  code: string; // "EUR"
+  symbol: string;
+  decimals: number;
+  is_base: boolean; // عملة الدفاتر
+}
+
+export interface ExchangeRate {
+  id?: number;
+  currency: string;
+  date: string; // يسري من هذا التاريخ حتى السعر التالي
+  rate: number; // قيمة وحدة واحدة من العملة بالعملة الأساسية
+  created_at?: string;
+}
+
+// رصيد المستندات الصادرة بعملة واحدة
+export interface CurrencyBalance {
+  currency: string;
+  total_invoices: number;
+  total_payments: number;
+  total_credits: number;
+  balance: number;
+  base_balance: number; // بالعملة الأساسية حسب أسعار المستندات
+}
+
 // تعريفات الأنواع للديون
 export interface Debt {
   customer_id: number;
@@ -73,6 +384,7 @@ export interface CustomerDebt {
   phone: string;
   total_debt: number;
   total_payments: number;
+  total_credits: number;
   total_invoices: number;
 }
 
@@ -80,26 +392,245 @@ export interface CustomerDebt {
 export interface ReportSummary {
   total_invoices: number;
   total_payments: number;
+  total_credits: number;
   total_debts: number;
   customer_count: number;
 }
 
-// تعريفات الأنواع للمعاملات (الفواتير والمدفوعات)
+// تعريفات الأنواع للمعاملات (الفواتير والمدفوعات والإشعارات الدائنة)
 export interface Transaction {
   record_id: number;
-  transaction_type: "invoice" | "payment";
+  transaction_type: "invoice" | "payment" | "credit_note";
   reference?: string;
   customer_id: number;
   customer_name: string;
   customer_phone: string;
   date: string;
-  amount: number;
+  amount: number; // بالعملة الأساسية
   created_at: string;
+  currency: string;
+  currency_amount: number; // بعملة المستند
+  running_balance: number; // رصيد العميل بعد هذه المعاملة
 }
 
 export interface TransactionsReport {
   data: Transaction[]; // جميع المعاملات (فواتير ومدفوعات)
   total_invoices: number; // مجموع الفواتير في الفترة
   total_payments: number; // مجموع المدفوعات في الفترة
-  remaining_total: number; // المتبقي (الفواتير - المدفوعات)
+  total_credits: number; // مجموع الإشعارات الدائنة في الفترة
+  remaining_total: number; // المتبقي (الفواتير - المدفوعات - الإشعارات الدائنة)
+  opening_balance: number; // الرصيد المرحل من قبل بداية الفترة
+  closing_balance: number; // الرصيد في نهاية الفترة
+}
+
+// تعريفات سجل التعديلات
+export interface AuditEntry {
+  id: number;
+  entity:
+    | "customer"
+    | "invoice"
+    | "payment"
+    | "customer_contact"
+    | "customer_address"
+    | "tag"
+    | "custom_field"
+    | "tax_rate"
+    | "credit_note"
+    | "quote"
+    | "delivery_note"
+    | "recurring_invoice"
+    | "product"
+    | "exchange_rate"
+    | "unit"
+    | "stock_movement";
+  entity_id: number;
+  action:
+    | "create"
+    | "update"
+    | "delete"
+    | "restore"
+    | "purge"
+    | "merge"
+    | "add_item"
+    | "delete_items"
+    | "credit_override"
+    | "update_tags"
+    | "finalize"
+    | "void"
+    | "convert";
+  before?: Record<string, any>;
+  after?: Record<string, any>;
+  user_name?: string;
+  created_at?: string;
+}
+
+// تعريفات سلة المحذوفات
+export interface DeletedItem {
+  entity: "customer" | "invoice" | "payment";
+  id: number;
+  label: string;
+  customer_name?: string;
+  amount?: number;
+  deleted_at: string;
+}
+
+// تعريفات نتائج البحث الشامل
+export interface SearchHit {
+  entity: "customer" | "invoice" | "item" | "payment";
+  record_id: number;
+  invoice_id?: number;
+  customer_id: number;
+  customer_name: string;
+  label: string;
+  snippet: string; // النص المطابق مع <mark>
+  score: number;
+}
+
+// تعريفات العملاء المكررين
+export interface DuplicateGroup {
+  customers: Customer[];
+  score: number; // 1 عند تطابق الهاتف
+}
+
+// أخطاء الأوامر: رسالة عامة أو أخطاء لكل حقل
+export type CommandError =
+  | { kind: "message"; message: string }
+  | { kind: "validation"; fields: Record<string, string> }
+  | {
+      kind: "credit_limit";
+      message: string;
+      mode: "block" | "warn";
+      new_balance: number;
+      status: CreditStatus;
+    };
+
+// حالة الحد الائتماني للعميل
+export interface CreditStatus {
+  customer_id: number;
+  customer_name: string;
+  balance: number;
+  credit_limit?: number;
+  max_days_overdue?: number;
+  days_overdue?: number; // أيام التأخر لأقدم فاتورة متأخرة
+  over_limit: boolean;
+  over_days: boolean;
+}
+
+// تقرير توحيد أرقام الهواتف
+export interface PhoneNormalizationReport {
+  updated: number;
+  unchanged: number;
+  invalid: {
+    customer_id: number;
+    customer_name: string;
+    phone: string;
+    error: string;
+  }[];
+}
+
+// تعريفات جهات الاتصال والعناوين للعميل
+export interface CustomerContact {
+  id?: number;
+  customer_id: number;
+  name: string;
+  role?: string;
+  phone?: string;
+  phone_e164?: string;
+  email?: string;
+  is_primary: boolean;
+  created_at?: string;
+}
+
+export interface CustomerAddress {
+  id?: number;
+  customer_id: number;
+  kind: "billing" | "delivery";
+  label?: string;
+  address: string;
+  is_primary: boolean;
+  created_at?: string;
+}
+
+// تعريفات الوسوم (التصنيفات) للعملاء
+export interface Tag {
+  id?: number;
+  name: string;
+  category?: string; // مثال: segment | region | salesperson
+  color?: string;
+  customer_count?: number;
+}
+
+// مجاميع الديون لكل وسم
+export interface TagDebtTotal {
+  tag_id: number;
+  tag_name: string;
+  category?: string;
+  customer_count: number;
+  total_invoices: number;
+  total_payments: number;
+  total_debt: number;
+}
+
+// تعريفات الحقول المخصصة للعملاء
+export interface CustomField {
+  id?: number;
+  key: string; // يستخدم في القوالب: {custom.key}
+  label: string;
+  field_type: "text" | "number" | "date" | "select";
+  options?: string[]; // خيارات الحقل من نوع select
+  required?: boolean;
+  position?: number;
+}
+
+// تقرير أعمار الديون
+// أساس التعمير: تاريخ الفاتورة أو تاريخ الاستحقاق
+export type AgingBasis = "invoice_date" | "due_date";
+
+export interface AgingBucket {
+  label: string; // current | 1-30 | 31-60 | 61-90 | 90+
+  from_days: number;
+  to_days?: number;
+}
+
+export interface AgingRow {
+  customer_id: number;
+  customer_name: string;
+  customer_phone: string;
+  amounts: number[]; // مبلغ لكل فترة
+  total: number;
+}
+
+export interface AgingReport {
+  as_of_date: string;
+  basis: AgingBasis;
+  buckets: AgingBucket[];
+  rows: AgingRow[];
+  totals: number[]; // مجموع كل فترة
+  total: number;
+}
+
+// فاتورة متأخرة عن السداد
+export interface OverdueInvoice {
+  id: number;
+  invoice_number: string;
+  customer_id: number;
+  customer_name: string;
+  customer_phone: string;
+  date: string;
+  due_date: string;
+  total: number; // بالعملة الأساسية
+  outstanding: number; // المتبقي بعد توزيع المدفوعات
+  days_overdue: number;
+}
+
+// تقرير الخصومات حسب البائع أو العميل
+export interface DiscountTotal {
+  customer_id?: number;
+  salesperson?: string;
+  name: string;
+  invoice_count: number;
+  gross_amount: number; // المبلغ قبل أي خصم
+  line_discounts: number; // خصومات البنود
+  invoice_discounts: number; // خصومات الفواتير
+  total_discount: number;
 }